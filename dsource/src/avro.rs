@@ -0,0 +1,634 @@
+//! Avro object container file data source implementation.
+//!
+//! The top-level record of the writer schema becomes the table schema. Nested
+//! records are flattened into dotted column names, as for JSON, and unions of
+//! `null` with one other type are read as that type with nulls. Logical types
+//! are read as their underlying values: dates as days since the epoch, times
+//! and timestamps as integers in their declared unit, UUIDs as strings and
+//! decimals as `Float64`. Arrays and maps are read as JSON text, as JSON
+//! sources read arrays. Records that contain themselves cannot be flattened
+//! and are rejected.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use apache_avro::schema::{Name, RecordField, Schema as AvroSchema};
+use apache_avro::types::Value;
+use apache_avro::{Decimal, Reader};
+use arrow::array::{
+    ArrayRef, BinaryBuilder, BooleanBuilder, Float32Builder, Float64Builder, Int32Builder,
+    Int64Builder, StringBuilder,
+};
+use arrow::datatypes::Schema as ArrowSchema;
+use arrow::record_batch::RecordBatch as ArrowRecordBatch;
+use dbms_dtype::{DataType, Field, RecordBatch, Schema};
+
+use crate::{BatchIterator, DataSource, resolve_projection};
+
+/// How the values of an Avro leaf type are read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Boolean,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    Utf8,
+    Binary,
+    /// An array or map, read as JSON text.
+    Json,
+    /// A decimal with the given scale, read as `Float64`.
+    Decimal(usize),
+}
+
+impl Kind {
+    fn dtype(&self) -> DataType {
+        match self {
+            Self::Boolean => DataType::Boolean,
+            Self::Int32 => DataType::Int32,
+            Self::Int64 => DataType::Int64,
+            Self::Float32 => DataType::Float32,
+            Self::Float64 | Self::Decimal(_) => DataType::Float64,
+            Self::Utf8 | Self::Json => DataType::Utf8,
+            Self::Binary => DataType::Binary,
+        }
+    }
+}
+
+/// A column of the table, found at `path` in each record.
+#[derive(Debug, Clone)]
+struct AvroColumn {
+    name: String,
+    /// Field index at each level of nested records.
+    path: Vec<usize>,
+    kind: Kind,
+}
+
+/// Flattens the fields of an Avro record schema into columns.
+fn columns(schema: &AvroSchema) -> Result<Vec<AvroColumn>, String> {
+    let AvroSchema::Record(record) = schema else {
+        return Err(format!(
+            "expected an Avro record schema, found {:?}",
+            schema
+        ));
+    };
+    let mut columns = Vec::new();
+    let mut names = HashMap::from([(record.name.clone(), schema.clone())]);
+    let mut parents = vec![record.name.clone()];
+    flatten(
+        &record.fields,
+        None,
+        &[],
+        &mut names,
+        &mut parents,
+        &mut columns,
+    )?;
+    Ok(columns)
+}
+
+/// Flattens the fields of a record. `parents` holds the names of the records
+/// being flattened, so that a record nested in itself is rejected rather than
+/// flattened forever.
+fn flatten(
+    fields: &[RecordField],
+    prefix: Option<&str>,
+    path: &[usize],
+    names: &mut HashMap<Name, AvroSchema>,
+    parents: &mut Vec<Name>,
+    columns: &mut Vec<AvroColumn>,
+) -> Result<(), String> {
+    for (i, field) in fields.iter().enumerate() {
+        let name = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, field.name),
+            None => field.name.clone(),
+        };
+        let mut path = path.to_vec();
+        path.push(i);
+
+        let schema =
+            resolve(&field.schema, names).map_err(|e| format!("column {}: {}", name, e))?;
+        if let AvroSchema::Record(record) = &schema {
+            if parents.contains(&record.name) {
+                return Err(format!(
+                    "column {}: unsupported recursive type {}",
+                    name, record.name
+                ));
+            }
+            parents.push(record.name.clone());
+            flatten(&record.fields, Some(&name), &path, names, parents, columns)?;
+            parents.pop();
+            continue;
+        }
+        let kind = kind(&schema).map_err(|e| format!("column {}: {}", name, e))?;
+        columns.push(AvroColumn { name, path, kind });
+    }
+    Ok(())
+}
+
+/// Unwraps nullable unions and named references, recording named types as
+/// they are defined.
+fn resolve(
+    schema: &AvroSchema,
+    names: &mut HashMap<Name, AvroSchema>,
+) -> Result<AvroSchema, String> {
+    match schema {
+        AvroSchema::Union(union) => {
+            let variants: Vec<_> = union
+                .variants()
+                .iter()
+                .filter(|variant| **variant != AvroSchema::Null)
+                .collect();
+            match variants.as_slice() {
+                [variant] if union.is_nullable() => resolve(variant, names),
+                _ => Err("only unions of null and one other type are supported".to_string()),
+            }
+        }
+        AvroSchema::Ref { name } => names
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("unknown type {}", name)),
+        AvroSchema::Record(record) => {
+            names.insert(record.name.clone(), schema.clone());
+            Ok(schema.clone())
+        }
+        AvroSchema::Enum(named) => {
+            names.insert(named.name.clone(), schema.clone());
+            Ok(schema.clone())
+        }
+        AvroSchema::Fixed(named) => {
+            names.insert(named.name.clone(), schema.clone());
+            Ok(schema.clone())
+        }
+        _ => Ok(schema.clone()),
+    }
+}
+
+/// Returns how a non-record Avro type is read.
+fn kind(schema: &AvroSchema) -> Result<Kind, String> {
+    Ok(match schema {
+        AvroSchema::Boolean => Kind::Boolean,
+        AvroSchema::Int | AvroSchema::Date | AvroSchema::TimeMillis => Kind::Int32,
+        AvroSchema::Long
+        | AvroSchema::TimeMicros
+        | AvroSchema::TimestampMillis
+        | AvroSchema::TimestampMicros
+        | AvroSchema::TimestampNanos
+        | AvroSchema::LocalTimestampMillis
+        | AvroSchema::LocalTimestampMicros
+        | AvroSchema::LocalTimestampNanos => Kind::Int64,
+        AvroSchema::Float => Kind::Float32,
+        AvroSchema::Double => Kind::Float64,
+        AvroSchema::String | AvroSchema::Enum(_) | AvroSchema::Uuid | AvroSchema::BigDecimal => {
+            Kind::Utf8
+        }
+        AvroSchema::Bytes | AvroSchema::Fixed(_) | AvroSchema::Duration => Kind::Binary,
+        AvroSchema::Decimal(decimal) => Kind::Decimal(decimal.scale),
+        AvroSchema::Array(_) | AvroSchema::Map(_) => Kind::Json,
+        other => return Err(format!("unsupported Avro type {:?}", other)),
+    })
+}
+
+/// Returns the value at `path` in a record, or `None` if it or an enclosing
+/// record is null.
+fn lookup<'a>(mut value: &'a Value, path: &[usize]) -> Option<&'a Value> {
+    for &i in path {
+        if let Value::Union(_, inner) = value {
+            value = inner;
+        }
+        match value {
+            Value::Record(fields) => value = &fields.get(i)?.1,
+            _ => return None,
+        }
+    }
+    if let Value::Union(_, inner) = value {
+        value = inner;
+    }
+    Some(value).filter(|value| **value != Value::Null)
+}
+
+/// Converts a two's complement big-endian unscaled decimal to a float.
+fn decimal_to_f64(decimal: &Decimal, scale: usize) -> Result<f64, String> {
+    let bytes = Vec::<u8>::try_from(decimal).map_err(|e| e.to_string())?;
+    if bytes.len() > 16 {
+        return Err("decimal wider than 128 bits".to_string());
+    }
+    let fill = if bytes.first().is_some_and(|b| b & 0x80 != 0) {
+        0xff
+    } else {
+        0
+    };
+    let mut buf = [fill; 16];
+    buf[16 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(i128::from_be_bytes(buf) as f64 / 10f64.powi(scale as i32))
+}
+
+/// Accumulates the values of one column for a batch.
+enum ColumnBuilder {
+    Boolean(BooleanBuilder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float32(Float32Builder),
+    Float64(Float64Builder),
+    Utf8(StringBuilder),
+    Binary(BinaryBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(kind: Kind) -> Self {
+        match kind {
+            Kind::Boolean => Self::Boolean(BooleanBuilder::new()),
+            Kind::Int32 => Self::Int32(Int32Builder::new()),
+            Kind::Int64 => Self::Int64(Int64Builder::new()),
+            Kind::Float32 => Self::Float32(Float32Builder::new()),
+            Kind::Float64 | Kind::Decimal(_) => Self::Float64(Float64Builder::new()),
+            Kind::Utf8 | Kind::Json => Self::Utf8(StringBuilder::new()),
+            Kind::Binary => Self::Binary(BinaryBuilder::new()),
+        }
+    }
+
+    /// Appends a value, or null if `value` is `None`.
+    fn append(&mut self, kind: Kind, value: Option<&Value>) -> Result<(), String> {
+        let mismatch = |value: &Value| format!("unexpected Avro value {:?}", value);
+        match (self, value) {
+            (Self::Boolean(b), None) => b.append_null(),
+            (Self::Int32(b), None) => b.append_null(),
+            (Self::Int64(b), None) => b.append_null(),
+            (Self::Float32(b), None) => b.append_null(),
+            (Self::Float64(b), None) => b.append_null(),
+            (Self::Utf8(b), None) => b.append_null(),
+            (Self::Binary(b), None) => b.append_null(),
+            (Self::Boolean(b), Some(Value::Boolean(v))) => b.append_value(*v),
+            (Self::Int32(b), Some(Value::Int(v) | Value::Date(v) | Value::TimeMillis(v))) => {
+                b.append_value(*v)
+            }
+            (
+                Self::Int64(b),
+                Some(
+                    Value::Long(v)
+                    | Value::TimeMicros(v)
+                    | Value::TimestampMillis(v)
+                    | Value::TimestampMicros(v)
+                    | Value::TimestampNanos(v)
+                    | Value::LocalTimestampMillis(v)
+                    | Value::LocalTimestampMicros(v)
+                    | Value::LocalTimestampNanos(v),
+                ),
+            ) => b.append_value(*v),
+            (Self::Float32(b), Some(Value::Float(v))) => b.append_value(*v),
+            (Self::Float64(b), Some(Value::Double(v))) => b.append_value(*v),
+            (Self::Float64(b), Some(Value::Decimal(v))) => {
+                let Kind::Decimal(scale) = kind else {
+                    return Err(mismatch(&Value::Decimal(v.clone())));
+                };
+                b.append_value(decimal_to_f64(v, scale)?)
+            }
+            (Self::Utf8(b), Some(Value::String(v) | Value::Enum(_, v))) => b.append_value(v),
+            (Self::Utf8(b), Some(Value::Uuid(v))) => b.append_value(v.to_string()),
+            (Self::Utf8(b), Some(Value::BigDecimal(v))) => b.append_value(v.to_string()),
+            (Self::Utf8(b), Some(value @ (Value::Array(_) | Value::Map(_)))) => {
+                let json = serde_json::Value::try_from(value.clone()).map_err(|e| e.to_string())?;
+                b.append_value(json.to_string())
+            }
+            (Self::Binary(b), Some(Value::Bytes(v) | Value::Fixed(_, v))) => b.append_value(v),
+            (Self::Binary(b), Some(Value::Duration(v))) => b.append_value(<[u8; 12]>::from(*v)),
+            (_, Some(value)) => return Err(mismatch(value)),
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Boolean(b) => Arc::new(b.finish()),
+            Self::Int32(b) => Arc::new(b.finish()),
+            Self::Int64(b) => Arc::new(b.finish()),
+            Self::Float32(b) => Arc::new(b.finish()),
+            Self::Float64(b) => Arc::new(b.finish()),
+            Self::Utf8(b) => Arc::new(b.finish()),
+            Self::Binary(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// A data source that reads from an Avro object container file.
+pub struct AvroDataSource {
+    path: PathBuf,
+    batch_size: usize,
+}
+
+impl AvroDataSource {
+    pub fn new(path: impl Into<PathBuf>, batch_size: usize) -> Self {
+        Self {
+            path: path.into(),
+            batch_size,
+        }
+    }
+
+    /// Opens the file, reading the writer schema from its header.
+    fn open(&self) -> Result<Reader<'static, BufReader<File>>, String> {
+        let file = File::open(&self.path).map_err(|e| e.to_string())?;
+        Reader::new(BufReader::new(file)).map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+}
+
+impl DataSource for AvroDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        let fields = columns(self.open()?.writer_schema())?
+            .into_iter()
+            .map(|column| Field::new(column.name, column.kind.dtype()))
+            .collect();
+        Ok(Schema::new(fields))
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        let reader = self.open()?;
+        let all = columns(reader.writer_schema())?;
+        let schema = Schema::new(
+            all.iter()
+                .map(|column| Field::new(column.name.clone(), column.kind.dtype()))
+                .collect(),
+        );
+        let indices = match projection {
+            Some(cols) => resolve_projection(&schema, cols)?,
+            None => (0..all.len()).collect(),
+        };
+
+        Ok(Box::new(AvroBatchIterator {
+            records: reader,
+            schema: Arc::new(schema.project(&indices).into()),
+            columns: indices.into_iter().map(|i| all[i].clone()).collect(),
+            batch_size: self.batch_size,
+        }))
+    }
+}
+
+struct AvroBatchIterator<R: Read> {
+    records: Reader<'static, R>,
+    schema: Arc<ArrowSchema>,
+    columns: Vec<AvroColumn>,
+    batch_size: usize,
+}
+
+impl<R: Read> AvroBatchIterator<R> {
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, String> {
+        let mut builders: Vec<_> = self
+            .columns
+            .iter()
+            .map(|column| ColumnBuilder::new(column.kind))
+            .collect();
+        let mut rows = 0;
+        for record in self.records.by_ref().take(self.batch_size) {
+            let record = record.map_err(|e| e.to_string())?;
+            for (column, builder) in self.columns.iter().zip(&mut builders) {
+                builder
+                    .append(column.kind, lookup(&record, &column.path))
+                    .map_err(|e| format!("column {}: {}", column.name, e))?;
+            }
+            rows += 1;
+        }
+        if rows == 0 {
+            return Ok(None);
+        }
+
+        let arrays = builders.iter_mut().map(ColumnBuilder::finish).collect();
+        let options = arrow::record_batch::RecordBatchOptions::new().with_row_count(Some(rows));
+        let batch = ArrowRecordBatch::try_new_with_options(self.schema.clone(), arrays, &options)
+            .map_err(|e| e.to_string())?;
+        batch.try_into().map(Some)
+    }
+}
+
+impl<R: Read> Iterator for AvroBatchIterator<R> {
+    type Item = Result<RecordBatch, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_batch().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance;
+    use apache_avro::Writer;
+    use dbms_dtype::Scalar;
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "event",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "name", "type": ["null", "string"]},
+            {"name": "kind", "type": {"type": "enum", "name": "kind", "symbols": ["click", "view"]}},
+            {"name": "day", "type": {"type": "int", "logicalType": "date"}},
+            {"name": "at", "type": {"type": "long", "logicalType": "timestamp-micros"}},
+            {"name": "price", "type": {
+                "type": "bytes", "logicalType": "decimal", "precision": 6, "scale": 2
+            }},
+            {"name": "user", "type": ["null", {
+                "type": "record",
+                "name": "user",
+                "fields": [
+                    {"name": "id", "type": "int"},
+                    {"name": "previous_kind", "type": "kind"}
+                ]
+            }]}
+        ]
+    }"#;
+
+    fn write_events(name: &str, count: i64) -> PathBuf {
+        let schema = AvroSchema::parse_str(SCHEMA).unwrap();
+        let mut writer = Writer::new(&schema, Vec::new());
+        for i in 0..count {
+            let name = match i % 2 {
+                0 => Value::Union(1, Box::new(Value::String(format!("e{}", i)))),
+                _ => Value::Union(0, Box::new(Value::Null)),
+            };
+            let user = match i % 2 {
+                0 => Value::Union(
+                    1,
+                    Box::new(Value::Record(vec![
+                        ("id".to_string(), Value::Int(i as i32 * 10)),
+                        (
+                            "previous_kind".to_string(),
+                            Value::Enum(1, "view".to_string()),
+                        ),
+                    ])),
+                ),
+                _ => Value::Union(0, Box::new(Value::Null)),
+            };
+            let record = Value::Record(vec![
+                ("id".to_string(), Value::Long(i)),
+                ("name".to_string(), name),
+                ("kind".to_string(), Value::Enum(0, "click".to_string())),
+                ("day".to_string(), Value::Date(19000 + i as i32)),
+                ("at".to_string(), Value::TimestampMicros(i * 1_000_000)),
+                (
+                    "price".to_string(),
+                    Value::Decimal(Decimal::from(vec![0x04, 0xd2])),
+                ),
+                ("user".to_string(), user),
+            ]);
+            writer.append(record).unwrap();
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("dbms-avro-{}-{}.avro", std::process::id(), name));
+        std::fs::write(&path, writer.into_inner().unwrap()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_schema() {
+        let path = write_events("schema", 1);
+        let schema = AvroDataSource::new(&path, 1024).schema().unwrap();
+        let fields: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name(), f.dtype().clone()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("id", DataType::Int64),
+                ("name", DataType::Utf8),
+                ("kind", DataType::Utf8),
+                ("day", DataType::Int32),
+                ("at", DataType::Int64),
+                ("price", DataType::Float64),
+                ("user.id", DataType::Int32),
+                ("user.previous_kind", DataType::Utf8),
+            ]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_scan() {
+        let path = write_events("scan", 5);
+        let source = AvroDataSource::new(&path, 2);
+        let batches: Vec<_> = source
+            .scan(Some(&["user.previous_kind", "price", "name"]))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[2].row_count(), 1);
+        let first = &batches[0];
+        assert_eq!(
+            first.field(0).get(0),
+            Scalar::Utf8(Some("view".to_string()))
+        );
+        assert_eq!(first.field(0).get(1), Scalar::Utf8(None));
+        assert_eq!(first.field(1).get(0), Scalar::Float64(Some(12.34)));
+        assert_eq!(first.field(2).get(0), Scalar::Utf8(Some("e0".to_string())));
+        assert_eq!(first.field(2).get(1), Scalar::Utf8(None));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_unsupported_union() {
+        let schema = AvroSchema::parse_str(
+            r#"{"type": "record", "name": "r", "fields": [
+                {"name": "v", "type": ["int", "string"]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            columns(&schema).unwrap_err(),
+            "column v: only unions of null and one other type are supported"
+        );
+    }
+
+    #[test]
+    fn test_recursive_record() {
+        let schema = AvroSchema::parse_str(
+            r#"{"type": "record", "name": "node", "fields": [
+                {"name": "value", "type": "int"},
+                {"name": "next", "type": ["null", "node"]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            columns(&schema).unwrap_err(),
+            "column next: unsupported recursive type node"
+        );
+
+        // A record may appear twice as long as it is not nested in itself
+        let schema = AvroSchema::parse_str(
+            r#"{"type": "record", "name": "edge", "fields": [
+                {"name": "from", "type": {"type": "record", "name": "point", "fields": [
+                    {"name": "x", "type": "int"}
+                ]}},
+                {"name": "to", "type": "point"}
+            ]}"#,
+        )
+        .unwrap();
+        let names: Vec<_> = columns(&schema)
+            .unwrap()
+            .into_iter()
+            .map(|column| column.name)
+            .collect();
+        assert_eq!(names, ["from.x", "to.x"]);
+    }
+
+    #[test]
+    fn test_arrays_and_maps() {
+        let schema = AvroSchema::parse_str(
+            r#"{"type": "record", "name": "r", "fields": [
+                {"name": "tags", "type": {"type": "array", "items": "string"}},
+                {"name": "counts", "type": ["null", {"type": "map", "values": "long"}]}
+            ]}"#,
+        )
+        .unwrap();
+        let mut writer = Writer::new(&schema, Vec::new());
+        writer
+            .append(Value::Record(vec![
+                (
+                    "tags".to_string(),
+                    Value::Array(vec![
+                        Value::String("a".to_string()),
+                        Value::String("b".to_string()),
+                    ]),
+                ),
+                (
+                    "counts".to_string(),
+                    Value::Union(
+                        1,
+                        Box::new(Value::Map(HashMap::from([(
+                            "x".to_string(),
+                            Value::Long(1),
+                        )]))),
+                    ),
+                ),
+            ]))
+            .unwrap();
+        let path =
+            std::env::temp_dir().join(format!("dbms-avro-{}-arrays.avro", std::process::id()));
+        std::fs::write(&path, writer.into_inner().unwrap()).unwrap();
+
+        let source = AvroDataSource::new(&path, 2);
+        let schema = source.schema().unwrap();
+        assert!(schema.fields().iter().all(|f| *f.dtype() == DataType::Utf8));
+        let batch = source.scan(None).unwrap().next().unwrap().unwrap();
+        assert_eq!(
+            batch.field(0).get(0),
+            Scalar::Utf8(Some(r#"["a","b"]"#.to_string()))
+        );
+        assert_eq!(
+            batch.field(1).get(0),
+            Scalar::Utf8(Some(r#"{"x":1}"#.to_string()))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_conformance() {
+        let path = write_events("conformance", 3);
+        conformance::check(&AvroDataSource::new(&path, 2));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
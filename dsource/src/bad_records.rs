@@ -0,0 +1,387 @@
+//! Handling of malformed records in text file sources.
+//!
+//! By default a record that cannot be parsed fails the scan. The other
+//! policies let the scan go on, counting every rejected record and keeping
+//! the line number and reason of the first [`MAX_KEPT_REJECTS`], which
+//! [`DataSource::scan_with_rejects`](crate::DataSource::scan_with_rejects)
+//! returns alongside the batches.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use arrow::array::{ArrayRef, BooleanArray, new_null_array};
+use arrow::compute::{concat_batches, filter_record_batch};
+use arrow::datatypes::{DataType as ArrowDataType, SchemaRef};
+use arrow::record_batch::{RecordBatch as ArrowRecordBatch, RecordBatchOptions};
+use dbms_dtype::{RecordBatch, Schema};
+use serde_json::json;
+
+/// Number of rejected records whose line and reason a scan keeps.
+pub const MAX_KEPT_REJECTS: usize = 100;
+
+/// What a scan does with a record that cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum BadRecordPolicy {
+    /// The scan fails with the line number and reason.
+    #[default]
+    Fail,
+    /// The record is left out.
+    Skip,
+    /// Fields that cannot be parsed, and missing fields, are read as null.
+    /// Records that cannot be split into fields at all are left out.
+    NullFill,
+    /// The record is left out and appended, with its line number and the
+    /// reason, as a JSON object on its own line of the given file.
+    Redirect(PathBuf),
+}
+
+/// A record rejected by a scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedRecord {
+    /// Line number of the record, counting from 1.
+    pub line: usize,
+    pub reason: String,
+}
+
+/// The records rejected by one scan so far, shared with the scan's batch
+/// iterator.
+#[derive(Debug, Clone, Default)]
+pub struct ScanRejects {
+    inner: Arc<Mutex<(usize, Vec<RejectedRecord>)>>,
+}
+
+impl ScanRejects {
+    /// Returns the number of rejected records.
+    pub fn count(&self) -> usize {
+        self.inner.lock().map(|inner| inner.0).unwrap_or_default()
+    }
+
+    /// Returns the first rejected records, in the order they were read.
+    pub fn records(&self) -> Vec<RejectedRecord> {
+        self.inner
+            .lock()
+            .map(|inner| inner.1.clone())
+            .unwrap_or_default()
+    }
+
+    fn add(&self, record: RejectedRecord) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.0 += 1;
+            if inner.1.len() < MAX_KEPT_REJECTS {
+                inner.1.push(record);
+            }
+        }
+    }
+}
+
+/// A record read from a text file, before its fields are decoded.
+pub(crate) struct TextRecord<R> {
+    pub record: R,
+    /// Why the record was already found to be malformed, if it was.
+    pub reason: Option<String>,
+    /// Whether the record is left out even when null-filling, because it
+    /// could not be split into fields.
+    pub unreadable: bool,
+}
+
+/// A record of a decoded batch found to be malformed. Its fields that failed
+/// to decode are null in the batch.
+pub(crate) struct MalformedRecord {
+    /// Index of the record in the batch.
+    pub row: usize,
+    /// Line number of the record, counting from 1.
+    pub line: usize,
+    /// The record as the file holds it, for the rejects file.
+    pub text: String,
+    pub reason: String,
+    /// Whether the record is left out even when null-filling.
+    pub unreadable: bool,
+}
+
+/// Decodes the records of a text file with the decoder that scans failing
+/// on malformed records use, so that values are read alike under every
+/// policy.
+pub(crate) trait RecordDecoder {
+    type Record;
+
+    /// Returns the line number of a record, counting from 1.
+    fn line(&self, record: &Self::Record) -> usize;
+
+    /// Returns a record as the file holds it, for the rejects file.
+    fn text(&self, record: &Self::Record) -> Result<String, String>;
+
+    /// Decodes records into a batch of the projected schema.
+    fn decode(&self, records: &[&Self::Record]) -> Result<ArrowRecordBatch, String>;
+
+    /// Decodes one projected column of a record on its own, into an array of
+    /// one value, or returns `None` if the record has no value for it. The
+    /// error is the reason the record is rejected.
+    fn decode_field(
+        &self,
+        record: &Self::Record,
+        column: usize,
+    ) -> Result<Option<ArrayRef>, String>;
+}
+
+/// Applies a bad-record policy during one scan.
+pub(crate) struct BadRecordHandler {
+    policy: BadRecordPolicy,
+    rejects: ScanRejects,
+    file: Option<File>,
+}
+
+impl BadRecordHandler {
+    pub(crate) fn new(policy: &BadRecordPolicy) -> Result<Self, String> {
+        let file = match policy {
+            BadRecordPolicy::Redirect(path) => Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?,
+            ),
+            _ => None,
+        };
+        Ok(Self {
+            policy: policy.clone(),
+            rejects: ScanRejects::default(),
+            file,
+        })
+    }
+
+    pub(crate) fn rejects(&self) -> ScanRejects {
+        self.rejects.clone()
+    }
+
+    /// Rejects a record, failing if the policy is to fail.
+    fn reject(&mut self, line: usize, text: &str, reason: String) -> Result<(), String> {
+        if self.policy == BadRecordPolicy::Fail {
+            return Err(format!("line {}: {}", line, reason));
+        }
+        if let Some(file) = &mut self.file {
+            let rejected = json!({ "line": line, "reason": reason, "record": text });
+            writeln!(file, "{}", rejected).map_err(|e| e.to_string())?;
+        }
+        self.rejects.add(RejectedRecord { line, reason });
+        Ok(())
+    }
+
+    /// Rejects the malformed records of a decoded batch. Returns the batch
+    /// without the records the policy leaves out, or `None` if every record
+    /// is left out.
+    pub(crate) fn reject_rows(
+        &mut self,
+        batch: ArrowRecordBatch,
+        malformed: Vec<MalformedRecord>,
+    ) -> Result<Option<RecordBatch>, String> {
+        let mut keep = vec![true; batch.num_rows()];
+        for record in malformed {
+            self.reject(record.line, &record.text, record.reason)?;
+            keep[record.row] = self.policy == BadRecordPolicy::NullFill && !record.unreadable;
+        }
+
+        let batch = match keep.iter().all(|&kept| kept) {
+            true => batch,
+            false => {
+                filter_record_batch(&batch, &BooleanArray::from(keep)).map_err(|e| e.to_string())?
+            }
+        };
+        if batch.num_rows() == 0 {
+            return Ok(None);
+        }
+        batch.try_into().map(Some)
+    }
+
+    /// Decodes records into a batch of `schema`, the projected schema,
+    /// rejecting those already found to be malformed and those that fail to
+    /// decode. Returns `None` if every record is left out.
+    pub(crate) fn build_batch<D: RecordDecoder>(
+        &mut self,
+        decoder: &D,
+        schema: &Schema,
+        records: Vec<TextRecord<D::Record>>,
+    ) -> Result<Option<RecordBatch>, String> {
+        if records.iter().all(|record| record.reason.is_none()) {
+            let all: Vec<_> = records.iter().map(|record| &record.record).collect();
+            if let Ok(batch) = decoder.decode(&all) {
+                return batch.try_into().map(Some);
+            }
+        }
+
+        // The records of a batch that fails to decode are decoded one at a
+        // time, and those that still fail field by field, to find the
+        // malformed ones and the fields to read as null
+        let arrow_schema: SchemaRef = Arc::new(schema.clone().into());
+        let mut rows = Vec::with_capacity(records.len());
+        let mut malformed = Vec::new();
+        for (
+            row,
+            TextRecord {
+                record,
+                mut reason,
+                unreadable,
+            },
+        ) in records.into_iter().enumerate()
+        {
+            let mut decode_error = None;
+            if reason.is_none() {
+                match decoder.decode(&[&record]) {
+                    Ok(decoded) => {
+                        rows.push(decoded);
+                        continue;
+                    }
+                    Err(e) => decode_error = Some(e),
+                }
+            }
+
+            let mut columns = Vec::with_capacity(schema.fields().len());
+            for (i, field) in schema.fields().iter().enumerate() {
+                let value = match decoder.decode_field(&record, i) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        reason.get_or_insert(e);
+                        None
+                    }
+                };
+                columns.push(value.unwrap_or_else(|| {
+                    new_null_array(&ArrowDataType::from(field.dtype().clone()), 1)
+                }));
+            }
+            let options = RecordBatchOptions::new().with_row_count(Some(1));
+            rows.push(
+                ArrowRecordBatch::try_new_with_options(arrow_schema.clone(), columns, &options)
+                    .map_err(|e| e.to_string())?,
+            );
+            malformed.push(MalformedRecord {
+                row,
+                line: decoder.line(&record),
+                text: decoder.text(&record)?,
+                reason: reason.or(decode_error).unwrap_or_default(),
+                unreadable,
+            });
+        }
+
+        let batch = concat_batches(&arrow_schema, &rows).map_err(|e| e.to_string())?;
+        self.reject_rows(batch, malformed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::StringArray;
+    use arrow::compute::{CastOptions, cast_with_options};
+    use dbms_dtype::{DataType, Field, Scalar};
+
+    /// Decodes records of text values by casting them strictly.
+    struct CastDecoder {
+        schema: Schema,
+    }
+
+    type Record = (usize, Vec<Option<&'static str>>);
+
+    impl CastDecoder {
+        fn cast(&self, column: usize, values: Vec<Option<&str>>) -> Result<ArrayRef, String> {
+            let dtype = ArrowDataType::from(self.schema.fields()[column].dtype().clone());
+            let options = CastOptions {
+                safe: false,
+                ..Default::default()
+            };
+            cast_with_options(&StringArray::from(values), &dtype, &options)
+                .map_err(|e| e.to_string())
+        }
+    }
+
+    impl RecordDecoder for CastDecoder {
+        type Record = Record;
+
+        fn line(&self, record: &Record) -> usize {
+            record.0
+        }
+
+        fn text(&self, record: &Record) -> Result<String, String> {
+            Ok(format!("{:?}", record.1))
+        }
+
+        fn decode(&self, records: &[&Record]) -> Result<ArrowRecordBatch, String> {
+            let columns = (0..self.schema.fields().len())
+                .map(|i| self.cast(i, records.iter().map(|r| r.1[i]).collect()))
+                .collect::<Result<_, _>>()?;
+            ArrowRecordBatch::try_new(Arc::new(self.schema.clone().into()), columns)
+                .map_err(|e| e.to_string())
+        }
+
+        fn decode_field(&self, record: &Record, column: usize) -> Result<Option<ArrayRef>, String> {
+            let name = self.schema.fields()[column].name();
+            match record.1[column] {
+                None => Ok(None),
+                Some(value) => self
+                    .cast(column, vec![Some(value)])
+                    .map(Some)
+                    .map_err(|_| format!("column {}: cannot parse '{}'", name, value)),
+            }
+        }
+    }
+
+    fn record(line: usize, values: &[Option<&'static str>]) -> TextRecord<Record> {
+        TextRecord {
+            record: (line, values.to_vec()),
+            reason: None,
+            unreadable: false,
+        }
+    }
+
+    #[test]
+    fn test_build_batch() {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64),
+            Field::new("b", DataType::Utf8),
+        ]);
+        let decoder = CastDecoder {
+            schema: schema.clone(),
+        };
+        let records = || {
+            vec![
+                record(1, &[Some("1"), Some("x")]),
+                record(2, &[Some("two"), None]),
+                record(3, &[None, Some("z")]),
+            ]
+        };
+
+        let mut handler = BadRecordHandler::new(&BadRecordPolicy::Fail).unwrap();
+        assert_eq!(
+            handler
+                .build_batch(&decoder, &schema, records())
+                .unwrap_err(),
+            "line 2: column a: cannot parse 'two'"
+        );
+
+        let mut handler = BadRecordHandler::new(&BadRecordPolicy::Skip).unwrap();
+        let batch = handler
+            .build_batch(&decoder, &schema, records())
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.row_count(), 2);
+        assert_eq!(batch.field(1).get(1), Scalar::Utf8(Some("z".to_string())));
+        assert_eq!(handler.rejects().count(), 1);
+        assert_eq!(handler.rejects().records()[0].line, 2);
+
+        // Unreadable records are left out even when null-filling
+        let mut records = records();
+        records.push(TextRecord {
+            reason: Some("not a record".to_string()),
+            unreadable: true,
+            ..record(4, &[None, None])
+        });
+        let mut handler = BadRecordHandler::new(&BadRecordPolicy::NullFill).unwrap();
+        let batch = handler
+            .build_batch(&decoder, &schema, records)
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.row_count(), 3);
+        assert_eq!(batch.field(0).get(1), Scalar::Int64(None));
+        assert_eq!(handler.rejects().count(), 2);
+    }
+}
@@ -0,0 +1,852 @@
+//! Catalog of named data sources.
+//!
+//! Tables are registered in schemas, and schemas in catalogs, so every table
+//! has a fully qualified name `catalog.schema.table`. Names with a schema but
+//! no catalog refer to the default catalog, and bare table names are looked
+//! up in each schema of the search path in turn.
+//!
+//! A catalog opened with [`Catalog::open`] keeps its schemas and external
+//! table definitions in a metadata file, rewritten after every change.
+//!
+//! Each catalog also has the virtual schemas `information_schema` and
+//! `system`, whose tables describe its contents.
+
+mod external;
+mod metadata;
+mod reference;
+mod system;
+
+pub use self::external::{ExternalTable, TableFormat};
+pub use self::reference::{SchemaReference, TableReference};
+pub use self::system::{FunctionDescription, FunctionKind, INFORMATION_SCHEMA, SYSTEM_SCHEMA};
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use dbms_dtype::Schema;
+
+use self::metadata::Definitions;
+use self::system::{SystemDataSource, SystemTable};
+use crate::{DataSource, Precision};
+
+/// Name of the catalog created with every [`Catalog`].
+pub const DEFAULT_CATALOG: &str = "default";
+
+/// Name of the schema created in the default catalog.
+pub const DEFAULT_SCHEMA: &str = "public";
+
+/// A data source that can be registered in a catalog and shared by threads.
+pub type TableSource = Arc<dyn DataSource + Send + Sync>;
+
+/// The schema and size of a registered table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableDescription {
+    pub reference: TableReference,
+    pub schema: Schema,
+    pub num_rows: Precision<usize>,
+}
+
+/// A registered table, with its definition if it is an external table.
+#[derive(Clone)]
+struct Table {
+    source: TableSource,
+    definition: Option<ExternalTable>,
+}
+
+type Tables = BTreeMap<String, Table>;
+
+struct State {
+    catalogs: BTreeMap<String, BTreeMap<String, Tables>>,
+    default_catalog: String,
+    /// Fully qualified schemas searched for bare table names, in order.
+    search_path: Vec<SchemaReference>,
+    /// Descriptions of the functions known to the query engine.
+    functions: Vec<FunctionDescription>,
+    /// Settings other than the default catalog and search path.
+    settings: BTreeMap<String, String>,
+}
+
+impl State {
+    /// Qualifies a schema name with the default catalog.
+    fn qualify_schema(&self, schema: SchemaReference) -> SchemaReference {
+        SchemaReference {
+            catalog: schema
+                .catalog
+                .or_else(|| Some(self.default_catalog.clone())),
+            schema: schema.schema,
+        }
+    }
+
+    /// Qualifies a table name for creation: bare names go into the first
+    /// schema of the search path.
+    fn qualify_table(&self, table: TableReference) -> Result<TableReference, String> {
+        let schema = match table.schema {
+            Some(schema) => self.qualify_schema(SchemaReference {
+                catalog: table.catalog,
+                schema,
+            }),
+            None => self
+                .search_path
+                .first()
+                .cloned()
+                .ok_or_else(|| format!("no schema in search path for table {}", table.table))?,
+        };
+        Ok(TableReference {
+            catalog: schema.catalog,
+            schema: Some(schema.schema),
+            table: table.table,
+        })
+    }
+
+    fn schema(&self, schema: &SchemaReference) -> Result<&Tables, String> {
+        self.catalogs
+            .get(schema.catalog.as_deref().unwrap_or_default())
+            .and_then(|schemas| schemas.get(&schema.schema))
+            .ok_or_else(|| format!("schema not found: {}", schema))
+    }
+
+    fn schema_mut(&mut self, schema: &SchemaReference) -> Result<&mut Tables, String> {
+        self.catalogs
+            .get_mut(schema.catalog.as_deref().unwrap_or_default())
+            .and_then(|schemas| schemas.get_mut(&schema.schema))
+            .ok_or_else(|| format!("schema not found: {}", schema))
+    }
+
+    /// Finds the fully qualified name of an existing table.
+    fn resolve(&self, table: TableReference) -> Result<TableReference, String> {
+        if table.schema.is_some() {
+            let qualified = self.qualify_table(table)?;
+            let schema = SchemaReference {
+                catalog: qualified.catalog.clone(),
+                schema: qualified.schema.clone().unwrap_or_default(),
+            };
+            if SystemTable::find(&schema.schema, &qualified.table).is_some() {
+                let catalog = schema.catalog.as_deref().unwrap_or_default();
+                return match self.catalogs.contains_key(catalog) {
+                    true => Ok(qualified),
+                    false => Err(format!("catalog not found: {}", catalog)),
+                };
+            }
+            return match self.schema(&schema)?.contains_key(&qualified.table) {
+                true => Ok(qualified),
+                false => Err(format!("table not found: {}", qualified)),
+            };
+        }
+
+        self.search_path
+            .iter()
+            .find(|schema| {
+                self.schema(schema)
+                    .is_ok_and(|tables| tables.contains_key(&table.table))
+            })
+            .map(|schema| TableReference {
+                catalog: schema.catalog.clone(),
+                schema: Some(schema.schema.clone()),
+                table: table.table.clone(),
+            })
+            .ok_or_else(|| format!("table not found: {}", table))
+    }
+
+    /// Returns every setting with its value, in order of name.
+    fn settings(&self) -> Vec<(String, String)> {
+        let search_path = self
+            .search_path
+            .iter()
+            .map(|schema| schema.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut settings = self.settings.clone();
+        settings.insert("default_catalog".to_string(), self.default_catalog.clone());
+        settings.insert("search_path".to_string(), search_path);
+        settings.into_iter().collect()
+    }
+
+    /// Returns every catalog and schema, and the external tables in them.
+    fn definitions(&self) -> Definitions {
+        self.catalogs
+            .iter()
+            .map(|(catalog, schemas)| {
+                let schemas = schemas
+                    .iter()
+                    .map(|(schema, tables)| {
+                        let tables = tables
+                            .iter()
+                            .filter_map(|(name, table)| {
+                                Some((name.clone(), table.definition.clone()?))
+                            })
+                            .collect();
+                        (schema.clone(), tables)
+                    })
+                    .collect();
+                (catalog.clone(), schemas)
+            })
+            .collect()
+    }
+}
+
+/// Splits a fully qualified table name into its schema and table name.
+fn split(table: &TableReference) -> (SchemaReference, &str) {
+    let schema = SchemaReference {
+        catalog: table.catalog.clone(),
+        schema: table.schema.clone().unwrap_or_default(),
+    };
+    (schema, &table.table)
+}
+
+/// A registry of data sources in catalog, schema and table namespaces.
+///
+/// All methods take `&self`, so a catalog can be shared across threads in an
+/// `Arc`.
+pub struct Catalog {
+    /// Shared with the virtual tables, which hold it weakly.
+    state: Arc<RwLock<State>>,
+    /// Metadata file that definitions are saved to, if persistent.
+    metadata: Option<PathBuf>,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Catalog {
+    /// Creates a catalog holding the empty schema `default.public`, which is
+    /// also the search path.
+    pub fn new() -> Self {
+        let public = SchemaReference {
+            catalog: Some(DEFAULT_CATALOG.to_string()),
+            schema: DEFAULT_SCHEMA.to_string(),
+        };
+        let schemas = BTreeMap::from([(DEFAULT_SCHEMA.to_string(), Tables::new())]);
+        let state = State {
+            catalogs: BTreeMap::from([(DEFAULT_CATALOG.to_string(), schemas)]),
+            default_catalog: DEFAULT_CATALOG.to_string(),
+            search_path: vec![public],
+            functions: Vec::new(),
+            settings: BTreeMap::new(),
+        };
+        Self {
+            state: Arc::new(RwLock::new(state)),
+            metadata: None,
+        }
+    }
+
+    /// Opens a catalog persisted in a metadata file, creating the file on
+    /// the first change if it does not exist. Catalogs, schemas and external
+    /// tables are restored from the file; other tables are not persisted.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let mut catalog = Self::new();
+        if let Some(definitions) = metadata::load(&path)? {
+            let mut state = catalog.write()?;
+            state.catalogs.clear();
+            for (name, schemas) in definitions {
+                let catalog = state.catalogs.entry(name).or_default();
+                for (name, tables) in schemas {
+                    let schema = catalog.entry(name).or_default();
+                    for (name, definition) in tables {
+                        let source = definition
+                            .source()
+                            .map_err(|e| format!("{}: table {}: {}", path.display(), name, e))?;
+                        let definition = Some(definition);
+                        schema.insert(name, Table { source, definition });
+                    }
+                }
+            }
+            state
+                .catalogs
+                .entry(DEFAULT_CATALOG.to_string())
+                .or_default();
+            drop(state);
+        }
+        catalog.metadata = Some(path);
+        Ok(catalog)
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, State>, String> {
+        self.state.read().map_err(|e| e.to_string())
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, State>, String> {
+        self.state.write().map_err(|e| e.to_string())
+    }
+
+    /// Applies a change and, if the catalog is persistent, saves the new
+    /// definitions. The change is undone if they cannot be saved.
+    fn update<T>(&self, change: impl FnOnce(&mut State) -> Result<T, String>) -> Result<T, String> {
+        let mut state = self.write()?;
+        let previous = state.catalogs.clone();
+        let result = change(&mut state)?;
+        if let Some(path) = &self.metadata
+            && let Err(e) = metadata::save(path, &state.definitions())
+        {
+            state.catalogs = previous;
+            return Err(e);
+        }
+        Ok(result)
+    }
+
+    /// Creates an empty catalog.
+    pub fn create_catalog(&self, name: &str) -> Result<(), String> {
+        self.update(|state| {
+            if state.catalogs.contains_key(name) {
+                return Err(format!("catalog already exists: {}", name));
+            }
+            state.catalogs.insert(name.to_string(), BTreeMap::new());
+            Ok(())
+        })
+    }
+
+    /// Drops a catalog. Unless `cascade` is set, it must have no schemas.
+    pub fn drop_catalog(&self, name: &str, cascade: bool) -> Result<(), String> {
+        self.update(|state| {
+            if name == state.default_catalog {
+                return Err(format!("cannot drop the default catalog {}", name));
+            }
+            let schemas = state
+                .catalogs
+                .get(name)
+                .ok_or_else(|| format!("catalog not found: {}", name))?;
+            if !schemas.is_empty() && !cascade {
+                return Err(format!("catalog {} is not empty", name));
+            }
+            state.catalogs.remove(name);
+            Ok(())
+        })
+    }
+
+    /// Creates an empty schema, in the default catalog unless qualified.
+    pub fn create_schema(&self, name: &str) -> Result<(), String> {
+        self.update(|state| {
+            let schema = state.qualify_schema(SchemaReference::parse(name)?);
+            if SystemTable::is_reserved(&schema.schema) {
+                return Err(format!("schema name is reserved: {}", schema.schema));
+            }
+            let catalog = schema.catalog.as_deref().unwrap_or_default();
+            let schemas = state
+                .catalogs
+                .get_mut(catalog)
+                .ok_or_else(|| format!("catalog not found: {}", catalog))?;
+            if schemas.contains_key(&schema.schema) {
+                return Err(format!("schema already exists: {}", schema));
+            }
+            schemas.insert(schema.schema, Tables::new());
+            Ok(())
+        })
+    }
+
+    /// Drops a schema. Unless `cascade` is set, it must have no tables.
+    pub fn drop_schema(&self, name: &str, cascade: bool) -> Result<(), String> {
+        self.update(|state| {
+            let schema = state.qualify_schema(SchemaReference::parse(name)?);
+            if !state.schema(&schema)?.is_empty() && !cascade {
+                return Err(format!("schema {} is not empty", schema));
+            }
+            let catalog = schema.catalog.as_deref().unwrap_or_default();
+            state
+                .catalogs
+                .get_mut(catalog)
+                .map(|schemas| schemas.remove(&schema.schema));
+            Ok(())
+        })
+    }
+
+    /// Registers a table, returning its fully qualified name. Bare names are
+    /// created in the first schema of the search path.
+    fn insert_table(&self, name: &str, table: Table) -> Result<TableReference, String> {
+        self.update(|state| {
+            let reference = state.qualify_table(TableReference::parse(name)?)?;
+            let (schema, table_name) = split(&reference);
+            let tables = state.schema_mut(&schema)?;
+            if tables.contains_key(table_name) {
+                return Err(format!("table already exists: {}", reference));
+            }
+            tables.insert(table_name.to_string(), table);
+            Ok(reference)
+        })
+    }
+
+    /// Registers a data source under a table name, returning its fully
+    /// qualified name. Bare names are created in the first schema of the
+    /// search path. The table is not persisted.
+    pub fn create_table(&self, name: &str, source: TableSource) -> Result<TableReference, String> {
+        let definition = None;
+        self.insert_table(name, Table { source, definition })
+    }
+
+    /// Registers a table over files, as `CREATE EXTERNAL TABLE` does,
+    /// returning its fully qualified name. The definition is persisted.
+    pub fn create_external_table(
+        &self,
+        name: &str,
+        definition: ExternalTable,
+    ) -> Result<TableReference, String> {
+        let source = definition.source()?;
+        let definition = Some(definition);
+        self.insert_table(name, Table { source, definition })
+    }
+
+    /// Removes a table, returning its data source.
+    pub fn drop_table(&self, name: &str) -> Result<TableSource, String> {
+        self.update(|state| {
+            let reference = state.resolve(TableReference::parse(name)?)?;
+            let (schema, table_name) = split(&reference);
+            if SystemTable::find(&schema.schema, table_name).is_some() {
+                return Err(format!("cannot drop system table {}", reference));
+            }
+            let table = state.schema_mut(&schema)?.remove(table_name);
+            Ok(table.expect("resolved table exists").source)
+        })
+    }
+
+    /// Returns the fully qualified name of an existing table.
+    pub fn resolve(&self, name: &str) -> Result<TableReference, String> {
+        self.read()?.resolve(TableReference::parse(name)?)
+    }
+
+    /// Returns the data source registered under a table name, or a virtual
+    /// table over the catalog's contents.
+    pub fn table(&self, name: &str) -> Result<TableSource, String> {
+        let state = self.read()?;
+        let table = state.resolve(TableReference::parse(name)?)?;
+        let (schema, table_name) = split(&table);
+        if let Some(system) = SystemTable::find(&schema.schema, table_name) {
+            let catalog = schema.catalog.as_deref().unwrap_or_default();
+            return Ok(Arc::new(SystemDataSource::new(
+                system,
+                catalog,
+                &self.state,
+            )));
+        }
+        Ok(state.schema(&schema)?[table_name].source.clone())
+    }
+
+    /// Returns the definition of a table, if it is an external table.
+    pub fn external_table(&self, name: &str) -> Result<Option<ExternalTable>, String> {
+        let state = self.read()?;
+        let table = state.resolve(TableReference::parse(name)?)?;
+        let (schema, table_name) = split(&table);
+        if SystemTable::find(&schema.schema, table_name).is_some() {
+            return Ok(None);
+        }
+        Ok(state.schema(&schema)?[table_name].definition.clone())
+    }
+
+    /// Describes the schema and size of a table.
+    pub fn describe(&self, name: &str) -> Result<TableDescription, String> {
+        let reference = self.resolve(name)?;
+        let source = self.table(&reference.to_string())?;
+        Ok(TableDescription {
+            reference,
+            schema: source.schema()?,
+            num_rows: source.statistics()?.num_rows,
+        })
+    }
+
+    /// Returns the names of all catalogs, in order.
+    pub fn catalog_names(&self) -> Result<Vec<String>, String> {
+        Ok(self.read()?.catalogs.keys().cloned().collect())
+    }
+
+    /// Returns the names of the schemas in a catalog, in order.
+    pub fn schema_names(&self, catalog: &str) -> Result<Vec<String>, String> {
+        let state = self.read()?;
+        let schemas = state
+            .catalogs
+            .get(catalog)
+            .ok_or_else(|| format!("catalog not found: {}", catalog))?;
+        Ok(schemas.keys().cloned().collect())
+    }
+
+    /// Returns the names of the tables in a schema, in order.
+    pub fn table_names(&self, schema: &str) -> Result<Vec<String>, String> {
+        let state = self.read()?;
+        let schema = state.qualify_schema(SchemaReference::parse(schema)?);
+        Ok(state.schema(&schema)?.keys().cloned().collect())
+    }
+
+    /// Returns the catalog that schema names without a catalog refer to.
+    pub fn default_catalog(&self) -> Result<String, String> {
+        Ok(self.read()?.default_catalog.clone())
+    }
+
+    /// Sets the catalog that schema names without a catalog refer to.
+    pub fn set_default_catalog(&self, name: &str) -> Result<(), String> {
+        let mut state = self.write()?;
+        if !state.catalogs.contains_key(name) {
+            return Err(format!("catalog not found: {}", name));
+        }
+        state.default_catalog = name.to_string();
+        Ok(())
+    }
+
+    /// Returns the fully qualified schemas searched for bare table names.
+    pub fn search_path(&self) -> Result<Vec<SchemaReference>, String> {
+        Ok(self.read()?.search_path.clone())
+    }
+
+    /// Sets the schemas searched for bare table names, in order. Schemas
+    /// without a catalog are qualified with the current default catalog.
+    /// Schemas that do not exist are skipped during lookup.
+    pub fn set_search_path(&self, schemas: &[&str]) -> Result<(), String> {
+        let mut state = self.write()?;
+        let search_path = schemas
+            .iter()
+            .map(|name| Ok(state.qualify_schema(SchemaReference::parse(name)?)))
+            .collect::<Result<_, String>>()?;
+        state.search_path = search_path;
+        Ok(())
+    }
+
+    /// Registers the description of a function, to be listed in
+    /// `system.functions`. Descriptions are not persisted.
+    pub fn register_function(&self, function: FunctionDescription) -> Result<(), String> {
+        let mut state = self.write()?;
+        if state
+            .functions
+            .iter()
+            .any(|f| f.name == function.name && f.signature == function.signature)
+        {
+            return Err(format!(
+                "function already registered: {} {}",
+                function.name, function.signature
+            ));
+        }
+        state.functions.push(function);
+        Ok(())
+    }
+
+    /// Returns the registered function descriptions, in registration order.
+    pub fn functions(&self) -> Result<Vec<FunctionDescription>, String> {
+        Ok(self.read()?.functions.clone())
+    }
+
+    /// Returns the value of a setting, if it is set.
+    pub fn setting(&self, name: &str) -> Result<Option<String>, String> {
+        let settings = self.read()?.settings();
+        Ok(settings
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v))
+    }
+
+    /// Sets a setting, to be listed in `system.settings`. Setting
+    /// `default_catalog` or `search_path`, a comma-separated list of schemas,
+    /// is the same as calling their setters. Settings are not persisted.
+    pub fn set_setting(&self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "default_catalog" => self.set_default_catalog(value),
+            "search_path" => {
+                let schemas: Vec<&str> = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .collect();
+                self.set_search_path(&schemas)
+            }
+            _ => {
+                self.write()?
+                    .settings
+                    .insert(name.to_string(), value.to_string());
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDataSource, conformance};
+    use dbms_dtype::{Column, DataType, Field, RecordBatch, Scalar};
+
+    fn source(rows: usize) -> TableSource {
+        let schema = Schema::new(vec![Field::new("id", DataType::Int64)]);
+        let batch = RecordBatch::new(
+            schema.clone(),
+            vec![Column::from_literal(Scalar::Int64(Some(1)), rows)],
+        );
+        Arc::new(InMemoryDataSource::new(schema, vec![batch]))
+    }
+
+    #[test]
+    fn test_create_and_drop() {
+        let catalog = Catalog::new();
+        let created = catalog.create_table("t", source(1)).unwrap();
+        assert_eq!(created, TableReference::full("default", "public", "t"));
+        assert_eq!(
+            catalog.create_table("public.t", source(1)).unwrap_err(),
+            "table already exists: default.public.t"
+        );
+
+        catalog.create_catalog("sales").unwrap();
+        catalog.create_schema("sales.eu").unwrap();
+        catalog.create_table("sales.eu.orders", source(2)).unwrap();
+        assert_eq!(catalog.catalog_names().unwrap(), ["default", "sales"]);
+        assert_eq!(catalog.schema_names("sales").unwrap(), ["eu"]);
+        assert_eq!(catalog.table_names("sales.eu").unwrap(), ["orders"]);
+        assert_eq!(
+            catalog.create_table("missing.t", source(1)).unwrap_err(),
+            "schema not found: default.missing"
+        );
+
+        assert_eq!(
+            catalog.drop_catalog("sales", false).unwrap_err(),
+            "catalog sales is not empty"
+        );
+        assert!(catalog.drop_schema("sales.eu", false).is_err());
+        catalog.drop_table("sales.eu.orders").unwrap();
+        catalog.drop_schema("sales.eu", false).unwrap();
+        catalog.drop_catalog("sales", false).unwrap();
+        assert_eq!(catalog.catalog_names().unwrap(), ["default"]);
+        assert!(catalog.drop_catalog("default", true).is_err());
+    }
+
+    #[test]
+    fn test_resolution() {
+        let catalog = Catalog::new();
+        catalog.create_schema("staging").unwrap();
+        catalog.create_table("t", source(1)).unwrap();
+        catalog.create_table("staging.t", source(2)).unwrap();
+        catalog.create_table("staging.u", source(3)).unwrap();
+
+        // Bare names resolve through the search path in order
+        assert_eq!(
+            catalog.resolve("t").unwrap().to_string(),
+            "default.public.t"
+        );
+        assert_eq!(catalog.resolve("u").unwrap_err(), "table not found: u");
+        catalog
+            .set_search_path(&["missing", "staging", "public"])
+            .unwrap();
+        assert_eq!(
+            catalog.resolve("t").unwrap().to_string(),
+            "default.staging.t"
+        );
+        assert_eq!(
+            catalog.resolve("u").unwrap().to_string(),
+            "default.staging.u"
+        );
+
+        // Qualified names bypass the search path
+        assert_eq!(
+            catalog.resolve("public.t").unwrap(),
+            TableReference::full("default", "public", "t")
+        );
+        assert_eq!(
+            catalog.resolve("default.public.u").unwrap_err(),
+            "table not found: default.public.u"
+        );
+
+        // Schema names without a catalog use the default catalog
+        catalog.create_catalog("other").unwrap();
+        catalog.create_schema("other.public").unwrap();
+        catalog.set_default_catalog("other").unwrap();
+        assert!(catalog.resolve("public.t").is_err());
+        assert!(catalog.resolve("t").is_ok());
+    }
+
+    #[test]
+    fn test_describe() {
+        let catalog = Catalog::new();
+        catalog.create_table("t", source(4)).unwrap();
+
+        let description = catalog.describe("t").unwrap();
+        assert_eq!(description.reference.to_string(), "default.public.t");
+        assert_eq!(description.schema.fields()[0].name(), "id");
+        assert_eq!(description.num_rows, Precision::Exact(4));
+
+        let batches = catalog.table("public.t").unwrap().scan(None).unwrap();
+        assert_eq!(batches.count(), 1);
+    }
+
+    #[test]
+    fn test_shared_across_threads() {
+        let catalog = Arc::new(Catalog::new());
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let catalog = catalog.clone();
+                std::thread::spawn(move || {
+                    catalog
+                        .create_table(&format!("t{}", i), source(i + 1))
+                        .unwrap();
+                    catalog.describe(&format!("t{}", i)).unwrap().num_rows
+                })
+            })
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), Precision::Exact(i + 1));
+        }
+        assert_eq!(catalog.table_names("public").unwrap().len(), 8);
+    }
+
+    /// Scans a table into rows of strings.
+    fn rows(source: &TableSource, projection: &[&str]) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        for batch in source.scan(Some(projection)).unwrap() {
+            let batch = batch.unwrap();
+            for row in 0..batch.row_count() {
+                let row = (0..batch.column_count()).map(|i| match batch.field(i).get(row) {
+                    Scalar::Utf8(Some(value)) => value,
+                    Scalar::Int64(Some(value)) => value.to_string(),
+                    value => panic!("unexpected value {:?}", value),
+                });
+                rows.push(row.collect());
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn test_system_tables() {
+        let catalog = Catalog::new();
+        let data = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/data/csv/simple.csv");
+        let definition = ExternalTable::new(TableFormat::Csv, &data).with_option("header", "true");
+        catalog.create_schema("staging").unwrap();
+        catalog.create_table("t", source(2)).unwrap();
+        catalog
+            .create_external_table("staging.people", definition)
+            .unwrap();
+
+        let schemata = catalog.table("information_schema.schemata").unwrap();
+        assert_eq!(
+            rows(&schemata, &["schema_name"]).concat(),
+            ["public", "staging", "information_schema", "system"]
+        );
+
+        // Tables are listed as of each scan, not of the lookup
+        let tables = catalog.table("default.information_schema.tables").unwrap();
+        catalog.create_table("staging.u", source(1)).unwrap();
+        let listed = rows(&tables, &["table_schema", "table_name", "table_type"]);
+        assert_eq!(
+            listed[..3],
+            [
+                ["public", "t", "BASE TABLE"],
+                ["staging", "people", "EXTERNAL TABLE"],
+                ["staging", "u", "BASE TABLE"],
+            ]
+        );
+        assert!(listed.contains(&vec![
+            "information_schema".to_string(),
+            "columns".to_string(),
+            "SYSTEM VIEW".to_string(),
+        ]));
+        conformance::check(tables.as_ref());
+
+        let columns = catalog.table("information_schema.columns").unwrap();
+        let listed = rows(
+            &columns,
+            &["table_name", "column_name", "ordinal_position", "data_type"],
+        );
+        assert_eq!(listed[0], ["t", "id", "1", "Int64"]);
+        assert_eq!(listed.iter().filter(|row| row[0] == "people").count(), 3);
+
+        catalog
+            .register_function(
+                FunctionDescription::new("upper", FunctionKind::Scalar, "(Utf8) -> Utf8")
+                    .with_description("Converts a string to upper case"),
+            )
+            .unwrap();
+        assert_eq!(
+            catalog
+                .register_function(FunctionDescription::new(
+                    "upper",
+                    FunctionKind::Scalar,
+                    "(Utf8) -> Utf8"
+                ))
+                .unwrap_err(),
+            "function already registered: upper (Utf8) -> Utf8"
+        );
+        let functions = catalog.table("system.functions").unwrap();
+        assert_eq!(
+            rows(&functions, &["function_name", "function_type"]),
+            [["upper", "SCALAR"]]
+        );
+
+        catalog
+            .set_setting("search_path", "staging, public")
+            .unwrap();
+        catalog.set_setting("batch_size", "1024").unwrap();
+        assert_eq!(
+            catalog.setting("search_path").unwrap().unwrap(),
+            "default.staging, default.public"
+        );
+        assert_eq!(
+            catalog.resolve("u").unwrap().to_string(),
+            "default.staging.u"
+        );
+        let settings = catalog.table("system.settings").unwrap();
+        assert_eq!(
+            rows(&settings, &["name"]).concat(),
+            ["batch_size", "default_catalog", "search_path"]
+        );
+        assert_eq!(
+            catalog.describe("system.settings").unwrap().num_rows,
+            Precision::Exact(3)
+        );
+
+        // Virtual tables are read-only and only reachable by qualified name
+        assert_eq!(
+            catalog.create_schema("system").unwrap_err(),
+            "schema name is reserved: system"
+        );
+        assert_eq!(
+            catalog
+                .drop_table("information_schema.tables")
+                .err()
+                .unwrap(),
+            "cannot drop system table default.information_schema.tables"
+        );
+        assert!(catalog.resolve("tables").is_err());
+        assert!(catalog.table("missing.information_schema.tables").is_err());
+
+        drop(catalog);
+        assert!(tables.scan(None).is_err());
+    }
+
+    #[test]
+    fn test_persistence() {
+        let path = std::env::temp_dir().join(format!("dbms-catalog-{}.json", std::process::id()));
+        let data = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/data/csv/simple.csv");
+        let definition = ExternalTable::new(TableFormat::Csv, &data).with_option("header", "true");
+
+        let catalog = Catalog::open(&path).unwrap();
+        catalog.create_schema("staging").unwrap();
+        catalog
+            .create_external_table("staging.people", definition.clone())
+            .unwrap();
+        catalog
+            .create_external_table("dropped", definition.clone())
+            .unwrap();
+        catalog.drop_table("dropped").unwrap();
+        catalog.create_table("in_memory", source(1)).unwrap();
+        drop(catalog);
+
+        // External tables and schemas survive a restart; other tables do not
+        let catalog = Catalog::open(&path).unwrap();
+        assert_eq!(
+            catalog.schema_names("default").unwrap(),
+            ["public", "staging"]
+        );
+        assert!(catalog.table_names("public").unwrap().is_empty());
+        assert_eq!(
+            catalog.external_table("staging.people").unwrap(),
+            Some(definition)
+        );
+        let description = catalog.describe("staging.people").unwrap();
+        assert_eq!(description.schema.fields().len(), 3);
+
+        // A change that cannot be saved is undone
+        std::fs::remove_file(&path).unwrap();
+        std::fs::create_dir_all(path.join("blocked")).unwrap();
+        assert!(catalog.create_schema("lost").is_err());
+        assert_eq!(
+            catalog.schema_names("default").unwrap(),
+            ["public", "staging"]
+        );
+
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
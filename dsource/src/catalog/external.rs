@@ -0,0 +1,331 @@
+//! Definitions of tables over files, as created by `CREATE EXTERNAL TABLE`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use dbms_dtype::Schema;
+
+use super::TableSource;
+use crate::{
+    ArrowIpcDataSource, AvroDataSource, BadRecordPolicy, CsvDataSource, CsvOptions,
+    DEFAULT_BATCH_SIZE, DeltaDataSource, FileCompression, IcebergDataSource, IpcFormat,
+    JsonDataSource, JsonOptions, ParquetDataSource,
+};
+
+/// File format of an external table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    Json,
+    Parquet,
+    ArrowIpc,
+    Avro,
+    Delta,
+    Iceberg,
+}
+
+impl TableFormat {
+    /// Parses a format name as written in `STORED AS`, ignoring case.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" | "ndjson" => Ok(Self::Json),
+            "parquet" => Ok(Self::Parquet),
+            "arrow" | "ipc" => Ok(Self::ArrowIpc),
+            "avro" => Ok(Self::Avro),
+            "delta" => Ok(Self::Delta),
+            "iceberg" => Ok(Self::Iceberg),
+            _ => Err(format!("unknown table format: {}", name)),
+        }
+    }
+}
+
+impl fmt::Display for TableFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Parquet => "parquet",
+            Self::ArrowIpc => "arrow",
+            Self::Avro => "avro",
+            Self::Delta => "delta",
+            Self::Iceberg => "iceberg",
+        })
+    }
+}
+
+/// The definition of a table over one or more files.
+///
+/// Options are kept as strings, as written in the `OPTIONS` clause, and are
+/// validated when the data source is created. Every format accepts
+/// `batch_size`; CSV accepts `delimiter`, `quote`, `escape`, `header`,
+/// `comment`, `null_regex` and `compression`; JSON accepts `compression`;
+/// both accept `bad_records` (`fail`, `skip`, `null_fill` or `redirect`) and
+/// `rejects_path`, the file redirected records are appended to; Arrow
+/// accepts `format` (`file` or `stream`); Delta accepts `version`; Iceberg
+/// accepts `snapshot_id`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalTable {
+    pub format: TableFormat,
+    pub location: PathBuf,
+    pub options: BTreeMap<String, String>,
+    /// Declared schema, or `None` to infer it from the files.
+    pub schema: Option<Schema>,
+}
+
+impl ExternalTable {
+    pub fn new(format: TableFormat, location: impl Into<PathBuf>) -> Self {
+        Self {
+            format,
+            location: location.into(),
+            options: BTreeMap::new(),
+            schema: None,
+        }
+    }
+
+    pub fn with_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Creates the data source described by this definition.
+    pub fn source(&self) -> Result<TableSource, String> {
+        let mut batch_size = DEFAULT_BATCH_SIZE;
+        let mut csv = CsvOptions::default();
+        let mut json = JsonOptions::default();
+        let mut ipc_format = None;
+        let mut version = None;
+        let mut snapshot_id = None;
+        let mut bad_records = None;
+        let mut rejects_path = None;
+
+        for (key, value) in &self.options {
+            let invalid = || format!("invalid value for option {}: {}", key, value);
+            match (self.format, key.as_str()) {
+                (_, "batch_size") => batch_size = value.parse().map_err(|_| invalid())?,
+                (TableFormat::Csv, "delimiter") => {
+                    csv.delimiter = byte(value).ok_or_else(invalid)?
+                }
+                (TableFormat::Csv, "quote") => csv.quote = byte(value).ok_or_else(invalid)?,
+                (TableFormat::Csv, "escape") => csv.escape = Some(byte(value).ok_or_else(invalid)?),
+                (TableFormat::Csv, "header") => {
+                    csv.header = value.parse().map_err(|_| invalid())?
+                }
+                (TableFormat::Csv, "comment") => {
+                    csv.comment = Some(byte(value).ok_or_else(invalid)?)
+                }
+                (TableFormat::Csv, "null_regex") => csv.null_regex = Some(value.clone()),
+                (TableFormat::Csv, "compression") => {
+                    csv.compression = Some(FileCompression::from_name(value).ok_or_else(invalid)?)
+                }
+                (TableFormat::Csv | TableFormat::Json, "bad_records") => {
+                    bad_records = Some(value.as_str())
+                }
+                (TableFormat::Csv | TableFormat::Json, "rejects_path") => {
+                    rejects_path = Some(PathBuf::from(value))
+                }
+                (TableFormat::Json, "compression") => {
+                    json.compression = Some(FileCompression::from_name(value).ok_or_else(invalid)?)
+                }
+                (TableFormat::ArrowIpc, "format") => {
+                    ipc_format = Some(match value.as_str() {
+                        "file" => IpcFormat::File,
+                        "stream" => IpcFormat::Stream,
+                        _ => return Err(invalid()),
+                    })
+                }
+                (TableFormat::Delta, "version") => {
+                    version = Some(value.parse().map_err(|_| invalid())?)
+                }
+                (TableFormat::Iceberg, "snapshot_id") => {
+                    snapshot_id = Some(value.parse().map_err(|_| invalid())?)
+                }
+                _ => {
+                    return Err(format!(
+                        "unknown option for {} tables: {}",
+                        self.format, key
+                    ));
+                }
+            }
+        }
+        if batch_size == 0 {
+            return Err("batch_size must be positive".to_string());
+        }
+        let bad_records = match (bad_records, rejects_path) {
+            (None | Some("fail"), None) => BadRecordPolicy::Fail,
+            (Some("skip"), None) => BadRecordPolicy::Skip,
+            (Some("null_fill"), None) => BadRecordPolicy::NullFill,
+            (Some("redirect"), Some(path)) => BadRecordPolicy::Redirect(path),
+            (Some("redirect"), None) => {
+                return Err("bad_records redirect requires rejects_path".to_string());
+            }
+            (Some(value), None) => {
+                return Err(format!("invalid value for option bad_records: {}", value));
+            }
+            (_, Some(_)) => {
+                return Err("rejects_path requires bad_records redirect".to_string());
+            }
+        };
+        csv.bad_records = bad_records.clone();
+        json.bad_records = bad_records;
+
+        let location = self.location.clone();
+        let schema = self.schema.clone();
+        Ok(match self.format {
+            TableFormat::Csv => {
+                Arc::new(CsvDataSource::new(location, schema, batch_size).with_options(csv))
+            }
+            TableFormat::Json => {
+                Arc::new(JsonDataSource::new(location, schema, batch_size).with_options(json))
+            }
+            TableFormat::Parquet => {
+                let source = ParquetDataSource::new(location, batch_size);
+                match schema {
+                    Some(schema) => Arc::new(source.with_table_schema(schema)),
+                    None => Arc::new(source),
+                }
+            }
+            TableFormat::ArrowIpc => {
+                if schema.is_some() {
+                    return Err("arrow tables take their schema from the file".to_string());
+                }
+                let source = ArrowIpcDataSource::new(location);
+                match ipc_format {
+                    Some(format) => Arc::new(source.with_format(format)),
+                    None => Arc::new(source),
+                }
+            }
+            TableFormat::Avro => {
+                if schema.is_some() {
+                    return Err("avro tables take their schema from the file".to_string());
+                }
+                Arc::new(AvroDataSource::new(location, batch_size))
+            }
+            TableFormat::Delta => {
+                if schema.is_some() {
+                    return Err("delta tables take their schema from the log".to_string());
+                }
+                let source = DeltaDataSource::new(location, batch_size);
+                match version {
+                    Some(version) => Arc::new(source.with_version(version)),
+                    None => Arc::new(source),
+                }
+            }
+            TableFormat::Iceberg => {
+                if schema.is_some() {
+                    return Err("iceberg tables take their schema from the metadata".to_string());
+                }
+                let source = IcebergDataSource::new(location, batch_size);
+                match snapshot_id {
+                    Some(snapshot_id) => Arc::new(source.with_snapshot_id(snapshot_id)),
+                    None => Arc::new(source),
+                }
+            }
+        })
+    }
+}
+
+/// Parses an option value that must be a single ASCII character.
+fn byte(value: &str) -> Option<u8> {
+    match value.as_bytes() {
+        [b] if b.is_ascii() => Some(*b),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Catalog;
+
+    fn test_data_path(relative: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test/data")
+            .join(relative)
+    }
+
+    #[test]
+    fn test_source() {
+        let table = ExternalTable::new(TableFormat::Csv, test_data_path("csv/simple.csv"))
+            .with_option("delimiter", ",")
+            .with_option("batch_size", "2");
+        let source = table.source().unwrap();
+        assert_eq!(source.schema().unwrap().fields().len(), 3);
+        assert_eq!(source.scan(None).unwrap().count(), 2);
+
+        let table = ExternalTable::new(TableFormat::Csv, test_data_path("csv/simple.csv"))
+            .with_option("bad_records", "skip");
+        assert!(table.source().is_ok());
+
+        let table = ExternalTable::new(
+            TableFormat::Parquet,
+            test_data_path("parquet/simple.parquet"),
+        );
+        assert_eq!(table.source().unwrap().schema().unwrap().fields().len(), 3);
+    }
+
+    #[test]
+    fn test_rejects() {
+        let path =
+            std::env::temp_dir().join(format!("dbms-external-rejects-{}.csv", std::process::id()));
+        std::fs::write(&path, "id,name\n1,a\n2,b,c\n3,c\n").unwrap();
+        let catalog = Catalog::new();
+        let table = ExternalTable::new(TableFormat::Csv, &path).with_option("bad_records", "skip");
+        catalog.create_external_table("people", table).unwrap();
+
+        // Rejects are counted through the catalog's handle on the table
+        let (batches, rejects) = catalog
+            .table("people")
+            .unwrap()
+            .scan_with_rejects(None)
+            .unwrap();
+        let rows: usize = batches.map(|b| b.unwrap().row_count()).sum();
+        assert_eq!(rows, 2);
+        assert_eq!(rejects.count(), 1);
+        assert_eq!(rejects.records()[0].line, 3);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let err = ExternalTable::new(TableFormat::Parquet, "t.parquet")
+            .with_option("delimiter", ";")
+            .source()
+            .err();
+        assert_eq!(
+            err.as_deref(),
+            Some("unknown option for parquet tables: delimiter")
+        );
+
+        let err = ExternalTable::new(TableFormat::Csv, "t.csv")
+            .with_option("delimiter", ";;")
+            .source()
+            .err();
+        assert_eq!(
+            err.as_deref(),
+            Some("invalid value for option delimiter: ;;")
+        );
+
+        let err = ExternalTable::new(TableFormat::Json, "t.json")
+            .with_option("bad_records", "redirect")
+            .source()
+            .err();
+        assert_eq!(
+            err.as_deref(),
+            Some("bad_records redirect requires rejects_path")
+        );
+
+        assert_eq!(TableFormat::parse("PARQUET").unwrap(), TableFormat::Parquet);
+        assert_eq!(TableFormat::parse("Delta").unwrap(), TableFormat::Delta);
+        assert_eq!(TableFormat::parse("iceberg").unwrap(), TableFormat::Iceberg);
+        assert!(TableFormat::parse("xlsx").is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Persistence of catalog definitions in a JSON metadata file.
+//!
+//! The file records every catalog and schema, and the definition of every
+//! external table. Tables registered from other data sources only live in
+//! memory.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use dbms_dtype::{DataType, Field, Schema};
+use serde_json::{Map, Value, json};
+
+use super::{ExternalTable, TableFormat};
+use crate::object_store::local_object;
+
+/// Version of the metadata file layout.
+const VERSION: u64 = 1;
+
+/// External table definitions by catalog, schema and table name. Empty
+/// schemas and catalogs are kept.
+pub(super) type Definitions = BTreeMap<String, BTreeMap<String, BTreeMap<String, ExternalTable>>>;
+
+/// Reads the definitions in a metadata file, or `None` if it does not exist.
+pub(super) fn load(path: &Path) -> Result<Option<Definitions>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    decode(&text)
+        .map(Some)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Replaces a metadata file with the given definitions. The file is written
+/// to a temporary file and renamed, so readers never see a partial update.
+pub(super) fn save(path: &Path, definitions: &Definitions) -> Result<(), String> {
+    let (store, location) = local_object(path);
+    store.put(&location, encode(definitions)?.into())
+}
+
+fn encode(definitions: &Definitions) -> Result<String, String> {
+    let mut catalogs = Map::new();
+    for (catalog, schemas) in definitions {
+        let mut schemas_json = Map::new();
+        for (schema, tables) in schemas {
+            let mut tables_json = Map::new();
+            for (name, table) in tables {
+                tables_json.insert(name.clone(), encode_table(table)?);
+            }
+            schemas_json.insert(schema.clone(), Value::Object(tables_json));
+        }
+        catalogs.insert(catalog.clone(), Value::Object(schemas_json));
+    }
+    let metadata = json!({ "version": VERSION, "catalogs": catalogs });
+    serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())
+}
+
+fn encode_table(table: &ExternalTable) -> Result<Value, String> {
+    let location = table
+        .location
+        .to_str()
+        .ok_or_else(|| format!("location is not valid UTF-8: {}", table.location.display()))?;
+    let schema = table.schema.as_ref().map(|schema| {
+        schema
+            .fields()
+            .iter()
+            .map(|field| json!({ "name": field.name(), "type": type_name(field.dtype()) }))
+            .collect::<Vec<_>>()
+    });
+    Ok(json!({
+        "format": table.format.to_string(),
+        "location": location,
+        "options": table.options,
+        "schema": schema,
+    }))
+}
+
+fn decode(text: &str) -> Result<Definitions, String> {
+    let metadata: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let version = metadata.get("version").and_then(Value::as_u64);
+    if version != Some(VERSION) {
+        return Err(format!("unsupported metadata version: {:?}", version));
+    }
+
+    let mut definitions = Definitions::new();
+    for (catalog, schemas) in object(&metadata, "catalogs")? {
+        let catalog = definitions.entry(catalog.clone()).or_default();
+        for (schema, tables) in as_object(schemas, "schemas")? {
+            let schema = catalog.entry(schema.clone()).or_default();
+            for (name, table) in as_object(tables, "tables")? {
+                let table = decode_table(table).map_err(|e| format!("table {}: {}", name, e))?;
+                schema.insert(name.clone(), table);
+            }
+        }
+    }
+    Ok(definitions)
+}
+
+fn decode_table(table: &Value) -> Result<ExternalTable, String> {
+    let format = TableFormat::parse(string(table, "format")?)?;
+    let mut definition = ExternalTable::new(format, string(table, "location")?);
+    for (key, value) in object(table, "options")? {
+        let value = value
+            .as_str()
+            .ok_or_else(|| format!("option {} is not a string", key))?;
+        definition = definition.with_option(key.clone(), value);
+    }
+
+    if let Some(fields) = table.get("schema").filter(|schema| !schema.is_null()) {
+        let fields = fields
+            .as_array()
+            .ok_or("schema is not an array")?
+            .iter()
+            .map(|field| {
+                Ok(Field::new(
+                    string(field, "name")?,
+                    data_type(string(field, "type")?)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        definition = definition.with_schema(Schema::new(fields));
+    }
+    Ok(definition)
+}
+
+fn object<'a>(value: &'a Value, key: &str) -> Result<&'a Map<String, Value>, String> {
+    as_object(
+        value.get(key).ok_or_else(|| format!("missing {}", key))?,
+        key,
+    )
+}
+
+fn as_object<'a>(value: &'a Value, what: &str) -> Result<&'a Map<String, Value>, String> {
+    value
+        .as_object()
+        .ok_or_else(|| format!("{} is not an object", what))
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> Result<&'a str, String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("missing string {}", key))
+}
+
+/// Every data type a column can have, for parsing type names.
+const DATA_TYPES: [DataType; 13] = [
+    DataType::Boolean,
+    DataType::Int8,
+    DataType::Int16,
+    DataType::Int32,
+    DataType::Int64,
+    DataType::UInt8,
+    DataType::UInt16,
+    DataType::UInt32,
+    DataType::UInt64,
+    DataType::Float32,
+    DataType::Float64,
+    DataType::Utf8,
+    DataType::Binary,
+];
+
+/// Returns the name a data type is stored under in the metadata file.
+fn type_name(dtype: &DataType) -> &'static str {
+    match dtype {
+        DataType::Boolean => "Boolean",
+        DataType::Int8 => "Int8",
+        DataType::Int16 => "Int16",
+        DataType::Int32 => "Int32",
+        DataType::Int64 => "Int64",
+        DataType::UInt8 => "UInt8",
+        DataType::UInt16 => "UInt16",
+        DataType::UInt32 => "UInt32",
+        DataType::UInt64 => "UInt64",
+        DataType::Float32 => "Float32",
+        DataType::Float64 => "Float64",
+        DataType::Utf8 => "Utf8",
+        DataType::Binary => "Binary",
+    }
+}
+
+/// Parses a data type name as written by `encode_table`.
+fn data_type(name: &str) -> Result<DataType, String> {
+    DATA_TYPES
+        .into_iter()
+        .find(|dtype| type_name(dtype) == name)
+        .ok_or_else(|| format!("unknown data type: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::Utf8),
+        ]);
+        let table = ExternalTable::new(TableFormat::Csv, "/data/t.csv")
+            .with_option("delimiter", "|")
+            .with_schema(schema);
+        let inferred = ExternalTable::new(TableFormat::Parquet, "/data/u");
+
+        let mut definitions = Definitions::new();
+        let public = definitions
+            .entry("default".to_string())
+            .or_default()
+            .entry("public".to_string())
+            .or_default();
+        public.insert("t".to_string(), table);
+        public.insert("u".to_string(), inferred);
+        definitions
+            .entry("empty".to_string())
+            .or_default()
+            .insert("s".to_string(), BTreeMap::new());
+
+        let text = encode(&definitions).unwrap();
+        assert_eq!(decode(&text).unwrap(), definitions);
+    }
+
+    #[test]
+    fn test_type_names() {
+        for dtype in DATA_TYPES {
+            assert_eq!(data_type(type_name(&dtype)).unwrap(), dtype);
+        }
+        assert_eq!(type_name(&DataType::UInt16), "UInt16");
+        assert_eq!(
+            data_type("Decimal").unwrap_err(),
+            "unknown data type: Decimal"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            decode(r#"{"version": 2, "catalogs": {}}"#).unwrap_err(),
+            "unsupported metadata version: Some(2)"
+        );
+        let text = r#"{"version": 1, "catalogs": {"c": {"s": {"t": {"format": "xlsx"}}}}}"#;
+        assert_eq!(
+            decode(text).unwrap_err(),
+            "table t: unknown table format: xlsx"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Possibly qualified names of tables and schemas.
+
+use std::fmt;
+
+/// A table name, optionally qualified by its schema and catalog.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TableReference {
+    pub catalog: Option<String>,
+    pub schema: Option<String>,
+    pub table: String,
+}
+
+impl TableReference {
+    /// Creates an unqualified reference.
+    pub fn bare(table: impl Into<String>) -> Self {
+        Self {
+            catalog: None,
+            schema: None,
+            table: table.into(),
+        }
+    }
+
+    /// Creates a reference qualified by its schema.
+    pub fn partial(schema: impl Into<String>, table: impl Into<String>) -> Self {
+        Self {
+            catalog: None,
+            schema: Some(schema.into()),
+            table: table.into(),
+        }
+    }
+
+    /// Creates a fully qualified reference.
+    pub fn full(
+        catalog: impl Into<String>,
+        schema: impl Into<String>,
+        table: impl Into<String>,
+    ) -> Self {
+        Self {
+            catalog: Some(catalog.into()),
+            schema: Some(schema.into()),
+            table: table.into(),
+        }
+    }
+
+    /// Parses a name of the form `table`, `schema.table` or
+    /// `catalog.schema.table`.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match split_name(name, 3)?.as_slice() {
+            [table] => Ok(Self::bare(*table)),
+            [schema, table] => Ok(Self::partial(*schema, *table)),
+            [catalog, schema, table] => Ok(Self::full(*catalog, *schema, *table)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl fmt::Display for TableReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(catalog) = &self.catalog {
+            write!(f, "{}.", catalog)?;
+        }
+        if let Some(schema) = &self.schema {
+            write!(f, "{}.", schema)?;
+        }
+        write!(f, "{}", self.table)
+    }
+}
+
+/// A schema name, optionally qualified by its catalog.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SchemaReference {
+    pub catalog: Option<String>,
+    pub schema: String,
+}
+
+impl SchemaReference {
+    /// Parses a name of the form `schema` or `catalog.schema`.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match split_name(name, 2)?.as_slice() {
+            [schema] => Ok(Self {
+                catalog: None,
+                schema: schema.to_string(),
+            }),
+            [catalog, schema] => Ok(Self {
+                catalog: Some(catalog.to_string()),
+                schema: schema.to_string(),
+            }),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl fmt::Display for SchemaReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(catalog) = &self.catalog {
+            write!(f, "{}.", catalog)?;
+        }
+        write!(f, "{}", self.schema)
+    }
+}
+
+/// Splits a dotted name into at most `max_parts` non-empty parts.
+fn split_name(name: &str, max_parts: usize) -> Result<Vec<&str>, String> {
+    let parts: Vec<&str> = name.split('.').collect();
+    if parts.len() > max_parts || parts.iter().any(|part| part.is_empty()) {
+        return Err(format!("invalid name: {}", name));
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            TableReference::parse("t").unwrap(),
+            TableReference::bare("t")
+        );
+        assert_eq!(
+            TableReference::parse("s.t").unwrap(),
+            TableReference::partial("s", "t")
+        );
+        let full = TableReference::parse("c.s.t").unwrap();
+        assert_eq!(full, TableReference::full("c", "s", "t"));
+        assert_eq!(full.to_string(), "c.s.t");
+
+        assert_eq!(
+            TableReference::parse("a.b.c.d").unwrap_err(),
+            "invalid name: a.b.c.d"
+        );
+        assert!(TableReference::parse("s.").is_err());
+        assert!(SchemaReference::parse("c.s.t").is_err());
+        assert_eq!(SchemaReference::parse("c.s").unwrap().to_string(), "c.s");
+    }
+}
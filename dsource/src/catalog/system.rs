@@ -0,0 +1,312 @@
+//! Virtual tables describing the contents of a catalog.
+//!
+//! Every catalog has two read-only schemas that are not stored in it:
+//! `information_schema`, holding the `schemata`, `tables` and `columns`
+//! views of the SQL standard, and `system`, listing registered functions and
+//! settings. Their rows are computed from the catalog on every scan, so they
+//! always reflect its current contents.
+
+use std::fmt;
+use std::sync::{Arc, RwLock, Weak};
+
+use arrow::array::{Int64Array, StringArray};
+use dbms_dtype::{Column, DataType, Field, RecordBatch, Schema};
+
+use super::{State, TableSource};
+use crate::{BatchIterator, DataSource, InMemoryDataSource, Statistics};
+
+/// Name of the schema holding the standard metadata views.
+pub const INFORMATION_SCHEMA: &str = "information_schema";
+
+/// Name of the schema holding the function and setting tables.
+pub const SYSTEM_SCHEMA: &str = "system";
+
+/// Kind of a registered function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionKind {
+    Scalar,
+    Aggregate,
+    Table,
+}
+
+impl fmt::Display for FunctionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Scalar => "SCALAR",
+            Self::Aggregate => "AGGREGATE",
+            Self::Table => "TABLE",
+        })
+    }
+}
+
+/// A function known to the query engine, as listed in `system.functions`.
+///
+/// Only the description is registered; the implementation lives with the
+/// engine. A name may be registered once per signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionDescription {
+    pub name: String,
+    pub kind: FunctionKind,
+    /// Argument and return types, such as `(Utf8, Int64) -> Utf8`.
+    pub signature: String,
+    pub description: String,
+}
+
+impl FunctionDescription {
+    pub fn new(name: impl Into<String>, kind: FunctionKind, signature: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            signature: signature.into(),
+            description: String::new(),
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+}
+
+/// One of the virtual tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SystemTable {
+    Schemata,
+    Tables,
+    Columns,
+    Functions,
+    Settings,
+}
+
+impl SystemTable {
+    const ALL: [Self; 5] = [
+        Self::Schemata,
+        Self::Tables,
+        Self::Columns,
+        Self::Functions,
+        Self::Settings,
+    ];
+
+    /// Returns the virtual table with the given schema and table name.
+    pub(super) fn find(schema: &str, table: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.schema_name() == schema && t.name() == table)
+    }
+
+    /// Returns true if a schema name is taken by virtual tables.
+    pub(super) fn is_reserved(schema: &str) -> bool {
+        schema == INFORMATION_SCHEMA || schema == SYSTEM_SCHEMA
+    }
+
+    fn schema_name(self) -> &'static str {
+        match self {
+            Self::Schemata | Self::Tables | Self::Columns => INFORMATION_SCHEMA,
+            Self::Functions | Self::Settings => SYSTEM_SCHEMA,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Schemata => "schemata",
+            Self::Tables => "tables",
+            Self::Columns => "columns",
+            Self::Functions => "functions",
+            Self::Settings => "settings",
+        }
+    }
+
+    fn schema(self) -> Schema {
+        let utf8 = |name| Field::new(name, DataType::Utf8);
+        Schema::new(match self {
+            Self::Schemata => vec![utf8("catalog_name"), utf8("schema_name")],
+            Self::Tables => vec![
+                utf8("table_catalog"),
+                utf8("table_schema"),
+                utf8("table_name"),
+                utf8("table_type"),
+            ],
+            Self::Columns => vec![
+                utf8("table_catalog"),
+                utf8("table_schema"),
+                utf8("table_name"),
+                utf8("column_name"),
+                Field::new("ordinal_position", DataType::Int64),
+                utf8("data_type"),
+                utf8("is_nullable"),
+            ],
+            Self::Functions => vec![
+                utf8("function_name"),
+                utf8("function_type"),
+                utf8("signature"),
+                utf8("description"),
+            ],
+            Self::Settings => vec![utf8("name"), utf8("value")],
+        })
+    }
+}
+
+/// A table visible in `information_schema.tables`: its schema, name, type
+/// and, for stored tables, its data source.
+type TableEntry = (String, String, &'static str, Option<TableSource>);
+
+/// A virtual table over the contents of one catalog.
+///
+/// It refers to the catalog weakly, so it can be registered as a table
+/// without keeping the catalog alive; scans fail once the catalog is dropped.
+pub(super) struct SystemDataSource {
+    table: SystemTable,
+    catalog: String,
+    state: Weak<RwLock<State>>,
+}
+
+impl SystemDataSource {
+    pub(super) fn new(table: SystemTable, catalog: &str, state: &Arc<RwLock<State>>) -> Self {
+        Self {
+            table,
+            catalog: catalog.to_string(),
+            state: Arc::downgrade(state),
+        }
+    }
+
+    /// Lists the schemas and tables of the catalog, including virtual ones.
+    /// The lock is released before returning, so sources can then be asked
+    /// for their schemas without blocking changes to the catalog.
+    fn entries(&self) -> Result<(Vec<String>, Vec<TableEntry>), String> {
+        self.read_state(|state| {
+            let schemas = state
+                .catalogs
+                .get(&self.catalog)
+                .ok_or_else(|| format!("catalog not found: {}", self.catalog))?;
+
+            let mut schema_names: Vec<String> = schemas.keys().cloned().collect();
+            let mut tables: Vec<TableEntry> = schemas
+                .iter()
+                .flat_map(|(schema, tables)| {
+                    tables.iter().map(|(name, table)| {
+                        let table_type = match table.definition {
+                            Some(_) => "EXTERNAL TABLE",
+                            None => "BASE TABLE",
+                        };
+                        let source = Some(table.source.clone());
+                        (schema.clone(), name.clone(), table_type, source)
+                    })
+                })
+                .collect();
+            schema_names.extend([INFORMATION_SCHEMA, SYSTEM_SCHEMA].map(String::from));
+            tables.extend(SystemTable::ALL.into_iter().map(|table| {
+                let schema = table.schema_name().to_string();
+                (schema, table.name().to_string(), "SYSTEM VIEW", None)
+            }));
+            Ok((schema_names, tables))
+        })?
+    }
+
+    /// Computes the current rows of the table.
+    fn contents(&self) -> Result<InMemoryDataSource, String> {
+        let columns = match self.table {
+            SystemTable::Schemata => {
+                let (schemas, _) = self.entries()?;
+                vec![
+                    utf8(vec![self.catalog.clone(); schemas.len()]),
+                    utf8(schemas),
+                ]
+            }
+            SystemTable::Tables => {
+                let (_, tables) = self.entries()?;
+                vec![
+                    utf8(vec![self.catalog.clone(); tables.len()]),
+                    utf8(tables.iter().map(|t| t.0.clone()).collect()),
+                    utf8(tables.iter().map(|t| t.1.clone()).collect()),
+                    utf8(tables.iter().map(|t| t.2.to_string()).collect()),
+                ]
+            }
+            SystemTable::Columns => self.columns()?,
+            SystemTable::Functions => {
+                let functions = self.read_state(|state| state.functions.clone())?;
+                vec![
+                    utf8(functions.iter().map(|f| f.name.clone()).collect()),
+                    utf8(functions.iter().map(|f| f.kind.to_string()).collect()),
+                    utf8(functions.iter().map(|f| f.signature.clone()).collect()),
+                    utf8(functions.iter().map(|f| f.description.clone()).collect()),
+                ]
+            }
+            SystemTable::Settings => {
+                let (names, values) = self
+                    .read_state(|state| state.settings())?
+                    .into_iter()
+                    .unzip();
+                vec![utf8(names), utf8(values)]
+            }
+        };
+        let schema = self.table.schema();
+        let batch = RecordBatch::new(schema.clone(), columns);
+        Ok(InMemoryDataSource::new(schema, vec![batch]))
+    }
+
+    /// Lists the columns of every table. Tables whose schema cannot be read,
+    /// such as external tables over missing files, are left out rather than
+    /// failing the scan.
+    fn columns(&self) -> Result<Vec<Column>, String> {
+        let (_, tables) = self.entries()?;
+        let mut rows: [Vec<String>; 6] = Default::default();
+        let mut positions = Vec::new();
+        for (schema_name, table_name, _, source) in tables {
+            let schema = match source {
+                Some(source) => match source.schema() {
+                    Ok(schema) => schema,
+                    Err(_) => continue,
+                },
+                None => SystemTable::find(&schema_name, &table_name)
+                    .expect("virtual table exists")
+                    .schema(),
+            };
+            for (i, field) in schema.fields().iter().enumerate() {
+                rows[0].push(self.catalog.clone());
+                rows[1].push(schema_name.clone());
+                rows[2].push(table_name.clone());
+                rows[3].push(field.name().to_string());
+                rows[4].push(format!("{:?}", field.dtype()));
+                rows[5].push("YES".to_string());
+                positions.push(i as i64 + 1);
+            }
+        }
+        let [catalogs, schemas, tables, names, types, nullable] = rows;
+        Ok(vec![
+            utf8(catalogs),
+            utf8(schemas),
+            utf8(tables),
+            utf8(names),
+            Column::Array(Arc::new(Int64Array::from(positions))),
+            utf8(types),
+            utf8(nullable),
+        ])
+    }
+
+    fn read_state<T>(&self, f: impl FnOnce(&State) -> T) -> Result<T, String> {
+        let state = self
+            .state
+            .upgrade()
+            .ok_or_else(|| format!("catalog {} has been dropped", self.catalog))?;
+        let state = state.read().map_err(|e| e.to_string())?;
+        Ok(f(&state))
+    }
+}
+
+fn utf8(values: Vec<String>) -> Column {
+    Column::Array(Arc::new(StringArray::from(values)))
+}
+
+impl DataSource for SystemDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        Ok(self.table.schema())
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        self.contents()?.scan(projection)
+    }
+
+    fn statistics(&self) -> Result<Statistics, String> {
+        self.contents()?.statistics()
+    }
+}
@@ -0,0 +1,103 @@
+//! Transparent decompression for line-based file formats.
+
+use std::io::Read;
+use std::path::Path;
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use xz2::read::XzDecoder;
+
+/// Compression codec applied to a whole file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileCompression {
+    #[default]
+    Uncompressed,
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+impl FileCompression {
+    /// Infers the compression codec from a file extension, e.g. `data.csv.gz`.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz" | "gzip") => Self::Gzip,
+            Some("zst" | "zstd") => Self::Zstd,
+            Some("bz2") => Self::Bzip2,
+            Some("xz") => Self::Xz,
+            _ => Self::Uncompressed,
+        }
+    }
+
+    /// Parses a codec name such as `gzip` or `none`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" | "uncompressed" => Some(Self::Uncompressed),
+            "gzip" | "gz" => Some(Self::Gzip),
+            "zstd" | "zst" => Some(Self::Zstd),
+            "bzip2" | "bz2" => Some(Self::Bzip2),
+            "xz" => Some(Self::Xz),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this codec is not `Uncompressed`.
+    pub fn is_compressed(&self) -> bool {
+        *self != Self::Uncompressed
+    }
+
+    /// Wraps a reader so that reading from it yields decompressed bytes.
+    pub fn decode<R: Read + Send + 'static>(
+        self,
+        reader: R,
+    ) -> Result<Box<dyn Read + Send>, String> {
+        Ok(match self {
+            Self::Uncompressed => Box::new(reader),
+            Self::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            Self::Zstd => Box::new(zstd::Decoder::new(reader).map_err(|e| e.to_string())?),
+            Self::Bzip2 => Box::new(MultiBzDecoder::new(reader)),
+            Self::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            FileCompression::from_path(Path::new("a.csv")),
+            FileCompression::Uncompressed
+        );
+        assert_eq!(
+            FileCompression::from_path(Path::new("a.csv.gz")),
+            FileCompression::Gzip
+        );
+        assert_eq!(
+            FileCompression::from_path(Path::new("a.csv.zst")),
+            FileCompression::Zstd
+        );
+        assert_eq!(
+            FileCompression::from_path(Path::new("a.csv.bz2")),
+            FileCompression::Bzip2
+        );
+        assert_eq!(
+            FileCompression::from_path(Path::new("a.csv.xz")),
+            FileCompression::Xz
+        );
+    }
+
+    #[test]
+    fn test_decode_uncompressed() {
+        let mut out = String::new();
+        FileCompression::Uncompressed
+            .decode(&b"a,b\n"[..])
+            .unwrap()
+            .read_to_string(&mut out)
+            .unwrap();
+        assert_eq!(out, "a,b\n");
+    }
+}
@@ -0,0 +1,137 @@
+//! Behaviour that every `DataSource` implementation must share.
+//!
+//! Each source's tests call [`check`] with a source over data that has at
+//! least two columns and one row.
+
+use std::cmp::Ordering;
+
+use dbms_dtype::Scalar;
+use futures::StreamExt;
+use tokio::runtime::Runtime;
+
+use crate::{BatchIterator, BatchStream, DataSource, Sample};
+
+/// Collects the rows of a scan as scalars, checking each batch's schema
+/// against the expected column names.
+fn collect_rows(batches: BatchIterator, names: &[&str]) -> Vec<Vec<Scalar>> {
+    let mut rows = Vec::new();
+    for batch in batches {
+        let batch = batch.unwrap();
+        let batch_names: Vec<_> = batch.schema().fields().iter().map(|f| f.name()).collect();
+        assert_eq!(batch_names, names);
+
+        for row in 0..batch.row_count() {
+            rows.push(
+                (0..batch.column_count())
+                    .map(|i| batch.field(i).get(row))
+                    .collect(),
+            );
+        }
+    }
+    rows
+}
+
+/// Collects the rows of an asynchronous scan, as [`collect_rows`] does.
+fn collect_stream_rows(runtime: &Runtime, stream: BatchStream, names: &[&str]) -> Vec<Vec<Scalar>> {
+    let batches = runtime.block_on(stream.collect::<Vec<_>>());
+    collect_rows(Box::new(batches.into_iter()), names)
+}
+
+fn sorted(mut rows: Vec<Vec<Scalar>>) -> Vec<Vec<Scalar>> {
+    rows.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    rows
+}
+
+/// Checks scans, projections, partitions and statistics of `source`.
+pub(crate) fn check(source: &dyn DataSource) {
+    let schema = source.schema().unwrap();
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name()).collect();
+    assert!(names.len() >= 2, "conformance data needs two columns");
+
+    // A full scan returns every column in schema order
+    let rows = collect_rows(source.scan(None).unwrap(), &names);
+    assert!(!rows.is_empty(), "conformance data needs a row");
+
+    // Projections return columns in the requested order
+    let reversed: Vec<&str> = names.iter().rev().copied().collect();
+    let projected = collect_rows(source.scan(Some(&reversed)).unwrap(), &reversed);
+    let expected: Vec<Vec<Scalar>> = rows
+        .iter()
+        .map(|row| row.iter().rev().cloned().collect())
+        .collect();
+    assert_eq!(projected, expected);
+
+    let last = [names[names.len() - 1]];
+    let projected = collect_rows(source.scan(Some(&last)).unwrap(), &last);
+    assert_eq!(projected.len(), rows.len());
+
+    // Unknown and duplicate names are rejected
+    let err = source.scan(Some(&[names[0], "no_such_column"])).err();
+    assert_eq!(err.as_deref(), Some("column not found: no_such_column"));
+    let err = source.scan(Some(&[names[0], names[1], names[0]])).err();
+    assert_eq!(
+        err,
+        Some(format!("duplicate column in projection: {}", names[0]))
+    );
+
+    // Outside a runtime an asynchronous scan either fails to start or reads
+    // every row without one
+    if let Ok(stream) = source.scan_stream(None) {
+        let batches = futures::executor::block_on(stream.collect::<Vec<_>>());
+        assert_eq!(collect_rows(Box::new(batches.into_iter()), &names), rows);
+    }
+
+    // Asynchronous scans return the same rows and reject the same projections
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _context = runtime.enter();
+    let stream = source.scan_stream(Some(&reversed)).unwrap();
+    let streamed = collect_stream_rows(&runtime, stream, &reversed);
+    assert_eq!(streamed, expected);
+    assert!(source.scan_stream(Some(&["no_such_column"])).is_err());
+
+    // Partitions together hold exactly the rows of a full scan
+    let count = source.partition_count().unwrap();
+    assert!(count >= 1);
+    let mut partitioned = Vec::new();
+    for partition in 0..count {
+        let batches = source.scan_partition(partition, Some(&reversed)).unwrap();
+        partitioned.extend(collect_rows(batches, &reversed));
+    }
+    let expected = sorted(expected);
+    assert_eq!(sorted(partitioned), expected);
+    assert!(source.scan_partition(count, None).is_err());
+
+    // Full samples hold every row and empty ones none, and a seed always
+    // picks the same rows
+    let methods: [fn(f64) -> Result<Sample, String>; 2] = [Sample::bernoulli, Sample::block];
+    for method in methods {
+        let all = method(1.0).unwrap();
+        let sampled = collect_rows(
+            source.scan_sample(&all, Some(&reversed)).unwrap(),
+            &reversed,
+        );
+        assert_eq!(sorted(sampled), expected);
+        let none = method(0.0).unwrap();
+        assert!(collect_rows(source.scan_sample(&none, None).unwrap(), &names).is_empty());
+        let half = method(0.5).unwrap().with_seed(3);
+        let first = collect_rows(source.scan_sample(&half, None).unwrap(), &names);
+        let second = collect_rows(source.scan_sample(&half, None).unwrap(), &names);
+        assert_eq!(first, second);
+    }
+    let sample = Sample::bernoulli(0.5).unwrap();
+    assert!(
+        source
+            .scan_sample(&sample, Some(&["no_such_column"]))
+            .is_err()
+    );
+
+    // Statistics describe every column and agree with the data when exact
+    let statistics = source.statistics().unwrap();
+    assert_eq!(statistics.column_statistics.len(), names.len());
+    if statistics.num_rows.is_exact() {
+        assert_eq!(statistics.num_rows.get(), Some(&rows.len()));
+    }
+}
@@ -1,84 +1,537 @@
 //! CSV data source implementation.
 
-use std::fs::File;
-use std::path::PathBuf;
-use std::sync::Arc;
+mod infer;
 
-use arrow::csv::reader::{Format, Reader, ReaderBuilder};
-use arrow::datatypes::Schema as ArrowSchema;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use arrow::array::{ArrayRef, BooleanArray, PrimitiveArray, StringArray};
+use arrow::compute::kernels::cast_utils::Parser;
+use arrow::csv::reader::{Decoder, Format, Reader, ReaderBuilder};
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType as ArrowDataType, Field as ArrowField, Float32Type, Float64Type,
+    Int8Type, Int16Type, Int32Type, Int64Type, Schema as ArrowSchema, SchemaRef, UInt8Type,
+    UInt16Type, UInt32Type, UInt64Type,
+};
+use arrow::record_batch::{RecordBatch as ArrowRecordBatch, RecordBatchOptions};
+use bytes::Bytes;
 use dbms_dtype::{RecordBatch, Schema};
+use regex::Regex;
+
+use crate::bad_records::{BadRecordHandler, MalformedRecord};
+use crate::object_store::{READ_BUFFER_SIZE, local_object};
+use crate::stream::{blocking_stream, current_runtime};
+use crate::{
+    BadRecordPolicy, BatchIterator, BatchStream, DataSource, FileCompression, ObjectMeta,
+    ObjectReader, ObjectStore, Precision, ScanRejects, Statistics, check_partition,
+    resolve_projection,
+};
+
+pub use infer::{InferenceDiagnostic, SchemaInference};
+
+/// Target size in bytes of each partition of an uncompressed file by default.
+pub const DEFAULT_CSV_PARTITION_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Number of records read to estimate the row count of a large file.
+const STATISTICS_SAMPLE_RECORDS: usize = 1000;
+
+/// Options controlling how CSV files are parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Field delimiter, `,` by default.
+    pub delimiter: u8,
+    /// Quote character, `"` by default.
+    pub quote: u8,
+    /// Escape character inside quoted fields, if any.
+    pub escape: Option<u8>,
+    /// Whether the first line is a header row.
+    pub header: bool,
+    /// Lines starting with this character are skipped.
+    pub comment: Option<u8>,
+    /// Fields matching this regex are read as null. Empty fields are null if unset.
+    pub null_regex: Option<String>,
+    /// Maximum number of records sampled during schema inference, or all if unset.
+    pub schema_infer_max_records: Option<usize>,
+    /// Whether rows with fewer fields than the schema are padded with nulls.
+    pub truncated_rows: bool,
+    /// Compression of the file, inferred from its extension if unset.
+    pub compression: Option<FileCompression>,
+    /// What a scan does with records that cannot be parsed.
+    pub bad_records: BadRecordPolicy,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            escape: None,
+            header: true,
+            comment: None,
+            null_regex: None,
+            schema_infer_max_records: None,
+            truncated_rows: false,
+            compression: None,
+            bad_records: BadRecordPolicy::Fail,
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_quote(mut self, quote: u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    pub fn with_escape(mut self, escape: u8) -> Self {
+        self.escape = Some(escape);
+        self
+    }
+
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn with_comment(mut self, comment: u8) -> Self {
+        self.comment = Some(comment);
+        self
+    }
+
+    pub fn with_null_regex(mut self, null_regex: impl Into<String>) -> Self {
+        self.null_regex = Some(null_regex.into());
+        self
+    }
+
+    pub fn with_schema_infer_max_records(mut self, max_records: usize) -> Self {
+        self.schema_infer_max_records = Some(max_records);
+        self
+    }
+
+    pub fn with_truncated_rows(mut self, allow: bool) -> Self {
+        self.truncated_rows = allow;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: FileCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
 
-use crate::DataSource;
+    pub fn with_bad_records(mut self, policy: BadRecordPolicy) -> Self {
+        self.bad_records = policy;
+        self
+    }
+
+    /// Builds the Arrow CSV format described by these options.
+    fn format(&self) -> Result<Format, String> {
+        let mut format = Format::default()
+            .with_delimiter(self.delimiter)
+            .with_quote(self.quote)
+            .with_header(self.header)
+            .with_truncated_rows(self.truncated_rows);
+        if let Some(escape) = self.escape {
+            format = format.with_escape(escape);
+        }
+        if let Some(comment) = self.comment {
+            format = format.with_comment(comment);
+        }
+        if let Some(regex) = self.null_regex()? {
+            format = format.with_null_regex(regex);
+        }
+        Ok(format)
+    }
+
+    /// Compiles the null regex, if any.
+    fn null_regex(&self) -> Result<Option<Regex>, String> {
+        self.null_regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| e.to_string())
+    }
+
+    /// Builds a record reader described by these options. Records with the
+    /// wrong number of fields are left to the bad-record policy, if any.
+    fn record_reader<R: Read>(&self, reader: R) -> csv::Reader<R> {
+        csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .escape(self.escape)
+            .has_headers(self.header)
+            .comment(self.comment)
+            .flexible(self.truncated_rows || self.bad_records != BadRecordPolicy::Fail)
+            .from_reader(reader)
+    }
+
+    /// Builds a record writer that quotes and escapes fields where needed, so
+    /// that the record reader reads them back unchanged.
+    fn record_writer<W: Write>(&self, writer: W) -> csv::Writer<W> {
+        let mut builder = csv::WriterBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .flexible(true);
+        if let Some(escape) = self.escape {
+            builder.escape(escape).double_quote(false);
+        }
+        builder.from_writer(writer)
+    }
+}
+
+/// A schema inference result, tagged with the file version it was computed from.
+struct CachedInference {
+    len: u64,
+    modified: Option<SystemTime>,
+    inference: SchemaInference,
+}
 
 /// A data source that reads from CSV files.
 pub struct CsvDataSource {
-    path: PathBuf,
+    store: Arc<dyn ObjectStore>,
+    location: String,
     schema: Option<Schema>,
     batch_size: usize,
+    options: CsvOptions,
+    partition_size: u64,
+    inferred: Mutex<Option<CachedInference>>,
 }
 
 impl CsvDataSource {
     pub fn new(path: impl Into<PathBuf>, schema: Option<Schema>, batch_size: usize) -> Self {
+        let (store, location) = local_object(&path.into());
+        Self::from_store(store, location, schema, batch_size)
+    }
+
+    /// Creates a source reading the object at `location` in `store`.
+    pub fn from_store(
+        store: Arc<dyn ObjectStore>,
+        location: impl Into<String>,
+        schema: Option<Schema>,
+        batch_size: usize,
+    ) -> Self {
         Self {
-            path: path.into(),
+            store,
+            location: location.into(),
             schema,
             batch_size,
+            options: CsvOptions::default(),
+            partition_size: DEFAULT_CSV_PARTITION_SIZE,
+            inferred: Mutex::new(None),
         }
     }
-}
 
-impl DataSource for CsvDataSource {
-    fn schema(&self) -> Result<Schema, String> {
-        if let Some(schema) = &self.schema {
-            return Ok(schema.clone());
+    /// Sets the options used to parse the file.
+    pub fn with_options(mut self, options: CsvOptions) -> Self {
+        self.options = options;
+        self.inferred = Mutex::new(None);
+        self
+    }
+
+    /// Sets the target size in bytes of each partition. Partition boundaries
+    /// are moved to the next line break, so quoted fields must not span lines
+    /// when a file has more than one partition.
+    pub fn with_partition_size(mut self, partition_size: u64) -> Self {
+        self.partition_size = partition_size.max(1);
+        self
+    }
+
+    /// Infers the schema of the file, reusing the previous result until the
+    /// file's size or modification time changes.
+    pub fn infer_schema(&self) -> Result<SchemaInference, String> {
+        let meta = self.store.head(&self.location)?;
+
+        let mut cached = self.inferred.lock().map_err(|e| e.to_string())?;
+        if let Some(c) = cached.as_ref()
+            && c.len == meta.size
+            && c.modified == meta.last_modified
+        {
+            return Ok(c.inference.clone());
         }
 
-        // Infer schema from file
-        let file = File::open(&self.path).map_err(|e| e.to_string())?;
-vscode ➜ /workspaces/dbmsile::open(&self.path).map_err(|e| e.to_string())?;
-            .with_header(true)
-            .infer_schema(&file, None)
+        let inference = infer::infer_schema(
+            self.options.record_reader(self.open()?),
+            self.options.header,
+            self.options.null_regex()?.as_ref(),
+            self.options.schema_infer_max_records,
+        )?;
+        *cached = Some(CachedInference {
+            len: meta.size,
+            modified: meta.last_modified,
+            inference: inference.clone(),
+        });
+        Ok(inference)
+    }
+
+    /// Returns the compression of the file, falling back to its extension.
+    fn compression(&self) -> FileCompression {
+        self.options
+            .compression
+            .unwrap_or_else(|| FileCompression::from_path(Path::new(&self.location)))
+    }
+
+    /// Returns a reader over the raw bytes of the file.
+    fn object_reader(&self) -> Result<ObjectReader, String> {
+        let meta = self.store.head(&self.location)?;
+        Ok(ObjectReader::new(self.store.clone(), &meta))
+    }
+
+    /// Opens the file for reading, decompressing it if necessary.
+    fn open(&self) -> Result<Box<dyn Read + Send>, String> {
+        self.compression().decode(self.object_reader()?.buffered())
+    }
+
+    /// Decodes batches from a reader positioned at the start of a line.
+    fn read(
+        &self,
+        reader: Box<dyn Read + Send>,
+        header: bool,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let reader = self
+            .reader_builder(header, projection)?
+            .build(reader)
             .map_err(|e| e.to_string())?;
 
-        Schema::try_from(&arrow_schema)
+        Ok(Box::new(CsvBatchIterator { reader }))
     }
 
-    fn scan(
+    /// Configures an Arrow CSV reader for the file's schema and options.
+    fn reader_builder(
         &self,
+        header: bool,
         projection: Option<&[&str]>,
-    ) -> Result<Box<dyn Iterator<Item = Result<RecordBatch, String>>>, String> {
+    ) -> Result<ReaderBuilder, String> {
         let schema = self.schema()?;
         let arrow_schema: ArrowSchema = schema.clone().into();
 
-        let file = File::open(&self.path).map_err(|e| e.to_string())?;
-
         let mut builder = ReaderBuilder::new(Arc::new(arrow_schema))
-            .with_header(true)
+            .with_format(self.options.format()?.with_header(header))
             .with_batch_size(self.batch_size);
 
         // Apply projection if specified
         if let Some(cols) = projection {
-            let indices: Vec<usize> = cols
-                .iter()
-                .map(|name| {
-                    schema
-                        .fields()
-                        .iter()
-                        .position(|f| f.name() == *name)
-                        .ok_or_else(|| format!("column not found: {}", name))
-                })
-                .collect::<Result<Vec<_>, _>>()?;
-            builder = builder.with_projection(indices.into());
+            let indices = resolve_projection(&schema, cols)?;
+            builder = builder.with_projection(indices);
         }
+        Ok(builder)
+    }
 
-        let reader = builder.build(file).map_err(|e| e.to_string())?;
+    /// Counts the rows of a small file, or extrapolates from the average size
+    /// of a sample of records. Compressed files cannot be extrapolated.
+    fn estimate_rows(&self, file_len: usize) -> Result<Precision<usize>, String> {
+        let mut reader = self.options.record_reader(self.open()?);
+        let start = if self.options.header {
+            reader.headers().map_err(|e| e.to_string())?;
+            reader.position().byte() as usize
+        } else {
+            0
+        };
 
-        Ok(Box::new(CsvBatchIterator { reader }))
+        let mut record = csv::StringRecord::new();
+        for count in 0..STATISTICS_SAMPLE_RECORDS {
+            if !reader.read_record(&mut record).map_err(|e| e.to_string())? {
+                return Ok(Precision::Exact(count));
+            }
+        }
+        if self.compression().is_compressed() {
+            return Ok(Precision::Absent);
+        }
+
+        let sampled = reader.position().byte() as usize - start;
+        let estimate =
+            (file_len - start) as f64 / sampled as f64 * STATISTICS_SAMPLE_RECORDS as f64;
+        Ok(Precision::Inexact(estimate.round() as usize))
+    }
+}
+
+impl DataSource for CsvDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        if let Some(schema) = &self.schema {
+            return Ok(schema.clone());
+        }
+
+        Ok(self.infer_schema()?.schema)
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        Ok(self.scan_with_rejects(projection)?.0)
+    }
+
+    /// Under a bad-record policy other than failing, fields are parsed record
+    /// by record, with the null regex and parsers of the Arrow CSV decoder
+    /// that other scans use.
+    fn scan_with_rejects(
+        &self,
+        projection: Option<&[&str]>,
+    ) -> Result<(BatchIterator, ScanRejects), String> {
+        if self.options.bad_records == BadRecordPolicy::Fail {
+            let batches = self.read(self.open()?, self.options.header, projection)?;
+            return Ok((batches, ScanRejects::default()));
+        }
+
+        let schema = self.schema()?;
+        let indices = match projection {
+            Some(cols) => resolve_projection(&schema, cols)?,
+            None => (0..schema.fields().len()).collect(),
+        };
+        let handler = BadRecordHandler::new(&self.options.bad_records)?;
+        let rejects = handler.rejects();
+        let batches = CsvRecordIterator {
+            records: self.options.record_reader(self.open()?),
+            field_count: schema.fields().len(),
+            schema: Arc::new(schema.project(&indices).into()),
+            indices,
+            null_regex: self.options.null_regex()?,
+            options: self.options.clone(),
+            batch_size: self.batch_size.max(1),
+            handler,
+            done: false,
+        };
+        Ok((Box::new(batches), rejects))
+    }
+
+    /// Uncompressed files are read and decoded asynchronously; compressed
+    /// files, and files read with a bad-record policy other than failing,
+    /// are read on the blocking thread pool.
+    fn scan_stream(&self, projection: Option<&[&str]>) -> Result<BatchStream, String> {
+        current_runtime()?;
+        if self.compression().is_compressed() || self.options.bad_records != BadRecordPolicy::Fail {
+            return blocking_stream(self.scan(projection)?);
+        }
+        let decoder = self
+            .reader_builder(self.options.header, projection)?
+            .build_decoder();
+
+        let state = CsvStream {
+            store: self.store.clone(),
+            meta: self.store.head(&self.location)?,
+            offset: 0,
+            buf: Bytes::new(),
+            decoder,
+        };
+        Ok(Box::pin(futures::stream::try_unfold(
+            state,
+            |mut state| async move {
+                match state.next_batch().await? {
+                    Some(batch) => Ok(Some((batch, state))),
+                    None => Ok(None),
+                }
+            },
+        )))
+    }
+
+    /// Uncompressed files are split into byte ranges; compressed files cannot
+    /// be split and form a single partition. So do files read with a
+    /// bad-record policy other than failing, as line numbers are counted
+    /// from the start of the file.
+    fn partition_count(&self) -> Result<usize, String> {
+        if self.compression().is_compressed() || self.options.bad_records != BadRecordPolicy::Fail {
+            return Ok(1);
+        }
+        let len = self.store.head(&self.location)?.size;
+        Ok(len.div_ceil(self.partition_size).max(1) as usize)
+    }
+
+    fn scan_partition(
+        &self,
+        partition: usize,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let count = self.partition_count()?;
+        check_partition(partition, count)?;
+        if count == 1 {
+            return self.scan(projection);
+        }
+
+        // Each partition reads the lines that start within its byte range
+        let mut reader = self.object_reader()?;
+        let len = reader.size();
+        let offset = partition as u64 * self.partition_size;
+        let start = next_line_start(&mut reader, offset, len)?;
+        let end = next_line_start(&mut reader, offset + self.partition_size, len)?;
+        reader
+            .seek(SeekFrom::Start(start))
+            .map_err(|e| e.to_string())?;
+
+        let header = self.options.header && start == 0;
+        self.read(
+            Box::new(reader.buffered().take(end.saturating_sub(start))),
+            header,
+            projection,
+        )
+    }
+
+    fn statistics(&self) -> Result<Statistics, String> {
+        let file_len = self.store.head(&self.location)?.size as usize;
+
+        let mut statistics = Statistics::unknown(&self.schema()?);
+        statistics.num_rows = self.estimate_rows(file_len)?;
+        statistics.total_byte_size = Precision::Exact(file_len);
+        Ok(statistics)
+    }
+}
+
+/// Returns the offset of the first line starting at or after `offset`.
+fn next_line_start(reader: &mut ObjectReader, offset: u64, len: u64) -> Result<u64, String> {
+    if offset == 0 || offset >= len {
+        return Ok(offset.min(len));
+    }
+    reader
+        .seek(SeekFrom::Start(offset - 1))
+        .map_err(|e| e.to_string())?;
+    let mut skipped = Vec::new();
+    let read = BufReader::new(reader)
+        .read_until(b'\n', &mut skipped)
+        .map_err(|e| e.to_string())?;
+    Ok(offset - 1 + read as u64)
+}
+
+/// An asynchronous scan that fetches the file in ranges as it decodes.
+struct CsvStream {
+    store: Arc<dyn ObjectStore>,
+    meta: ObjectMeta,
+    offset: u64,
+    buf: Bytes,
+    decoder: Decoder,
+}
+
+impl CsvStream {
+    async fn next_batch(&mut self) -> Result<Option<RecordBatch>, String> {
+        // Feed the decoder until a batch is full or the file ends
+        loop {
+            if self.buf.is_empty() && self.offset < self.meta.size {
+                let end = self.meta.size.min(self.offset + READ_BUFFER_SIZE as u64);
+                self.buf = self
+                    .store
+                    .clone()
+                    .get_range_async(self.meta.location.clone(), self.offset..end)
+                    .await?;
+                self.offset = end;
+            }
+            let decoded = self.decoder.decode(&self.buf).map_err(|e| e.to_string())?;
+            self.buf = self.buf.slice(decoded..);
+            if decoded == 0 || self.decoder.capacity() == 0 {
+                break;
+            }
+        }
+
+        match self.decoder.flush().map_err(|e| e.to_string())? {
+            Some(batch) => Ok(Some(batch.try_into()?)),
+            None => Ok(None),
+        }
     }
 }
 
 struct CsvBatchIterator {
-    reader: Reader<File>,
+    reader: Reader<Box<dyn Read + Send>>,
 }
 
 impl Iterator for CsvBatchIterator {
@@ -93,9 +546,219 @@ impl Iterator for CsvBatchIterator {
     }
 }
 
+/// Reads records one at a time, so malformed records can be handled by a
+/// bad-record policy instead of failing the batch they are in.
+struct CsvRecordIterator {
+    records: csv::Reader<Box<dyn Read + Send>>,
+    /// Number of fields in a well-formed record.
+    field_count: usize,
+    /// Projected schema, and the indices of its fields in each record.
+    schema: SchemaRef,
+    indices: Vec<usize>,
+    null_regex: Option<Regex>,
+    options: CsvOptions,
+    batch_size: usize,
+    handler: BadRecordHandler,
+    done: bool,
+}
+
+impl CsvRecordIterator {
+    /// Reads up to a batch of records, noting those with the wrong number of
+    /// fields.
+    fn read_records(&mut self) -> Result<Vec<(csv::ByteRecord, Option<String>)>, String> {
+        let mut records = Vec::with_capacity(self.batch_size);
+        let mut record = csv::ByteRecord::new();
+        while records.len() < self.batch_size {
+            if !self
+                .records
+                .read_byte_record(&mut record)
+                .map_err(|e| e.to_string())?
+            {
+                self.done = true;
+                break;
+            }
+            let short = record.len() < self.field_count && !self.options.truncated_rows;
+            let reason = (record.len() > self.field_count || short).then(|| {
+                format!(
+                    "expected {} fields, found {}",
+                    self.field_count,
+                    record.len()
+                )
+            });
+            records.push((record.clone(), reason));
+        }
+        Ok(records)
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, String> {
+        while !self.done {
+            let records = self.read_records()?;
+            if records.is_empty() {
+                break;
+            }
+
+            let mut reasons: Vec<_> = records.iter().map(|(_, reason)| reason.clone()).collect();
+            let mut columns = Vec::with_capacity(self.indices.len());
+            for (field, &index) in self.schema.fields().iter().zip(&self.indices) {
+                let fields: Vec<_> = records
+                    .iter()
+                    .map(|(record, _)| record.get(index))
+                    .collect();
+                let (column, errors) = parse_column(field, &fields, self.null_regex.as_ref())?;
+                for (reason, error) in reasons.iter_mut().zip(errors) {
+                    if reason.is_none() {
+                        *reason = error;
+                    }
+                }
+                columns.push(column);
+            }
+            let options = RecordBatchOptions::new().with_row_count(Some(records.len()));
+            let batch =
+                ArrowRecordBatch::try_new_with_options(self.schema.clone(), columns, &options)
+                    .map_err(|e| e.to_string())?;
+
+            let mut malformed = Vec::new();
+            for (row, ((record, _), reason)) in records.iter().zip(reasons).enumerate() {
+                if let Some(reason) = reason {
+                    malformed.push(MalformedRecord {
+                        row,
+                        line: record.position().map_or(0, |p| p.line() as usize),
+                        text: self.text(record)?,
+                        reason,
+                        unreadable: false,
+                    });
+                }
+            }
+            if let Some(batch) = self.handler.reject_rows(batch, malformed)? {
+                return Ok(Some(batch));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns a record as the file holds it, quoted where needed.
+    fn text(&self, record: &csv::ByteRecord) -> Result<String, String> {
+        let mut writer = self.options.record_writer(Vec::new());
+        writer
+            .write_byte_record(record)
+            .map_err(|e| e.to_string())?;
+        let text = writer.into_inner().map_err(|e| e.to_string())?;
+        let text = text.strip_suffix(b"\n").unwrap_or(&text);
+        Ok(String::from_utf8_lossy(text).into_owned())
+    }
+}
+
+/// Parses the fields of one column like the Arrow CSV decoder, reading
+/// missing fields and fields matching the null regex, or empty fields if
+/// there is none, as null. Fields that cannot be parsed are also read as
+/// null, and the reason is returned for the record they are in.
+fn parse_column(
+    field: &ArrowField,
+    fields: &[Option<&[u8]>],
+    null_regex: Option<&Regex>,
+) -> Result<(ArrayRef, Vec<Option<String>>), String> {
+    let mut errors = vec![None; fields.len()];
+    let values: Vec<Option<&str>> = fields
+        .iter()
+        .zip(&mut errors)
+        .map(|(value, error)| {
+            let value = std::str::from_utf8((*value)?)
+                .map_err(|_| *error = Some(format!("column {}: invalid UTF-8", field.name())))
+                .ok()?;
+            let null = match null_regex {
+                Some(regex) => regex.is_match(value),
+                None => value.is_empty(),
+            };
+            (!null).then_some(value)
+        })
+        .collect();
+
+    let column: ArrayRef = match field.data_type() {
+        ArrowDataType::Boolean => Arc::new(BooleanArray::from(parse_values(
+            field,
+            &values,
+            &mut errors,
+            parse_bool,
+        ))),
+        ArrowDataType::Int8 => parse_primitive::<Int8Type>(field, &values, &mut errors),
+        ArrowDataType::Int16 => parse_primitive::<Int16Type>(field, &values, &mut errors),
+        ArrowDataType::Int32 => parse_primitive::<Int32Type>(field, &values, &mut errors),
+        ArrowDataType::Int64 => parse_primitive::<Int64Type>(field, &values, &mut errors),
+        ArrowDataType::UInt8 => parse_primitive::<UInt8Type>(field, &values, &mut errors),
+        ArrowDataType::UInt16 => parse_primitive::<UInt16Type>(field, &values, &mut errors),
+        ArrowDataType::UInt32 => parse_primitive::<UInt32Type>(field, &values, &mut errors),
+        ArrowDataType::UInt64 => parse_primitive::<UInt64Type>(field, &values, &mut errors),
+        ArrowDataType::Float32 => parse_primitive::<Float32Type>(field, &values, &mut errors),
+        ArrowDataType::Float64 => parse_primitive::<Float64Type>(field, &values, &mut errors),
+        ArrowDataType::Utf8 => Arc::new(StringArray::from(values)),
+        other => return Err(format!("unsupported CSV column type {}", other)),
+    };
+    Ok((column, errors))
+}
+
+fn parse_primitive<T: ArrowPrimitiveType + Parser>(
+    field: &ArrowField,
+    values: &[Option<&str>],
+    errors: &mut [Option<String>],
+) -> ArrayRef {
+    let values = parse_values(field, values, errors, T::parse);
+    Arc::new(values.into_iter().collect::<PrimitiveArray<T>>())
+}
+
+/// Parses non-null values, noting those that cannot be parsed.
+fn parse_values<T>(
+    field: &ArrowField,
+    values: &[Option<&str>],
+    errors: &mut [Option<String>],
+    parse: impl Fn(&str) -> Option<T>,
+) -> Vec<Option<T>> {
+    values
+        .iter()
+        .zip(errors)
+        .map(|(value, error)| {
+            let value = (*value)?;
+            let parsed = parse(value);
+            if parsed.is_none() {
+                *error = Some(format!(
+                    "column {}: cannot parse '{}' as {}",
+                    field.name(),
+                    value,
+                    field.data_type()
+                ));
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Parses a boolean as the Arrow CSV decoder does.
+fn parse_bool(value: &str) -> Option<bool> {
+    if value.eq_ignore_ascii_case("true") {
+        Some(true)
+    } else if value.eq_ignore_ascii_case("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+impl Iterator for CsvRecordIterator {
+    type Item = Result<RecordBatch, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.next_batch();
+        if batch.is_err() {
+            self.done = true;
+        }
+        batch.transpose()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{InMemoryObjectStore, RejectedRecord, conformance};
+    use dbms_dtype::{DataType, Field, Scalar};
     use std::path::PathBuf;
 
     fn test_data_path(relative: &str) -> PathBuf {
@@ -140,4 +803,401 @@ mod tests {
         assert_eq!(batch.schema().fields()[0].name(), "name");
         assert_eq!(batch.schema().fields()[1].name(), "age");
     }
+
+    #[test]
+    fn test_options() {
+        let path = test_data_path("csv/semicolon.csv");
+        let options = CsvOptions::default()
+            .with_delimiter(b';')
+            .with_header(false)
+            .with_comment(b'#')
+            .with_escape(b'\\')
+            .with_null_regex("^NA$");
+        let source = CsvDataSource::new(path, None, 1024).with_options(options);
+
+        let schema = source.schema().unwrap();
+        assert_eq!(schema.fields().len(), 3);
+        assert_eq!(schema.fields()[1].dtype(), &DataType::Utf8);
+        assert_eq!(schema.fields()[2].dtype(), &DataType::Int64);
+
+        let batches: Vec<_> = source.scan(None).unwrap().collect();
+        let batch = batches[0].as_ref().unwrap();
+        assert_eq!(batch.row_count(), 3);
+        assert_eq!(
+            batch.field(1).get(0),
+            Scalar::Utf8(Some("Alice; A.".to_string()))
+        );
+        assert_eq!(
+            batch.field(1).get(2),
+            Scalar::Utf8(Some("Carol \"C\"".to_string()))
+        );
+        assert_eq!(batch.field(2).get(0), Scalar::Int64(None));
+        assert_eq!(batch.field(2).get(1), Scalar::Int64(Some(25)));
+    }
+
+    #[test]
+    fn test_invalid_null_regex() {
+        let path = test_data_path("csv/simple.csv");
+        let options = CsvOptions::default().with_null_regex("(");
+        let source = CsvDataSource::new(path, None, 1024).with_options(options);
+
+        assert!(source.schema().is_err());
+    }
+
+    #[test]
+    fn test_compressed() {
+        for file in [
+            "csv/simple.csv.gz",
+            "csv/simple.csv.zst",
+            "csv/simple.csv.bz2",
+            "csv/simple.csv.xz",
+        ] {
+            let source = CsvDataSource::new(test_data_path(file), None, 1024);
+            let schema = source.schema().unwrap();
+            assert_eq!(schema.fields().len(), 3, "{}", file);
+            assert_eq!(schema.fields()[2].dtype(), &DataType::Int64, "{}", file);
+
+            let batches: Vec<_> = source.scan(None).unwrap().collect();
+            let batch = batches[0].as_ref().unwrap();
+            assert_eq!(batch.row_count(), 3, "{}", file);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_stream() {
+        use futures::TryStreamExt;
+
+        for file in ["csv/simple.csv", "csv/simple.csv.gz"] {
+            let source = CsvDataSource::new(test_data_path(file), None, 2);
+            let batches: Vec<_> = source
+                .scan_stream(Some(&["age", "name"]))
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+
+            assert_eq!(batches.len(), 2, "{}", file);
+            assert_eq!(batches[0].schema().fields()[0].name(), "age");
+            assert_eq!(batches[1].row_count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_explicit_compression() {
+        // An explicit codec takes precedence over the file extension.
+        let path = test_data_path("csv/simple.csv.gz");
+        let options = CsvOptions::default().with_compression(FileCompression::Uncompressed);
+        let source = CsvDataSource::new(path.clone(), None, 1024).with_options(options);
+        assert!(source.schema().is_err());
+
+        let options = CsvOptions::default().with_compression(FileCompression::Gzip);
+        let source = CsvDataSource::new(path, None, 1024).with_options(options);
+        assert_eq!(source.schema().unwrap().fields().len(), 3);
+    }
+
+    #[test]
+    fn test_cached_inference() {
+        let path = std::env::temp_dir().join(format!("dbms-csv-cache-{}.csv", std::process::id()));
+        std::fs::write(&path, "a,b\n1,x\n").unwrap();
+        let source = CsvDataSource::new(path.clone(), None, 1024);
+
+        let first = source.infer_schema().unwrap();
+        assert_eq!(first.schema.fields()[0].dtype(), &DataType::Int64);
+        assert_eq!(source.infer_schema().unwrap(), first);
+
+        // Rewriting the file with a different size invalidates the cache
+        std::fs::write(&path, "a,b\n1.5,x\n2,y\n").unwrap();
+        let second = source.infer_schema().unwrap();
+        assert_eq!(second.schema.fields()[0].dtype(), &DataType::Float64);
+        assert_eq!(second.records_read, 2);
+        assert_eq!(second.diagnostics.len(), 0);
+
+        // Every record is read unless a limit is set
+        let mut data = String::from("a,b\n");
+        for i in 0..2000 {
+            data.push_str(&format!("{},x\n", i));
+        }
+        data.push_str("late,x\n");
+        std::fs::write(&path, data).unwrap();
+        let third = source.infer_schema().unwrap();
+        assert_eq!(third.schema.fields()[0].dtype(), &DataType::Utf8);
+        assert_eq!(third.records_read, 2001);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_inference_diagnostics() {
+        let path = test_data_path("csv/semicolon.csv");
+        let options = CsvOptions::default()
+            .with_delimiter(b';')
+            .with_header(false)
+            .with_comment(b'#')
+            .with_escape(b'\\')
+            .with_null_regex("^NA$")
+            .with_schema_infer_max_records(1);
+        let source = CsvDataSource::new(path, None, 1024).with_options(options);
+        let inference = source.infer_schema().unwrap();
+
+        assert_eq!(inference.records_read, 1);
+        assert_eq!(inference.diagnostics.len(), 1);
+        assert_eq!(inference.diagnostics[0].column, "column_3");
+        assert_eq!(inference.diagnostics[0].dtype, DataType::Utf8);
+        assert!(
+            inference.diagnostics[0]
+                .reason
+                .contains("no non-null values")
+        );
+    }
+
+    #[test]
+    fn test_statistics() {
+        let source = CsvDataSource::new(test_data_path("csv/simple.csv"), None, 1024);
+        let stats = source.statistics().unwrap();
+        assert_eq!(stats.num_rows, Precision::Exact(3));
+        assert!(stats.total_byte_size.is_exact());
+        assert_eq!(stats.column_statistics.len(), 3);
+
+        // Row counts of large files are extrapolated from a sample
+        let path = std::env::temp_dir().join(format!("dbms-csv-stats-{}.csv", std::process::id()));
+        let mut data = String::from("id,name\n");
+        for i in 0..5000 {
+            data.push_str(&format!("{},name{}\n", i % 10, i % 10));
+        }
+        std::fs::write(&path, data).unwrap();
+
+        let source = CsvDataSource::new(path.clone(), None, 1024);
+        let Precision::Inexact(rows) = source.statistics().unwrap().num_rows else {
+            panic!("expected an estimated row count");
+        };
+        assert!((4900..=5100).contains(&rows), "{}", rows);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_partitions() {
+        let path =
+            std::env::temp_dir().join(format!("dbms-csv-partitions-{}.csv", std::process::id()));
+        let mut data = String::from("id,name\n");
+        for i in 0..100 {
+            data.push_str(&format!("{},name{}\n", i, i));
+        }
+        std::fs::write(&path, data).unwrap();
+
+        let source = CsvDataSource::new(path.clone(), None, 16).with_partition_size(100);
+        let count = source.partition_count().unwrap();
+        assert!(count > 1);
+
+        // Scan partitions concurrently and check every row is read exactly once
+        let mut ids: Vec<i64> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..count)
+                .map(|p| {
+                    let source = &source;
+                    scope.spawn(move || {
+                        let mut ids = Vec::new();
+                        for batch in source.scan_partition(p, Some(&["id"])).unwrap() {
+                            let batch = batch.unwrap();
+                            for row in 0..batch.row_count() {
+                                let Scalar::Int64(Some(id)) = batch.field(0).get(row) else {
+                                    panic!("unexpected id");
+                                };
+                                ids.push(id);
+                            }
+                        }
+                        ids
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        });
+        ids.sort();
+        assert_eq!(ids, (0..100).collect::<Vec<_>>());
+
+        // Compressed files cannot be split
+        let source = CsvDataSource::new(test_data_path("csv/simple.csv.gz"), None, 1024)
+            .with_partition_size(1);
+        assert_eq!(source.partition_count().unwrap(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_conformance() {
+        for file in ["csv/simple.csv", "csv/simple.csv.zst"] {
+            conformance::check(&CsvDataSource::new(test_data_path(file), None, 2));
+        }
+        let source =
+            CsvDataSource::new(test_data_path("csv/simple.csv"), None, 2).with_partition_size(8);
+        conformance::check(&source);
+    }
+
+    #[test]
+    fn test_bad_records() {
+        let store = Arc::new(InMemoryObjectStore::new());
+        let data = "id,name,age\n1,Alice,30\n2,Bob,abc\n3,Carol\n4,Dave,40,x\n5,Eve,50\n";
+        store
+            .put("people.csv", data.as_bytes().to_vec().into())
+            .unwrap();
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::Utf8),
+            Field::new("age", DataType::Int64),
+        ]);
+        let source = |policy| {
+            let options = CsvOptions::default().with_bad_records(policy);
+            CsvDataSource::from_store(store.clone(), "people.csv", Some(schema.clone()), 2)
+                .with_options(options)
+        };
+        let first_column = |batches: BatchIterator| -> Vec<Scalar> {
+            let batches: Vec<_> = batches.map(Result::unwrap).collect();
+            batches
+                .iter()
+                .flat_map(|b| (0..b.row_count()).map(|i| b.field(0).get(i)))
+                .collect()
+        };
+
+        assert!(
+            source(BadRecordPolicy::Fail)
+                .scan(None)
+                .unwrap()
+                .any(|batch| batch.is_err())
+        );
+
+        let (batches, rejects) = source(BadRecordPolicy::Skip)
+            .scan_with_rejects(None)
+            .unwrap();
+        assert_eq!(
+            first_column(batches),
+            [Scalar::Int64(Some(1)), Scalar::Int64(Some(5))]
+        );
+        assert_eq!(rejects.count(), 3);
+        assert_eq!(
+            rejects.records(),
+            [
+                RejectedRecord {
+                    line: 3,
+                    reason: "column age: cannot parse 'abc' as Int64".to_string()
+                },
+                RejectedRecord {
+                    line: 4,
+                    reason: "expected 3 fields, found 2".to_string()
+                },
+                RejectedRecord {
+                    line: 5,
+                    reason: "expected 3 fields, found 4".to_string()
+                },
+            ]
+        );
+
+        // Only projected columns are parsed
+        let (batches, rejects) = source(BadRecordPolicy::Skip)
+            .scan_with_rejects(Some(&["id", "name"]))
+            .unwrap();
+        assert_eq!(first_column(batches).len(), 3);
+        assert_eq!(rejects.count(), 2);
+
+        let (batches, rejects) = source(BadRecordPolicy::NullFill)
+            .scan_with_rejects(Some(&["age"]))
+            .unwrap();
+        assert_eq!(
+            first_column(batches),
+            [30, 0, 0, 40, 50].map(|age| Scalar::Int64((age > 0).then_some(age)))
+        );
+        assert_eq!(rejects.count(), 3);
+
+        let path =
+            std::env::temp_dir().join(format!("dbms-csv-rejects-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let (batches, rejects) = source(BadRecordPolicy::Redirect(path.clone()))
+            .scan_with_rejects(None)
+            .unwrap();
+        assert_eq!(first_column(batches).len(), 2);
+        assert_eq!(rejects.count(), 3);
+        let redirected = std::fs::read_to_string(&path).unwrap();
+        let first: serde_json::Value =
+            serde_json::from_str(redirected.lines().next().unwrap()).unwrap();
+        assert_eq!(first["line"], 3);
+        assert_eq!(first["record"], "2,Bob,abc");
+        assert_eq!(redirected.lines().count(), 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_bad_record_parsing() {
+        let store = Arc::new(InMemoryObjectStore::new());
+        let data = "flag,name\nTRUE,\"Smith, Jo\"\nyes,\"Doe, \"\"J\"\"\"\n";
+        store
+            .put("flags.csv", data.as_bytes().to_vec().into())
+            .unwrap();
+        let schema = Schema::new(vec![
+            Field::new("flag", DataType::Boolean),
+            Field::new("name", DataType::Utf8),
+        ]);
+        let path =
+            std::env::temp_dir().join(format!("dbms-csv-quoted-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let source = |policy| {
+            let options = CsvOptions::default().with_bad_records(policy);
+            CsvDataSource::from_store(store.clone(), "flags.csv", Some(schema.clone()), 10)
+                .with_options(options)
+        };
+
+        // Values are parsed the same way whatever the policy
+        assert!(
+            source(BadRecordPolicy::Fail)
+                .scan(None)
+                .unwrap()
+                .any(|batch| batch.is_err())
+        );
+        let (batches, rejects) = source(BadRecordPolicy::Redirect(path.clone()))
+            .scan_with_rejects(None)
+            .unwrap();
+        let batches: Vec<_> = batches.map(Result::unwrap).collect();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].field(0).get(0), Scalar::Boolean(Some(true)));
+        assert_eq!(
+            batches[0].field(1).get(0),
+            Scalar::Utf8(Some("Smith, Jo".to_string()))
+        );
+        assert_eq!(
+            rejects.records()[0].reason,
+            "column flag: cannot parse 'yes' as Boolean"
+        );
+
+        // Rejected records are written back with their quoting
+        let redirected = std::fs::read_to_string(&path).unwrap();
+        let rejected: serde_json::Value = serde_json::from_str(redirected.trim()).unwrap();
+        assert_eq!(rejected["record"], "yes,\"Doe, \"\"J\"\"\"");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_object_store() {
+        use futures::TryStreamExt;
+
+        let store = Arc::new(InMemoryObjectStore::new());
+        let data = std::fs::read(test_data_path("csv/simple.csv")).unwrap();
+        store.put("data/simple.csv", data.into()).unwrap();
+
+        let source = CsvDataSource::from_store(store, "data/simple.csv", None, 2);
+        assert_eq!(source.schema().unwrap().fields().len(), 3);
+        let rows: usize = source
+            .scan(None)
+            .unwrap()
+            .map(|batch| batch.unwrap().row_count())
+            .sum();
+        assert_eq!(rows, 3);
+
+        let batches: Vec<_> = source
+            .scan_stream(Some(&["name"]))
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.row_count()).sum::<usize>(), 3);
+    }
 }
@@ -0,0 +1,299 @@
+//! Schema inference for CSV files with diagnostics.
+
+use std::fmt;
+use std::io::Read;
+use std::sync::LazyLock;
+
+use dbms_dtype::{DataType, Field, Schema};
+use regex::{Regex, RegexSet};
+
+/// Patterns recognised during inference, in priority order.
+static PATTERNS: LazyLock<RegexSet> = LazyLock::new(|| {
+    RegexSet::new([
+        r"(?i)^(true|false)$",
+        r"^-?\d+$",
+        r"^-?((\d*\.\d+|\d+\.\d*)([eE][-+]?\d+)?|\d+([eE][-+]?\d+))$",
+        r"^\d{4}-\d\d-\d\d([T ]\d\d:\d\d:\d\d.*)?$",
+    ])
+    .unwrap()
+});
+
+/// The result of inferring a schema from a sample of CSV records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaInference {
+    /// The inferred schema.
+    pub schema: Schema,
+    /// The number of records sampled.
+    pub records_read: usize,
+    /// Explanations for columns that were widened beyond their narrowest type.
+    pub diagnostics: Vec<InferenceDiagnostic>,
+}
+
+/// Explains why a column was inferred as a wider type than its first values suggested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InferenceDiagnostic {
+    /// The column name.
+    pub column: String,
+    /// The inferred data type.
+    pub dtype: DataType,
+    /// Why the column was widened to `dtype`.
+    pub reason: String,
+}
+
+impl fmt::Display for InferenceDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} inferred as {:?}: {}",
+            self.column, self.dtype, self.reason
+        )
+    }
+}
+
+/// The kind of a single non-null CSV value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    Boolean,
+    Integer,
+    Float,
+    Temporal,
+    Text,
+}
+
+impl ValueKind {
+    fn classify(value: &str) -> Self {
+        if value.starts_with('"') {
+            return Self::Text;
+        }
+        match PATTERNS.matches(value).into_iter().next() {
+            Some(0) => Self::Boolean,
+            // Integers that overflow Int64 can only be represented as text
+            Some(1) if value.parse::<i64>().is_err() => Self::Text,
+            Some(1) => Self::Integer,
+            Some(2) => Self::Float,
+            Some(3) => Self::Temporal,
+            _ => Self::Text,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::Boolean => "boolean",
+            Self::Integer => "integer",
+            Self::Float => "float",
+            Self::Temporal => "temporal",
+            Self::Text => "text",
+        }
+    }
+}
+
+/// The first value of a given kind seen in a column.
+#[derive(Debug, Clone)]
+struct Sample {
+    kind: ValueKind,
+    value: String,
+    line: u64,
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} value {:?} on line {}",
+            self.kind.describe(),
+            self.value,
+            self.line
+        )
+    }
+}
+
+/// Tracks the kinds of values seen in one column.
+#[derive(Debug, Default)]
+struct ColumnInference {
+    samples: Vec<Sample>,
+}
+
+impl ColumnInference {
+    fn update(&mut self, value: &str, line: u64) {
+        let kind = ValueKind::classify(value);
+        if !self.samples.iter().any(|s| s.kind == kind) {
+            self.samples.push(Sample {
+                kind,
+                value: value.to_string(),
+                line,
+            });
+        }
+    }
+
+    fn find(&self, kind: ValueKind) -> Option<&Sample> {
+        self.samples.iter().find(|s| s.kind == kind)
+    }
+
+    /// Returns the narrowest type that holds every value seen, and why it is
+    /// wider than the first value's type, if it is.
+    fn resolve(&self) -> (DataType, Option<String>) {
+        let Some(first) = self.samples.first() else {
+            return (
+                DataType::Utf8,
+                Some("no non-null values in the sampled records".to_string()),
+            );
+        };
+        let kinds: Vec<ValueKind> = self.samples.iter().map(|s| s.kind).collect();
+        let only = |allowed: &[ValueKind]| kinds.iter().all(|k| allowed.contains(k));
+
+        if only(&[ValueKind::Text]) {
+            return (DataType::Utf8, None);
+        }
+        if only(&[ValueKind::Boolean]) {
+            return (DataType::Boolean, None);
+        }
+        if only(&[ValueKind::Integer]) {
+            return (DataType::Int64, None);
+        }
+        if only(&[ValueKind::Integer, ValueKind::Float]) {
+            let reason = match first.kind {
+                ValueKind::Integer => self.find(ValueKind::Float).map(|s| format!("found {}", s)),
+                _ => None,
+            };
+            return (DataType::Float64, reason);
+        }
+
+        let reason = if let Some(text) = self.find(ValueKind::Text) {
+            format!("found {}", text)
+        } else if let Some(temporal) = self.find(ValueKind::Temporal) {
+            format!("found {}, and temporal types are not supported", temporal)
+        } else {
+            // Only booleans mixed with numbers remain
+            let boolean = self.find(ValueKind::Boolean).unwrap();
+            let number = self
+                .samples
+                .iter()
+                .find(|s| s.kind != ValueKind::Boolean)
+                .unwrap();
+            format!("mixes {} and {}", boolean, number)
+        };
+        (DataType::Utf8, Some(reason))
+    }
+}
+
+/// Infers a schema from up to `max_records` records of `reader`.
+pub(super) fn infer_schema<R: Read>(
+    mut reader: csv::Reader<R>,
+    header: bool,
+    null_regex: Option<&Regex>,
+    max_records: Option<usize>,
+) -> Result<SchemaInference, String> {
+    let first = reader.headers().map_err(|e| e.to_string())?;
+    let names: Vec<String> = if header {
+        first.iter().map(|s| s.to_string()).collect()
+    } else {
+        (0..first.len())
+            .map(|i| format!("column_{}", i + 1))
+            .collect()
+    };
+
+    let is_null = |value: &str| match null_regex {
+        Some(regex) => regex.is_match(value),
+        None => value.is_empty(),
+    };
+
+    let mut columns: Vec<ColumnInference> = names.iter().map(|_| Default::default()).collect();
+    let mut records_read = 0;
+    let mut record = csv::StringRecord::new();
+    while records_read < max_records.unwrap_or(usize::MAX) {
+        if !reader.read_record(&mut record).map_err(|e| e.to_string())? {
+            break;
+        }
+        records_read += 1;
+
+        let line = record.position().map_or(0, |p| p.line());
+        for (column, value) in columns.iter_mut().zip(record.iter()) {
+            if !is_null(value) {
+                column.update(value, line);
+            }
+        }
+    }
+
+    let mut fields = Vec::with_capacity(names.len());
+    let mut diagnostics = Vec::new();
+    for (name, column) in names.iter().zip(&columns) {
+        let (dtype, reason) = column.resolve();
+        if let Some(reason) = reason {
+            diagnostics.push(InferenceDiagnostic {
+                column: name.clone(),
+                dtype: dtype.clone(),
+                reason,
+            });
+        }
+        fields.push(Field::new(name, dtype));
+    }
+
+    Ok(SchemaInference {
+        schema: Schema::new(fields),
+        records_read,
+        diagnostics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn infer(data: &str) -> SchemaInference {
+        let reader = csv::ReaderBuilder::new().from_reader(data.as_bytes());
+        infer_schema(reader, true, None, None).unwrap()
+    }
+
+    #[test]
+    fn test_infer_types() {
+        let inference = infer("a,b,c,d\n1,1.5,true,x\n2,,false,y\n");
+        let dtypes: Vec<_> = inference
+            .schema
+            .fields()
+            .iter()
+            .map(|f| f.dtype().clone())
+            .collect();
+
+        assert_eq!(
+            dtypes,
+            vec![
+                DataType::Int64,
+                DataType::Float64,
+                DataType::Boolean,
+                DataType::Utf8
+            ]
+        );
+        assert_eq!(inference.records_read, 2);
+        assert!(inference.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let inference = infer("a,b,c,d,e\n1,1,true,2024-01-01,\n2.5,n/a,1,x,\n");
+        let reasons: Vec<_> = inference
+            .diagnostics
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+
+        assert_eq!(
+            reasons,
+            vec![
+                r#"a inferred as Float64: found float value "2.5" on line 3"#,
+                r#"b inferred as Utf8: found text value "n/a" on line 3"#,
+                r#"c inferred as Utf8: mixes boolean value "true" on line 2 and integer value "1" on line 3"#,
+                r#"d inferred as Utf8: found text value "x" on line 3"#,
+                "e inferred as Utf8: no non-null values in the sampled records",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_records() {
+        let reader = csv::ReaderBuilder::new().from_reader("a\n1\nx\n".as_bytes());
+        let inference = infer_schema(reader, true, None, Some(1)).unwrap();
+
+        assert_eq!(inference.records_read, 1);
+        assert_eq!(inference.schema.fields()[0].dtype(), &DataType::Int64);
+    }
+}
@@ -0,0 +1,769 @@
+//! Delta Lake table reader.
+//!
+//! A Delta table is a directory of Parquet files with a `_delta_log` of
+//! numbered JSON commits, each adding and removing files. Replaying the
+//! commits up to a version gives the files of the table at that version.
+//! Checkpoints are not read, so the log must hold every commit from version
+//! 0, and only reader protocol version 1 is supported.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use dbms_dtype::{Column, DataType, Field, RecordBatch, Scalar, Schema};
+use serde_json::{Map, Value};
+
+use crate::object_store::local_object;
+use crate::pruning::may_match_all;
+use crate::{
+    BatchIterator, ColumnFilter, ColumnStatistics, DataSource, ObjectStore, ParquetDataSource,
+    Precision, Statistics, check_partition, resolve_projection,
+};
+
+/// A data source over a Delta table at its latest or a given version.
+///
+/// Partition columns are filled in from the values recorded in the log.
+/// Files that cannot hold rows matching the filters, judged by their
+/// partition values and column statistics, are skipped.
+///
+/// The log is listed on every call, but replayed only when the version read
+/// changes.
+pub struct DeltaDataSource {
+    store: Arc<dyn ObjectStore>,
+    location: String,
+    version: Option<u64>,
+    batch_size: usize,
+    filters: Vec<ColumnFilter>,
+    snapshot: Mutex<Option<Arc<Snapshot>>>,
+}
+
+impl DeltaDataSource {
+    /// Creates a source over the Delta table in a local directory.
+    pub fn new(path: impl Into<PathBuf>, batch_size: usize) -> Self {
+        let (store, location) = local_object(&path.into());
+        Self::from_store(store, location, batch_size)
+    }
+
+    /// Creates a source over the Delta table under `location` in `store`.
+    pub fn from_store(
+        store: Arc<dyn ObjectStore>,
+        location: impl Into<String>,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            store,
+            location: location.into(),
+            version: None,
+            batch_size,
+            filters: Vec::new(),
+            snapshot: Mutex::new(None),
+        }
+    }
+
+    /// Reads the table as of a version instead of the latest one.
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = Some(version);
+        self.snapshot = Mutex::new(None);
+        self
+    }
+
+    /// Skips files that cannot hold rows matching every filter. Rows of the
+    /// files read are not filtered.
+    pub fn with_filters(mut self, filters: Vec<ColumnFilter>) -> Self {
+        self.filters = filters;
+        self.snapshot = Mutex::new(None);
+        self
+    }
+
+    /// Returns the version of the table that is read.
+    pub fn version(&self) -> Result<u64, String> {
+        Ok(self.snapshot()?.version)
+    }
+
+    /// Returns the locations of the data files that are read, after pruning.
+    pub fn data_files(&self) -> Result<Vec<String>, String> {
+        Ok(self
+            .snapshot()?
+            .files
+            .iter()
+            .map(|file| file.location.clone())
+            .collect())
+    }
+
+    /// Returns the location of `path` relative to the table root.
+    fn child(&self, path: &str) -> String {
+        match self.location.trim_end_matches('/') {
+            "" => path.to_string(),
+            root => format!("{}/{}", root, path),
+        }
+    }
+
+    /// Replays the log up to the requested version and prunes its files,
+    /// reusing the previous result if it is of the same version.
+    fn snapshot(&self) -> Result<Arc<Snapshot>, String> {
+        let log = self.child("_delta_log/");
+        let commits: Vec<(u64, String)> = self
+            .store
+            .list(&log)?
+            .into_iter()
+            .filter_map(|meta| {
+                let name = meta.location.strip_prefix(&log)?.strip_suffix(".json")?;
+                let version = name.parse().ok().filter(|_| name.len() == 20)?;
+                Some((version, meta.location))
+            })
+            .collect();
+
+        let Some(&(latest, _)) = commits.last() else {
+            return Err(format!("{}: not a Delta table", self.location));
+        };
+        let version = self.version.unwrap_or(latest);
+        if version > latest {
+            return Err(format!(
+                "version {} not found; latest version is {}",
+                version, latest
+            ));
+        }
+        for (expected, (version, _)) in commits.iter().enumerate() {
+            if *version != expected as u64 {
+                return Err(format!(
+                    "Delta log is missing version {}; checkpoints are not supported",
+                    expected
+                ));
+            }
+        }
+
+        let mut cached = self.snapshot.lock().map_err(|e| e.to_string())?;
+        if let Some(snapshot) = cached.as_ref()
+            && snapshot.version == version
+        {
+            return Ok(snapshot.clone());
+        }
+
+        let mut log = LogReplay::default();
+        for (_, location) in &commits[..=version as usize] {
+            let data = self.store.get(location)?;
+            let text = std::str::from_utf8(&data).map_err(|e| format!("{}: {}", location, e))?;
+            for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                let action: Map<String, Value> =
+                    serde_json::from_str(line).map_err(|e| format!("{}: {}", location, e))?;
+                log.apply(&action)
+                    .map_err(|e| format!("{}: {}", location, e))?;
+            }
+        }
+        let (schema, partition_columns) = log
+            .metadata
+            .ok_or_else(|| format!("{}: Delta log has no metadata", self.location))?;
+
+        let mut files = Vec::with_capacity(log.files.len());
+        for (path, add) in log.files {
+            let file = DataFile::try_new(self.child(&path), &add, &schema, &partition_columns)?;
+            let column_index = |name: &str| schema.fields().iter().position(|f| f.name() == name);
+            if may_match_all(&self.filters, &file.statistics, column_index) {
+                files.push(file);
+            }
+        }
+        let snapshot = Arc::new(Snapshot {
+            version,
+            schema,
+            files,
+        });
+        *cached = Some(snapshot.clone());
+        Ok(snapshot)
+    }
+
+    /// Reads the given files, filling in partition columns.
+    fn read(
+        &self,
+        schema: &Schema,
+        files: Vec<DataFile>,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let indices = match projection {
+            Some(cols) => resolve_projection(schema, cols)?,
+            None => (0..schema.fields().len()).collect(),
+        };
+        let projected = schema.project(&indices);
+        let Some(file) = files.first() else {
+            return Ok(Box::new(std::iter::empty()));
+        };
+
+        let data_columns: Vec<usize> = (0..schema.fields().len())
+            .filter(|&i| file.partition_values[i].is_none())
+            .collect();
+        let data_schema = schema.project(&data_columns);
+        // Rows are counted from a data column even if only partition
+        // columns are projected
+        let mut read: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|i| data_columns.contains(i))
+            .collect();
+        if read.is_empty() {
+            read.extend(data_columns.first());
+        }
+        let names: Vec<String> = read
+            .iter()
+            .map(|&i| schema.fields()[i].name().to_string())
+            .collect();
+
+        let store = self.store.clone();
+        let batch_size = self.batch_size;
+        Ok(Box::new(files.into_iter().flat_map(
+            move |file| -> BatchIterator {
+                let names: Vec<&str> = names.iter().map(String::as_str).collect();
+                let source =
+                    ParquetDataSource::from_store(store.clone(), &file.location, batch_size)
+                        .with_table_schema(data_schema.clone());
+                let batches = match source.scan(Some(&names)) {
+                    Ok(batches) => batches,
+                    Err(e) => return Box::new(std::iter::once(Err(e))),
+                };
+                let (indices, read, projected) = (indices.clone(), read.clone(), projected.clone());
+                Box::new(batches.map(move |batch| {
+                    let batch = batch?;
+                    let rows = batch.row_count();
+                    let columns = indices
+                        .iter()
+                        .map(|&i| match &file.partition_values[i] {
+                            Some(value) => Column::from_literal(value.clone(), rows),
+                            None => {
+                                let position = read.iter().position(|&r| r == i).unwrap();
+                                batch.field(position).clone()
+                            }
+                        })
+                        .collect();
+                    Ok(RecordBatch::new(projected.clone(), columns))
+                }))
+            },
+        )))
+    }
+}
+
+impl DataSource for DeltaDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        Ok(self.snapshot()?.schema.clone())
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        let snapshot = self.snapshot()?;
+        self.read(&snapshot.schema, snapshot.files.clone(), projection)
+    }
+
+    /// Each data file is a partition.
+    fn partition_count(&self) -> Result<usize, String> {
+        Ok(self.snapshot()?.files.len().max(1))
+    }
+
+    fn scan_partition(
+        &self,
+        partition: usize,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let snapshot = self.snapshot()?;
+        check_partition(partition, snapshot.files.len().max(1))?;
+        // Without any files the single partition is empty
+        let files = snapshot.files.get(partition).cloned().into_iter().collect();
+        self.read(&snapshot.schema, files, projection)
+    }
+
+    /// Combines the statistics recorded in the log for each file, without
+    /// reading any data.
+    fn statistics(&self) -> Result<Statistics, String> {
+        let snapshot = self.snapshot()?;
+        let empty = Statistics {
+            num_rows: Precision::Exact(0),
+            total_byte_size: Precision::Exact(0),
+            column_statistics: vec![ColumnStatistics::default(); snapshot.schema.fields().len()],
+        };
+        Ok(snapshot
+            .files
+            .iter()
+            .map(|file| file.statistics.clone())
+            .reduce(Statistics::merge)
+            .unwrap_or(empty))
+    }
+}
+
+/// The table at one version.
+struct Snapshot {
+    version: u64,
+    schema: Schema,
+    files: Vec<DataFile>,
+}
+
+/// The state built up by replaying commits.
+#[derive(Default)]
+struct LogReplay {
+    /// Table schema and partition columns.
+    metadata: Option<(Schema, Vec<String>)>,
+    /// Add actions of the active files, by path.
+    files: BTreeMap<String, Map<String, Value>>,
+}
+
+impl LogReplay {
+    fn apply(&mut self, action: &Map<String, Value>) -> Result<(), String> {
+        if let Some(protocol) = action.get("protocol") {
+            let version = protocol["minReaderVersion"].as_u64().unwrap_or(1);
+            let features = protocol["readerFeatures"].as_array().map_or(0, Vec::len);
+            if version > 1 && !(version == 3 && features == 0) {
+                return Err(format!("unsupported Delta reader version: {}", version));
+            }
+        }
+        if let Some(metadata) = action.get("metaData") {
+            let schema = metadata["schemaString"]
+                .as_str()
+                .ok_or("metaData has no schemaString")?;
+            let schema: Value = serde_json::from_str(schema).map_err(|e| e.to_string())?;
+            let partition_columns = metadata["partitionColumns"]
+                .as_array()
+                .map(|columns| {
+                    columns
+                        .iter()
+                        .filter_map(|c| c.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+            self.metadata = Some((parse_schema(&schema)?, partition_columns));
+        }
+        if let Some(add) = action.get("add").and_then(Value::as_object) {
+            let path = add
+                .get("path")
+                .and_then(Value::as_str)
+                .ok_or("add action has no path")?;
+            self.files.insert(decode_path(path)?, add.clone());
+        }
+        if let Some(remove) = action.get("remove") {
+            let path = remove["path"].as_str().ok_or("remove action has no path")?;
+            self.files.remove(&decode_path(path)?);
+        }
+        Ok(())
+    }
+}
+
+/// Converts a Delta schema to a table schema.
+fn parse_schema(schema: &Value) -> Result<Schema, String> {
+    let fields = schema["fields"].as_array().ok_or("schema has no fields")?;
+    let fields = fields
+        .iter()
+        .map(|field| {
+            let name = field["name"].as_str().ok_or("schema field has no name")?;
+            let dtype = match field["type"].as_str() {
+                Some("boolean") => DataType::Boolean,
+                Some("byte") => DataType::Int8,
+                Some("short") => DataType::Int16,
+                Some("integer") => DataType::Int32,
+                Some("long") => DataType::Int64,
+                Some("float") => DataType::Float32,
+                Some("double") => DataType::Float64,
+                Some("string") => DataType::Utf8,
+                Some("binary") => DataType::Binary,
+                _ => {
+                    return Err(format!(
+                        "unsupported Delta type for column {}: {}",
+                        name, field["type"]
+                    ));
+                }
+            };
+            Ok(Field::new(name, dtype))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(Schema::new(fields))
+}
+
+/// Decodes the percent-encoded path of a data file relative to the table.
+fn decode_path(path: &str) -> Result<String, String> {
+    if path.contains("://") {
+        return Err(format!("unsupported data file path: {}", path));
+    }
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = match bytes[i] {
+            b'%' => {
+                let hex = path.get(i + 1..i + 3).unwrap_or_default();
+                let byte = u8::from_str_radix(hex, 16)
+                    .map_err(|_| format!("invalid data file path: {}", path))?;
+                i += 2;
+                byte
+            }
+            byte => byte,
+        };
+        decoded.push(byte);
+        i += 1;
+    }
+    String::from_utf8(decoded).map_err(|_| format!("invalid data file path: {}", path))
+}
+
+/// An active data file of a snapshot.
+#[derive(Clone)]
+struct DataFile {
+    location: String,
+    /// The value of each partition column, by table column.
+    partition_values: Vec<Option<Scalar>>,
+    statistics: Statistics,
+}
+
+impl DataFile {
+    fn try_new(
+        location: String,
+        add: &Map<String, Value>,
+        schema: &Schema,
+        partition_columns: &[String],
+    ) -> Result<Self, String> {
+        let stats: Value = match add.get("stats").and_then(Value::as_str) {
+            Some(stats) => {
+                serde_json::from_str(stats).map_err(|e| format!("{}: {}", location, e))?
+            }
+            None => Value::Null,
+        };
+        let num_rows = match stats["numRecords"].as_u64() {
+            Some(rows) => Precision::Exact(rows as usize),
+            None => Precision::Absent,
+        };
+
+        let mut partition_values = Vec::with_capacity(schema.fields().len());
+        let mut column_statistics = Vec::with_capacity(schema.fields().len());
+        for field in schema.fields() {
+            let name = field.name();
+            if partition_columns.iter().any(|c| c == name) {
+                let value = add
+                    .get("partitionValues")
+                    .and_then(|values| values.get(name));
+                let value = match value.and_then(Value::as_str) {
+                    None | Some("") => Scalar::null(field.dtype().clone()),
+                    Some(value) => {
+                        parse_partition_value(value, field.dtype()).ok_or_else(|| {
+                            format!("invalid partition value for column {}: {}", name, value)
+                        })?
+                    }
+                };
+                let is_null = value == Scalar::null(field.dtype().clone());
+                column_statistics.push(ColumnStatistics {
+                    null_count: match is_null {
+                        true => num_rows.clone(),
+                        false => Precision::Exact(0),
+                    },
+                    min_value: Precision::Exact(value.clone()),
+                    max_value: Precision::Exact(value.clone()),
+                    distinct_count: Precision::Exact(usize::from(!is_null)),
+                });
+                partition_values.push(Some(value));
+            } else {
+                let bound = |key: &str| match json_value(&stats[key][name], field.dtype()) {
+                    // Long strings may be truncated
+                    Some(value) if *field.dtype() == DataType::Utf8 => Precision::Inexact(value),
+                    Some(value) => Precision::Exact(value),
+                    None => Precision::Absent,
+                };
+                column_statistics.push(ColumnStatistics {
+                    null_count: match stats["nullCount"][name].as_u64() {
+                        Some(nulls) => Precision::Exact(nulls as usize),
+                        None => Precision::Absent,
+                    },
+                    min_value: bound("minValues"),
+                    max_value: bound("maxValues"),
+                    distinct_count: Precision::Absent,
+                });
+                partition_values.push(None);
+            }
+        }
+
+        let total_byte_size = match add.get("size").and_then(Value::as_u64) {
+            Some(size) => Precision::Exact(size as usize),
+            None => Precision::Absent,
+        };
+        Ok(Self {
+            location,
+            partition_values,
+            statistics: Statistics {
+                num_rows,
+                total_byte_size,
+                column_statistics,
+            },
+        })
+    }
+}
+
+/// Parses a partition value, which the log records as a string.
+fn parse_partition_value(value: &str, dtype: &DataType) -> Option<Scalar> {
+    Some(match dtype {
+        DataType::Boolean => Scalar::Boolean(Some(value.parse().ok()?)),
+        DataType::Int8 => Scalar::Int8(Some(value.parse().ok()?)),
+        DataType::Int16 => Scalar::Int16(Some(value.parse().ok()?)),
+        DataType::Int32 => Scalar::Int32(Some(value.parse().ok()?)),
+        DataType::Int64 => Scalar::Int64(Some(value.parse().ok()?)),
+        DataType::UInt8 => Scalar::UInt8(Some(value.parse().ok()?)),
+        DataType::UInt16 => Scalar::UInt16(Some(value.parse().ok()?)),
+        DataType::UInt32 => Scalar::UInt32(Some(value.parse().ok()?)),
+        DataType::UInt64 => Scalar::UInt64(Some(value.parse().ok()?)),
+        DataType::Float32 => Scalar::Float32(Some(value.parse().ok()?)),
+        DataType::Float64 => Scalar::Float64(Some(value.parse().ok()?)),
+        DataType::Utf8 => Scalar::Utf8(Some(value.to_string())),
+        DataType::Binary => Scalar::Binary(Some(value.as_bytes().to_vec())),
+    })
+}
+
+/// Converts a statistics value from the log, if it has the column's type.
+fn json_value(value: &Value, dtype: &DataType) -> Option<Scalar> {
+    Some(match dtype {
+        DataType::Boolean => Scalar::Boolean(Some(value.as_bool()?)),
+        DataType::Int8 => Scalar::Int8(Some(value.as_i64()?.try_into().ok()?)),
+        DataType::Int16 => Scalar::Int16(Some(value.as_i64()?.try_into().ok()?)),
+        DataType::Int32 => Scalar::Int32(Some(value.as_i64()?.try_into().ok()?)),
+        DataType::Int64 => Scalar::Int64(Some(value.as_i64()?)),
+        DataType::UInt8 => Scalar::UInt8(Some(value.as_u64()?.try_into().ok()?)),
+        DataType::UInt16 => Scalar::UInt16(Some(value.as_u64()?.try_into().ok()?)),
+        DataType::UInt32 => Scalar::UInt32(Some(value.as_u64()?.try_into().ok()?)),
+        DataType::UInt64 => Scalar::UInt64(Some(value.as_u64()?)),
+        DataType::Float32 => Scalar::Float32(Some(value.as_f64()? as f32)),
+        DataType::Float64 => Scalar::Float64(Some(value.as_f64()?)),
+        DataType::Utf8 => Scalar::Utf8(Some(value.as_str()?.to_string())),
+        DataType::Binary => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FilterOp, InMemoryObjectStore, conformance};
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{DataType as ArrowDataType, Field as ArrowField, Schema as ArrowSchema};
+    use arrow::record_batch::RecordBatch as ArrowRecordBatch;
+    use parquet::arrow::ArrowWriter;
+
+    const METADATA: &str = r#"{"metaData":{"id":"t","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"id\",\"type\":\"long\",\"nullable\":true,\"metadata\":{}},{\"name\":\"name\",\"type\":\"string\",\"nullable\":true,\"metadata\":{}},{\"name\":\"region\",\"type\":\"string\",\"nullable\":true,\"metadata\":{}}]}","partitionColumns":["region"],"configuration":{}}}"#;
+
+    /// Writes a data file holding ids `ids` and returns its add action.
+    fn add(store: &InMemoryObjectStore, path: &str, region: &str, ids: &[i64]) -> String {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", ArrowDataType::Int64, true),
+            ArrowField::new("name", ArrowDataType::Utf8, true),
+        ]));
+        let names: Vec<String> = ids.iter().map(|id| format!("n{}", id)).collect();
+        let batch = ArrowRecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(ids.to_vec())),
+                Arc::new(StringArray::from(names)),
+            ],
+        )
+        .unwrap();
+        let mut data = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut data, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let size = data.len();
+        store
+            .put(&format!("t/{}", decode_path(path).unwrap()), data.into())
+            .unwrap();
+
+        let stats = serde_json::json!({
+            "numRecords": ids.len(),
+            "minValues": {"id": ids.iter().min()},
+            "maxValues": {"id": ids.iter().max()},
+            "nullCount": {"id": 0, "name": 0},
+        });
+        serde_json::json!({"add": {
+            "path": path,
+            "partitionValues": {"region": region},
+            "size": size,
+            "modificationTime": 0,
+            "dataChange": true,
+            "stats": stats.to_string(),
+        }})
+        .to_string()
+    }
+
+    fn commit(store: &InMemoryObjectStore, version: u64, actions: &[String]) {
+        let location = format!("t/_delta_log/{:020}.json", version);
+        store.put(&location, actions.join("\n").into()).unwrap();
+    }
+
+    /// A table of three commits: two appends and a rewrite of the first file.
+    fn table() -> Arc<InMemoryObjectStore> {
+        let store = Arc::new(InMemoryObjectStore::new());
+        let protocol = r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}"#;
+        let a = add(&store, "region=eu/a.parquet", "eu", &[1, 2]);
+        commit(&store, 0, &[protocol.to_string(), METADATA.to_string(), a]);
+        let b = add(&store, "region=us%20east/b.parquet", "us east", &[3, 4, 5]);
+        commit(&store, 1, &[b]);
+        let remove = r#"{"remove":{"path":"region=eu/a.parquet","dataChange":true}}"#;
+        let c = add(&store, "region=eu/c.parquet", "eu", &[10, 20]);
+        commit(&store, 2, &[remove.to_string(), c]);
+        store
+    }
+
+    fn ids(source: &DeltaDataSource) -> Vec<Scalar> {
+        let mut ids: Vec<_> = source
+            .scan(Some(&["id"]))
+            .unwrap()
+            .flat_map(|batch| {
+                let batch = batch.unwrap();
+                (0..batch.row_count())
+                    .map(|i| batch.field(0).get(i))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        ids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ids
+    }
+
+    fn int64s(values: &[i64]) -> Vec<Scalar> {
+        values.iter().map(|&v| Scalar::Int64(Some(v))).collect()
+    }
+
+    #[test]
+    fn test_versions() {
+        let store = table();
+        let latest = DeltaDataSource::from_store(store.clone(), "t", 1024);
+        assert_eq!(latest.version().unwrap(), 2);
+        assert_eq!(
+            latest.data_files().unwrap(),
+            ["t/region=eu/c.parquet", "t/region=us east/b.parquet"]
+        );
+        assert_eq!(ids(&latest), int64s(&[3, 4, 5, 10, 20]));
+
+        let first = DeltaDataSource::from_store(store.clone(), "t", 1024).with_version(0);
+        assert_eq!(ids(&first), int64s(&[1, 2]));
+        assert_eq!(
+            DeltaDataSource::from_store(store, "t", 1024)
+                .with_version(3)
+                .version()
+                .unwrap_err(),
+            "version 3 not found; latest version is 2"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_cache() {
+        let store = table();
+        let source = DeltaDataSource::from_store(store.clone(), "t", 1024);
+        assert_eq!(ids(&source), int64s(&[3, 4, 5, 10, 20]));
+
+        // The log is not replayed again while the version is unchanged
+        store
+            .put("t/_delta_log/00000000000000000001.json", "{".into())
+            .unwrap();
+        assert_eq!(source.partition_count().unwrap(), 2);
+        assert_eq!(ids(&source), int64s(&[3, 4, 5, 10, 20]));
+
+        // A new commit is replayed from the start of the log
+        commit(
+            &store,
+            3,
+            &[add(&store, "region=eu/d.parquet", "eu", &[30])],
+        );
+        let err = source.version().unwrap_err();
+        assert!(
+            err.starts_with("t/_delta_log/00000000000000000001.json"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_partition_values() {
+        let source = DeltaDataSource::from_store(table(), "t", 1024);
+        let schema = source.schema().unwrap();
+        assert_eq!(schema.fields()[2], Field::new("region", DataType::Utf8));
+
+        // Only a partition column is projected
+        let mut regions: Vec<_> = source
+            .scan(Some(&["region"]))
+            .unwrap()
+            .map(|batch| {
+                let batch = batch.unwrap();
+                (batch.row_count(), batch.field(0).get(0))
+            })
+            .collect();
+        regions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            regions,
+            [
+                (2, Scalar::Utf8(Some("eu".to_string()))),
+                (3, Scalar::Utf8(Some("us east".to_string())))
+            ]
+        );
+
+        let stats = source.statistics().unwrap();
+        assert_eq!(stats.num_rows, Precision::Exact(5));
+        assert_eq!(
+            stats.column_statistics[0].max_value,
+            Precision::Exact(Scalar::Int64(Some(20)))
+        );
+    }
+
+    #[test]
+    fn test_pruning() {
+        let source =
+            |filter| DeltaDataSource::from_store(table(), "t", 1024).with_filters(vec![filter]);
+        let eu = ColumnFilter::new("region", FilterOp::Eq(Scalar::Utf8(Some("eu".to_string()))));
+        assert_eq!(source(eu).data_files().unwrap(), ["t/region=eu/c.parquet"]);
+
+        // Files are skipped by their statistics, not filtered by row
+        let small = source(ColumnFilter::new(
+            "id",
+            FilterOp::Lt(Scalar::Int64(Some(4))),
+        ));
+        assert_eq!(ids(&small), int64s(&[3, 4, 5]));
+        assert_eq!(small.partition_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_truncated_string_statistics() {
+        // The writer truncated the name bound "n1" to "n"
+        let store = Arc::new(InMemoryObjectStore::new());
+        let mut action: Value =
+            serde_json::from_str(&add(&store, "region=eu/a.parquet", "eu", &[1])).unwrap();
+        let stats = serde_json::json!({
+            "numRecords": 1,
+            "minValues": {"name": "n"},
+            "maxValues": {"name": "n"},
+            "nullCount": {"name": 0},
+        });
+        action["add"]["stats"] = Value::String(stats.to_string());
+        commit(&store, 0, &[METADATA.to_string(), action.to_string()]);
+
+        let filter = ColumnFilter::new("name", FilterOp::Gt(Scalar::Utf8(Some("n0".to_string()))));
+        let source = DeltaDataSource::from_store(store, "t", 1024).with_filters(vec![filter]);
+        assert_eq!(ids(&source), int64s(&[1]));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let store = Arc::new(InMemoryObjectStore::new());
+        let protocol = r#"{"protocol":{"minReaderVersion":2,"minWriterVersion":5}}"#;
+        commit(&store, 0, &[protocol.to_string(), METADATA.to_string()]);
+        let err = DeltaDataSource::from_store(store.clone(), "t", 1024)
+            .schema()
+            .unwrap_err();
+        assert!(
+            err.ends_with("unsupported Delta reader version: 2"),
+            "{}",
+            err
+        );
+
+        assert_eq!(
+            DeltaDataSource::from_store(store, "missing", 1024)
+                .schema()
+                .unwrap_err(),
+            "missing: not a Delta table"
+        );
+    }
+
+    #[test]
+    fn test_conformance() {
+        conformance::check(&DeltaDataSource::from_store(table(), "t", 1));
+    }
+
+    #[test]
+    fn test_decode_path() {
+        assert_eq!(
+            decode_path("a%3Db/c%20d.parquet").unwrap(),
+            "a=b/c d.parquet"
+        );
+        assert!(decode_path("a%zz").is_err());
+        assert!(decode_path("s3://bucket/a.parquet").is_err());
+    }
+}
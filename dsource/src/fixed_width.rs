@@ -0,0 +1,482 @@
+//! Fixed-width text file data source implementation.
+//!
+//! Each line holds one record, and each column is read from the same byte
+//! range of every line, as described by a layout. Offsets and widths are in
+//! bytes, so multi-byte characters must not straddle a column boundary.
+
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BinaryArray, BooleanArray, PrimitiveArray, StringArray};
+use arrow::datatypes::{
+    ArrowPrimitiveType, Float32Type, Float64Type, Int8Type, Int16Type, Int32Type, Int64Type,
+    UInt8Type, UInt16Type, UInt32Type, UInt64Type,
+};
+use dbms_dtype::{Column, DataType, Field, RecordBatch, Schema};
+
+use crate::object_store::local_object;
+use crate::{
+    BatchIterator, DataSource, FileCompression, ObjectReader, ObjectStore, Precision, Statistics,
+    resolve_projection,
+};
+
+/// Which whitespace is removed from a field before it is parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Trim {
+    /// The field is read as is.
+    None,
+    /// Leading whitespace is removed.
+    Left,
+    /// Trailing whitespace is removed.
+    Right,
+    /// Leading and trailing whitespace is removed.
+    #[default]
+    Both,
+}
+
+impl Trim {
+    fn apply(self, field: &[u8]) -> &[u8] {
+        match self {
+            Self::None => field,
+            Self::Left => field.trim_ascii_start(),
+            Self::Right => field.trim_ascii_end(),
+            Self::Both => field.trim_ascii(),
+        }
+    }
+}
+
+/// A column of a fixed-width layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedWidthColumn {
+    pub name: String,
+    /// Byte offset of the column from the start of the line.
+    pub start: usize,
+    /// Width of the column in bytes.
+    pub width: usize,
+    pub dtype: DataType,
+    /// Whitespace removed before parsing, from both ends by default.
+    pub trim: Trim,
+}
+
+impl FixedWidthColumn {
+    pub fn new(name: impl Into<String>, start: usize, width: usize, dtype: DataType) -> Self {
+        Self {
+            name: name.into(),
+            start,
+            width,
+            dtype,
+            trim: Trim::default(),
+        }
+    }
+
+    pub fn with_trim(mut self, trim: Trim) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Returns the column's field in a line, or an empty field if the line
+    /// ends before the column.
+    fn field<'a>(&self, line: &'a [u8]) -> &'a [u8] {
+        let start = self.start.min(line.len());
+        let end = self.start.saturating_add(self.width).min(line.len());
+        self.trim.apply(&line[start..end])
+    }
+}
+
+/// Options controlling how fixed-width files are read.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FixedWidthOptions {
+    /// Number of lines skipped at the start of the file, such as headers.
+    pub skip_lines: usize,
+    /// Lines starting with this character are skipped.
+    pub comment: Option<u8>,
+    /// Compression of the file, inferred from its extension if unset.
+    pub compression: Option<FileCompression>,
+}
+
+impl FixedWidthOptions {
+    pub fn with_skip_lines(mut self, skip_lines: usize) -> Self {
+        self.skip_lines = skip_lines;
+        self
+    }
+
+    pub fn with_comment(mut self, comment: u8) -> Self {
+        self.comment = Some(comment);
+        self
+    }
+
+    pub fn with_compression(mut self, compression: FileCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+}
+
+/// A data source that reads from fixed-width text files.
+///
+/// Empty fields, after trimming, are read as null, and so are columns past
+/// the end of a short line. Blank lines are skipped.
+pub struct FixedWidthDataSource {
+    store: Arc<dyn ObjectStore>,
+    location: String,
+    columns: Vec<FixedWidthColumn>,
+    batch_size: usize,
+    options: FixedWidthOptions,
+}
+
+impl FixedWidthDataSource {
+    pub fn new(
+        path: impl Into<PathBuf>,
+        columns: Vec<FixedWidthColumn>,
+        batch_size: usize,
+    ) -> Self {
+        let (store, location) = local_object(&path.into());
+        Self::from_store(store, location, columns, batch_size)
+    }
+
+    /// Creates a source reading the object at `location` in `store`.
+    pub fn from_store(
+        store: Arc<dyn ObjectStore>,
+        location: impl Into<String>,
+        columns: Vec<FixedWidthColumn>,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            store,
+            location: location.into(),
+            columns,
+            batch_size,
+            options: FixedWidthOptions::default(),
+        }
+    }
+
+    /// Sets the options used to read the file.
+    pub fn with_options(mut self, options: FixedWidthOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn columns(&self) -> &[FixedWidthColumn] {
+        &self.columns
+    }
+
+    /// Opens the file for reading, decompressing it if necessary.
+    fn open(&self) -> Result<Box<dyn Read + Send>, String> {
+        let meta = self.store.head(&self.location)?;
+        let compression = self
+            .options
+            .compression
+            .unwrap_or_else(|| FileCompression::from_path(Path::new(&self.location)));
+        compression.decode(ObjectReader::new(self.store.clone(), &meta).buffered())
+    }
+}
+
+impl DataSource for FixedWidthDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        let mut fields: Vec<Field> = Vec::with_capacity(self.columns.len());
+        for column in &self.columns {
+            if column.width == 0 {
+                return Err(format!("column {} has zero width", column.name));
+            }
+            if column.start.checked_add(column.width).is_none() {
+                return Err(format!(
+                    "column {} ends past the largest offset",
+                    column.name
+                ));
+            }
+            if fields.iter().any(|f| f.name() == column.name) {
+                return Err(format!("duplicate column name: {}", column.name));
+            }
+            fields.push(Field::new(&column.name, column.dtype.clone()));
+        }
+        Ok(Schema::new(fields))
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        let schema = self.schema()?;
+        let indices = match projection {
+            Some(cols) => resolve_projection(&schema, cols)?,
+            None => (0..schema.fields().len()).collect(),
+        };
+
+        Ok(Box::new(FixedWidthBatchIterator {
+            reader: BufReader::new(self.open()?),
+            columns: indices.iter().map(|&i| self.columns[i].clone()).collect(),
+            schema: schema.project(&indices),
+            batch_size: self.batch_size.max(1),
+            skip_lines: self.options.skip_lines,
+            comment: self.options.comment,
+            line: 0,
+            done: false,
+        }))
+    }
+
+    fn statistics(&self) -> Result<Statistics, String> {
+        let mut statistics = Statistics::unknown(&self.schema()?);
+        let size = self.store.head(&self.location)?.size as usize;
+        statistics.total_byte_size = Precision::Exact(size);
+        Ok(statistics)
+    }
+}
+
+struct FixedWidthBatchIterator {
+    reader: BufReader<Box<dyn Read + Send>>,
+    /// Layout of the projected columns, in output order.
+    columns: Vec<FixedWidthColumn>,
+    schema: Schema,
+    batch_size: usize,
+    skip_lines: usize,
+    comment: Option<u8>,
+    /// Number of lines read so far.
+    line: usize,
+    done: bool,
+}
+
+impl FixedWidthBatchIterator {
+    /// Reads up to a batch of records, with the line number of each.
+    fn read_lines(&mut self) -> Result<Vec<(usize, Vec<u8>)>, String> {
+        let mut lines = Vec::with_capacity(self.batch_size);
+        while lines.len() < self.batch_size {
+            let mut line = Vec::new();
+            if self
+                .reader
+                .read_until(b'\n', &mut line)
+                .map_err(|e| e.to_string())?
+                == 0
+            {
+                self.done = true;
+                break;
+            }
+            self.line += 1;
+            if line.ends_with(b"\n") {
+                line.pop();
+            }
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+            if self.line <= self.skip_lines
+                || line.trim_ascii().is_empty()
+                || self.comment.is_some_and(|c| line.first() == Some(&c))
+            {
+                continue;
+            }
+            lines.push((self.line, line));
+        }
+        Ok(lines)
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, String> {
+        let lines = self.read_lines()?;
+        if lines.is_empty() {
+            return Ok(None);
+        }
+        let columns = self
+            .columns
+            .iter()
+            .map(|column| parse_column(column, &lines).map(Column::Array))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(RecordBatch::new(self.schema.clone(), columns)))
+    }
+}
+
+impl Iterator for FixedWidthBatchIterator {
+    type Item = Result<RecordBatch, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let batch = self.next_batch();
+        if batch.is_err() {
+            self.done = true;
+        }
+        batch.transpose()
+    }
+}
+
+/// Parses one column of a batch of lines into an array.
+fn parse_column(column: &FixedWidthColumn, lines: &[(usize, Vec<u8>)]) -> Result<ArrayRef, String> {
+    let fields = lines.iter().map(|(line, text)| {
+        let field = column.field(text);
+        (*line, (!field.is_empty()).then_some(field))
+    });
+
+    Ok(match column.dtype {
+        DataType::Boolean => Arc::new(
+            fields
+                .map(|(line, field)| {
+                    field
+                        .map(|field| {
+                            match text(line, column, field)?.to_ascii_lowercase().as_str() {
+                                "true" | "t" | "y" | "1" => Ok(true),
+                                "false" | "f" | "n" | "0" => Ok(false),
+                                other => Err(parse_error(line, column, other)),
+                            }
+                        })
+                        .transpose()
+                })
+                .collect::<Result<BooleanArray, String>>()?,
+        ),
+        DataType::Int8 => parse_primitive::<Int8Type>(column, fields)?,
+        DataType::Int16 => parse_primitive::<Int16Type>(column, fields)?,
+        DataType::Int32 => parse_primitive::<Int32Type>(column, fields)?,
+        DataType::Int64 => parse_primitive::<Int64Type>(column, fields)?,
+        DataType::UInt8 => parse_primitive::<UInt8Type>(column, fields)?,
+        DataType::UInt16 => parse_primitive::<UInt16Type>(column, fields)?,
+        DataType::UInt32 => parse_primitive::<UInt32Type>(column, fields)?,
+        DataType::UInt64 => parse_primitive::<UInt64Type>(column, fields)?,
+        DataType::Float32 => parse_primitive::<Float32Type>(column, fields)?,
+        DataType::Float64 => parse_primitive::<Float64Type>(column, fields)?,
+        DataType::Utf8 => Arc::new(
+            fields
+                .map(|(line, field)| field.map(|field| text(line, column, field)).transpose())
+                .collect::<Result<StringArray, String>>()?,
+        ),
+        DataType::Binary => Arc::new(fields.map(|(_, field)| field).collect::<BinaryArray>()),
+    })
+}
+
+fn parse_primitive<'a, T>(
+    column: &FixedWidthColumn,
+    fields: impl Iterator<Item = (usize, Option<&'a [u8]>)>,
+) -> Result<ArrayRef, String>
+where
+    T: ArrowPrimitiveType,
+    T::Native: FromStr,
+{
+    let array = fields
+        .map(|(line, field)| {
+            field
+                .map(|field| {
+                    let text = text(line, column, field)?;
+                    text.parse::<T::Native>()
+                        .map_err(|_| parse_error(line, column, text))
+                })
+                .transpose()
+        })
+        .collect::<Result<PrimitiveArray<T>, String>>()?;
+    Ok(Arc::new(array))
+}
+
+fn text<'a>(line: usize, column: &FixedWidthColumn, field: &'a [u8]) -> Result<&'a str, String> {
+    std::str::from_utf8(field)
+        .map_err(|_| format!("line {}: column {}: invalid UTF-8", line, column.name))
+}
+
+fn parse_error(line: usize, column: &FixedWidthColumn, text: &str) -> String {
+    format!(
+        "line {}: column {}: cannot parse '{}' as {:?}",
+        line, column.name, text, column.dtype
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryObjectStore, conformance};
+    use dbms_dtype::Scalar;
+
+    const DATA: &str = "\
+ID  NAME      AMOUNT  ACTIVE
+0001Alice       12.50 Y
+# a comment
+
+0002  Bob        -3.25N
+0003Carol
+";
+
+    fn source(batch_size: usize) -> FixedWidthDataSource {
+        let store = Arc::new(InMemoryObjectStore::new());
+        store
+            .put("extract.txt", DATA.as_bytes().to_vec().into())
+            .unwrap();
+        let columns = vec![
+            FixedWidthColumn::new("id", 0, 4, DataType::Int32),
+            FixedWidthColumn::new("name", 4, 10, DataType::Utf8).with_trim(Trim::Right),
+            FixedWidthColumn::new("amount", 14, 8, DataType::Float64),
+            FixedWidthColumn::new("active", 22, 1, DataType::Boolean),
+        ];
+        let options = FixedWidthOptions::default()
+            .with_skip_lines(1)
+            .with_comment(b'#');
+        FixedWidthDataSource::from_store(store, "extract.txt", columns, batch_size)
+            .with_options(options)
+    }
+
+    #[test]
+    fn test_scan() {
+        let batches: Vec<_> = source(2).scan(None).unwrap().map(Result::unwrap).collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].row_count(), 2);
+
+        let batch = &batches[0];
+        assert_eq!(batch.field(0).get(1), Scalar::Int32(Some(2)));
+        assert_eq!(
+            batch.field(1).get(0),
+            Scalar::Utf8(Some("Alice".to_string()))
+        );
+        assert_eq!(
+            batch.field(1).get(1),
+            Scalar::Utf8(Some("  Bob".to_string()))
+        );
+        assert_eq!(batch.field(2).get(1), Scalar::Float64(Some(-3.25)));
+        assert_eq!(batch.field(3).get(0), Scalar::Boolean(Some(true)));
+
+        // Columns past the end of a short line are null
+        let batch = &batches[1];
+        assert_eq!(
+            batch.field(1).get(0),
+            Scalar::Utf8(Some("Carol".to_string()))
+        );
+        assert_eq!(batch.field(2).get(0), Scalar::Float64(None));
+
+        conformance::check(&source(2));
+    }
+
+    #[test]
+    fn test_projection() {
+        let batch = source(10)
+            .scan(Some(&["active", "id"]))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.column_count(), 2);
+        assert_eq!(batch.schema().fields()[0].name(), "active");
+        assert_eq!(batch.field(0).get(1), Scalar::Boolean(Some(false)));
+        assert_eq!(batch.field(1).get(2), Scalar::Int32(Some(3)));
+    }
+
+    #[test]
+    fn test_errors() {
+        let store = Arc::new(InMemoryObjectStore::new());
+        store.put("bad.txt", b"12\nx4\n".to_vec().into()).unwrap();
+        let columns = vec![FixedWidthColumn::new("n", 0, 2, DataType::Int64)];
+        let source = FixedWidthDataSource::from_store(store.clone(), "bad.txt", columns, 10);
+        let mut batches = source.scan(None).unwrap();
+        assert_eq!(
+            batches.next().unwrap().unwrap_err(),
+            "line 2: column n: cannot parse 'x4' as Int64"
+        );
+        assert!(batches.next().is_none());
+
+        let columns = vec![
+            FixedWidthColumn::new("n", 0, 1, DataType::Int64),
+            FixedWidthColumn::new("n", 1, 1, DataType::Int64),
+        ];
+        let source = FixedWidthDataSource::from_store(store.clone(), "bad.txt", columns, 10);
+        assert_eq!(source.schema().unwrap_err(), "duplicate column name: n");
+        let columns = vec![FixedWidthColumn::new("n", 0, 0, DataType::Int64)];
+        let source = FixedWidthDataSource::from_store(store.clone(), "bad.txt", columns, 10);
+        assert_eq!(source.schema().unwrap_err(), "column n has zero width");
+        let columns = vec![FixedWidthColumn::new("n", 1, usize::MAX, DataType::Int64)];
+        let source = FixedWidthDataSource::from_store(store, "bad.txt", columns, 10);
+        assert_eq!(
+            source.schema().unwrap_err(),
+            "column n ends past the largest offset"
+        );
+        let column = FixedWidthColumn::new("n", 1, usize::MAX, DataType::Utf8);
+        assert_eq!(column.field(b"abc"), b"bc");
+    }
+}
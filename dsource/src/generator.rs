@@ -0,0 +1,55 @@
+//! Data sources that generate rows instead of reading them, for tests and
+//! benchmarks.
+//!
+//! Generated batches are computed as they are scanned, so arbitrarily large
+//! sources take no memory beyond the batch being read.
+
+mod random;
+mod sequence;
+mod tpch;
+
+pub use self::random::{ColumnOptions, Distribution, RandomDataSource};
+pub use self::sequence::SequenceDataSource;
+pub use self::tpch::{TpchDataSource, TpchTable, write_tpch};
+
+use std::ops::Range;
+
+/// Returns the rows of `partition` when `rows` are split into `partitions`
+/// contiguous ranges of nearly equal size.
+fn partition_rows(rows: u64, partitions: usize, partition: usize) -> Range<u64> {
+    let partitions = partitions as u64;
+    let partition = partition as u64;
+    let bound = |p: u64| (rows as u128 * p as u128 / partitions as u128) as u64;
+    bound(partition)..bound(partition + 1)
+}
+
+/// Splits a range of rows into consecutive batches of at most `batch_size`.
+fn batch_ranges(rows: Range<u64>, batch_size: usize) -> impl Iterator<Item = Range<u64>> + Send {
+    let batch_size = batch_size.max(1) as u64;
+    (rows.start..rows.end)
+        .step_by(batch_size as usize)
+        .map(move |start| start..(start + batch_size).min(rows.end))
+}
+
+/// The SplitMix64 finalizer, a fast bijective hash of 64-bit integers.
+pub(crate) fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_rows() {
+        let ranges: Vec<_> = (0..3).map(|p| partition_rows(10, 3, p)).collect();
+        assert_eq!(ranges, [0..3, 3..6, 6..10]);
+        assert_eq!(partition_rows(0, 2, 1), 0..0);
+
+        let batches: Vec<_> = batch_ranges(3..10, 3).collect();
+        assert_eq!(batches, [3..6, 6..9, 9..10]);
+    }
+}
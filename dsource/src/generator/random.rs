@@ -0,0 +1,445 @@
+//! Seeded random data for any schema.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Float32Array, Float64Array, Int8Array, Int16Array,
+    Int32Array, Int64Array, StringArray, UInt8Array, UInt16Array, UInt32Array, UInt64Array,
+};
+use dbms_dtype::{Column, DataType, RecordBatch, Schema};
+
+use super::{batch_ranges, mix, partition_rows};
+use crate::{
+    BatchIterator, ColumnStatistics, DEFAULT_BATCH_SIZE, DataSource, Precision, Statistics,
+    check_partition, resolve_projection,
+};
+
+/// How often each of a column's distinct values occurs.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Distribution {
+    /// Every value is equally likely.
+    #[default]
+    Uniform,
+    /// Values near the middle of the domain are most likely, with a standard
+    /// deviation of a sixth of the domain.
+    Normal,
+    /// The `k`th value has a probability proportional to `1 / k^s`, so the
+    /// first values are by far the most common. `s` must be positive.
+    Zipf(f64),
+}
+
+/// How the values of a generated column are drawn.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColumnOptions {
+    /// Fraction of values that are null, from 0 to 1.
+    pub null_ratio: f64,
+    /// Number of distinct non-null values, or the row count if unset.
+    pub cardinality: Option<u64>,
+    pub distribution: Distribution,
+}
+
+impl ColumnOptions {
+    pub fn with_null_ratio(mut self, null_ratio: f64) -> Self {
+        self.null_ratio = null_ratio;
+        self
+    }
+
+    pub fn with_cardinality(mut self, cardinality: u64) -> Self {
+        self.cardinality = Some(cardinality);
+        self
+    }
+
+    pub fn with_distribution(mut self, distribution: Distribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.null_ratio) {
+            return Err(format!(
+                "null ratio must be between 0 and 1: {}",
+                self.null_ratio
+            ));
+        }
+        if self.cardinality == Some(0) {
+            return Err("cardinality must be positive".to_string());
+        }
+        if let Distribution::Zipf(s) = self.distribution
+            && s.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater)
+        {
+            return Err(format!("Zipf exponent must be positive: {}", s));
+        }
+        Ok(())
+    }
+}
+
+/// A data source generating seeded random rows for a schema.
+///
+/// Each value depends only on the seed, its column and its row number, so
+/// scans return the same rows whatever the batch size or partitioning. The
+/// `k`th distinct value of a column is `k` for numeric columns, `k % 2 == 1`
+/// for booleans, `"{column}-{k}"` for strings and the big-endian bytes of
+/// `k` for binary columns; integers wrap if the cardinality exceeds their
+/// range.
+#[derive(Debug, Clone)]
+pub struct RandomDataSource {
+    schema: Schema,
+    rows: u64,
+    seed: u64,
+    batch_size: usize,
+    partitions: usize,
+    options: ColumnOptions,
+    column_options: HashMap<String, ColumnOptions>,
+}
+
+impl RandomDataSource {
+    pub fn new(schema: Schema, rows: u64, seed: u64) -> Self {
+        Self {
+            schema,
+            rows,
+            seed,
+            batch_size: DEFAULT_BATCH_SIZE,
+            partitions: 1,
+            options: ColumnOptions::default(),
+            column_options: HashMap::new(),
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Splits the rows into this many contiguous partitions.
+    pub fn with_partitions(mut self, partitions: usize) -> Self {
+        self.partitions = partitions.max(1);
+        self
+    }
+
+    /// Sets the options of every column without options of its own.
+    pub fn with_options(mut self, options: ColumnOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the options of one column.
+    pub fn with_column_options(
+        mut self,
+        column: impl Into<String>,
+        options: ColumnOptions,
+    ) -> Self {
+        self.column_options.insert(column.into(), options);
+        self
+    }
+
+    /// Returns the options of each column in schema order.
+    fn columns(&self) -> Result<Vec<ColumnOptions>, String> {
+        for name in self.column_options.keys() {
+            if !self.schema.fields().iter().any(|f| f.name() == name) {
+                return Err(format!("column not found: {}", name));
+            }
+        }
+        self.schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let options = self
+                    .column_options
+                    .get(field.name())
+                    .unwrap_or(&self.options);
+                options
+                    .validate()
+                    .map_err(|e| format!("column {}: {}", field.name(), e))?;
+                Ok(options.clone())
+            })
+            .collect()
+    }
+
+    fn scan_rows(
+        &self,
+        rows: std::ops::Range<u64>,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let options = self.columns()?;
+        let indices = match projection {
+            Some(cols) => resolve_projection(&self.schema, cols)?,
+            None => (0..self.schema.fields().len()).collect(),
+        };
+        let schema = self.schema.project(&indices);
+        let generators: Vec<_> = indices
+            .into_iter()
+            .map(|i| ColumnGenerator {
+                seed: self.seed,
+                column: i as u64,
+                name: self.schema.fields()[i].name().to_string(),
+                dtype: self.schema.fields()[i].dtype().clone(),
+                options: options[i].clone(),
+                domain: options[i].cardinality.unwrap_or(self.rows).max(1),
+            })
+            .collect();
+
+        Ok(Box::new(batch_ranges(rows, self.batch_size).map(
+            move |rows| {
+                let columns = generators
+                    .iter()
+                    .map(|generator| Column::try_from(generator.generate(rows.clone())))
+                    .collect::<Result<_, _>>()?;
+                Ok(RecordBatch::new(schema.clone(), columns))
+            },
+        )))
+    }
+}
+
+impl DataSource for RandomDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        Ok(self.schema.clone())
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        self.scan_rows(0..self.rows, projection)
+    }
+
+    fn partition_count(&self) -> Result<usize, String> {
+        Ok(self.partitions)
+    }
+
+    fn scan_partition(
+        &self,
+        partition: usize,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        check_partition(partition, self.partitions)?;
+        self.scan_rows(
+            partition_rows(self.rows, self.partitions, partition),
+            projection,
+        )
+    }
+
+    fn statistics(&self) -> Result<Statistics, String> {
+        let rows = self.rows as usize;
+        let column_statistics = self
+            .columns()?
+            .into_iter()
+            .map(|options| ColumnStatistics {
+                null_count: Precision::Inexact((rows as f64 * options.null_ratio) as usize),
+                distinct_count: Precision::Inexact(
+                    options.cardinality.map_or(rows, |c| (c as usize).min(rows)),
+                ),
+                ..ColumnStatistics::default()
+            })
+            .collect();
+        Ok(Statistics {
+            num_rows: Precision::Exact(rows),
+            total_byte_size: Precision::Absent,
+            column_statistics,
+        })
+    }
+}
+
+/// Generates the values of one column.
+struct ColumnGenerator {
+    seed: u64,
+    column: u64,
+    name: String,
+    dtype: DataType,
+    options: ColumnOptions,
+    /// Number of distinct non-null values.
+    domain: u64,
+}
+
+impl ColumnGenerator {
+    /// Returns a uniform random number in `[0, 1)` for a row; `draw`
+    /// distinguishes the numbers used for one value.
+    fn uniform(&self, row: u64, draw: u64) -> f64 {
+        let hash = mix(self.seed ^ mix(self.column ^ mix(row ^ mix(draw))));
+        (hash >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns the index of the distinct value in a row, or `None` for null.
+    fn index(&self, row: u64) -> Option<u64> {
+        if self.options.null_ratio > 0.0 && self.uniform(row, 0) < self.options.null_ratio {
+            return None;
+        }
+        let n = self.domain as f64;
+        let u = self.uniform(row, 1);
+        let x = match self.options.distribution {
+            Distribution::Uniform => u * n,
+            Distribution::Normal => {
+                // Box-Muller transform
+                let v = self.uniform(row, 2);
+                let z = (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos();
+                n / 2.0 + z * n / 6.0
+            }
+            // Inverts the CDF of the continuous power law on [1, n + 1)
+            Distribution::Zipf(s) if (s - 1.0).abs() < 1e-9 => (n + 1.0).powf(u) - 1.0,
+            Distribution::Zipf(s) => {
+                let a = 1.0 - s;
+                (((n + 1.0).powf(a) - 1.0) * u + 1.0).powf(1.0 / a) - 1.0
+            }
+        };
+        Some((x.max(0.0) as u64).min(self.domain - 1))
+    }
+
+    fn generate(&self, rows: std::ops::Range<u64>) -> ArrayRef {
+        let indices = rows.map(|row| self.index(row));
+        match self.dtype {
+            DataType::Boolean => Arc::new(BooleanArray::from_iter(
+                indices.map(|k| k.map(|k| k % 2 == 1)),
+            )),
+            DataType::Int8 => Arc::new(Int8Array::from_iter(indices.map(|k| k.map(|k| k as i8)))),
+            DataType::Int16 => {
+                Arc::new(Int16Array::from_iter(indices.map(|k| k.map(|k| k as i16))))
+            }
+            DataType::Int32 => {
+                Arc::new(Int32Array::from_iter(indices.map(|k| k.map(|k| k as i32))))
+            }
+            DataType::Int64 => {
+                Arc::new(Int64Array::from_iter(indices.map(|k| k.map(|k| k as i64))))
+            }
+            DataType::UInt8 => Arc::new(UInt8Array::from_iter(indices.map(|k| k.map(|k| k as u8)))),
+            DataType::UInt16 => {
+                Arc::new(UInt16Array::from_iter(indices.map(|k| k.map(|k| k as u16))))
+            }
+            DataType::UInt32 => {
+                Arc::new(UInt32Array::from_iter(indices.map(|k| k.map(|k| k as u32))))
+            }
+            DataType::UInt64 => Arc::new(UInt64Array::from_iter(indices)),
+            DataType::Float32 => Arc::new(Float32Array::from_iter(
+                indices.map(|k| k.map(|k| k as f32)),
+            )),
+            DataType::Float64 => Arc::new(Float64Array::from_iter(
+                indices.map(|k| k.map(|k| k as f64)),
+            )),
+            DataType::Utf8 => Arc::new(StringArray::from_iter(
+                indices.map(|k| k.map(|k| format!("{}-{}", self.name, k))),
+            )),
+            DataType::Binary => Arc::new(BinaryArray::from_iter(
+                indices.map(|k| k.map(u64::to_be_bytes)),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance;
+    use dbms_dtype::{Field, Scalar};
+
+    fn test_schema() -> Schema {
+        Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::Utf8),
+            Field::new("score", DataType::Float64),
+            Field::new("flag", DataType::Boolean),
+            Field::new("data", DataType::Binary),
+        ])
+    }
+
+    fn rows(batches: BatchIterator) -> Vec<Vec<Scalar>> {
+        batches
+            .flat_map(|batch| {
+                let batch = batch.unwrap();
+                (0..batch.row_count())
+                    .map(|i| {
+                        (0..batch.column_count())
+                            .map(|c| batch.field(c).get(i))
+                            .collect()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Returns how often each value of an integer column occurs.
+    fn counts(source: &RandomDataSource) -> HashMap<Option<i64>, usize> {
+        let mut counts = HashMap::new();
+        for row in rows(source.scan(None).unwrap()) {
+            let value = match &row[0] {
+                Scalar::Int32(v) => v.map(i64::from),
+                Scalar::Int64(v) => *v,
+                other => panic!("unexpected value {:?}", other),
+            };
+            *counts.entry(value).or_default() += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let source = RandomDataSource::new(test_schema(), 1000, 42)
+            .with_options(ColumnOptions::default().with_null_ratio(0.1));
+        let expected = rows(source.scan(None).unwrap());
+        assert_eq!(expected.len(), 1000);
+
+        // Batch size and partitioning do not change the rows
+        let source = source.with_batch_size(7).with_partitions(3);
+        assert_eq!(rows(source.scan(None).unwrap()), expected);
+        let partitioned: Vec<_> = (0..3)
+            .flat_map(|p| rows(source.scan_partition(p, None).unwrap()))
+            .collect();
+        assert_eq!(partitioned, expected);
+
+        let other = RandomDataSource::new(test_schema(), 1000, 43);
+        assert_ne!(rows(other.scan(None).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_null_ratio_and_cardinality() {
+        let schema = Schema::new(vec![Field::new("v", DataType::Int32)]);
+        let options = ColumnOptions::default()
+            .with_null_ratio(0.25)
+            .with_cardinality(10);
+        let source = RandomDataSource::new(schema, 10_000, 1).with_column_options("v", options);
+
+        let counts = counts(&source);
+        let nulls = counts[&None];
+        assert!((2300..2700).contains(&nulls), "{} nulls", nulls);
+        assert_eq!(counts.len(), 11);
+        assert!(counts.keys().all(|v| *v < Some(10)));
+    }
+
+    #[test]
+    fn test_distributions() {
+        let schema = Schema::new(vec![Field::new("v", DataType::Int64)]);
+        let source = |distribution| {
+            let options = ColumnOptions::default()
+                .with_cardinality(100)
+                .with_distribution(distribution);
+            RandomDataSource::new(schema.clone(), 10_000, 7).with_options(options)
+        };
+        let count =
+            |counts: &HashMap<Option<i64>, usize>, v| counts.get(&Some(v)).copied().unwrap_or(0);
+
+        let zipf = counts(&source(Distribution::Zipf(1.2)));
+        assert!(count(&zipf, 0) > 20 * count(&zipf, 50).max(1));
+
+        let normal = counts(&source(Distribution::Normal));
+        assert!(count(&normal, 50) > 5 * count(&normal, 2).max(1));
+
+        let uniform = counts(&source(Distribution::Uniform));
+        assert!((50..150).contains(&count(&uniform, 0)));
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let source = RandomDataSource::new(test_schema(), 10, 0)
+            .with_column_options("score", ColumnOptions::default().with_null_ratio(1.5));
+        assert_eq!(
+            source.scan(None).err().as_deref(),
+            Some("column score: null ratio must be between 0 and 1: 1.5")
+        );
+        let source = RandomDataSource::new(test_schema(), 10, 0)
+            .with_column_options("missing", ColumnOptions::default());
+        assert!(source.statistics().is_err());
+    }
+
+    #[test]
+    fn test_conformance() {
+        let source = RandomDataSource::new(test_schema(), 100, 5)
+            .with_batch_size(16)
+            .with_partitions(3);
+        conformance::check(&source);
+    }
+}
@@ -0,0 +1,325 @@
+//! Integer ranges and date and timestamp series.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow::array::{ArrayRef, Int32Array, Int64Array};
+use arrow::compute::kernels::cast_utils::{Parser, string_to_timestamp_nanos};
+use arrow::datatypes::Date32Type;
+use dbms_dtype::{Column, DataType, Field, RecordBatch, Scalar, Schema};
+
+use super::{batch_ranges, partition_rows};
+use crate::{
+    BatchIterator, ColumnStatistics, DEFAULT_BATCH_SIZE, DataSource, Precision, Statistics,
+    check_partition, resolve_projection,
+};
+
+/// A data source with one column holding an arithmetic sequence.
+///
+/// Dates are days since the Unix epoch as `Int32`, and timestamps are
+/// microseconds since the epoch as `Int64`.
+#[derive(Debug, Clone)]
+pub struct SequenceDataSource {
+    name: String,
+    dtype: DataType,
+    start: i64,
+    step: i64,
+    len: u64,
+    batch_size: usize,
+    partitions: usize,
+}
+
+impl SequenceDataSource {
+    /// Creates a column `value` of the integers from `start` up to, but not
+    /// including, `stop`, `step` apart. A negative step counts down.
+    pub fn range(start: i64, stop: i64, step: i64) -> Result<Self, String> {
+        Self::new("value", DataType::Int64, start, stop, step, false)
+    }
+
+    /// Creates a column `date` of the dates from `start` to `end` inclusive,
+    /// `step_days` apart. Dates are written as `YYYY-MM-DD`.
+    pub fn dates(start: &str, end: &str, step_days: i64) -> Result<Self, String> {
+        let parse = |date: &str| {
+            Date32Type::parse(date)
+                .map(i64::from)
+                .ok_or_else(|| format!("invalid date: {}", date))
+        };
+        Self::new(
+            "date",
+            DataType::Int32,
+            parse(start)?,
+            parse(end)?,
+            step_days,
+            true,
+        )
+    }
+
+    /// Creates a column `timestamp` of the instants from `start` to `end`
+    /// inclusive, `step` apart. Timestamps are written in RFC 3339 form and
+    /// are read as UTC if they have no offset.
+    pub fn timestamps(start: &str, end: &str, step: Duration) -> Result<Self, String> {
+        let parse = |timestamp: &str| {
+            string_to_timestamp_nanos(timestamp)
+                .map(|nanos| nanos.div_euclid(1000))
+                .map_err(|_| format!("invalid timestamp: {}", timestamp))
+        };
+        let step = i64::try_from(step.as_micros()).map_err(|_| "step is too large".to_string())?;
+        Self::new(
+            "timestamp",
+            DataType::Int64,
+            parse(start)?,
+            parse(end)?,
+            step,
+            true,
+        )
+    }
+
+    fn new(
+        name: &str,
+        dtype: DataType,
+        start: i64,
+        end: i64,
+        step: i64,
+        inclusive: bool,
+    ) -> Result<Self, String> {
+        if step == 0 {
+            return Err("step must not be zero".to_string());
+        }
+        let (start, end, step) = (start as i128, end as i128, step as i128);
+        let end = if inclusive { end + step.signum() } else { end };
+        let len = match (end - start) / step {
+            n if n <= 0 => 0,
+            n if (end - start) % step == 0 => n,
+            n => n + 1,
+        };
+        Ok(Self {
+            name: name.to_string(),
+            dtype,
+            start: start as i64,
+            step: step as i64,
+            len: len as u64,
+            batch_size: DEFAULT_BATCH_SIZE,
+            partitions: 1,
+        })
+    }
+
+    /// Renames the generated column.
+    pub fn with_column_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Splits the sequence into this many contiguous partitions.
+    pub fn with_partitions(mut self, partitions: usize) -> Self {
+        self.partitions = partitions.max(1);
+        self
+    }
+
+    /// Returns the `i`th value of the sequence.
+    fn value(&self, i: u64) -> i64 {
+        (self.start as i128 + self.step as i128 * i as i128) as i64
+    }
+
+    fn scalar(&self, value: i64) -> Scalar {
+        match self.dtype {
+            DataType::Int32 => Scalar::Int32(Some(value as i32)),
+            _ => Scalar::Int64(Some(value)),
+        }
+    }
+
+    fn scan_rows(
+        &self,
+        rows: std::ops::Range<u64>,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let schema = self.schema()?;
+        let indices = match projection {
+            Some(cols) => resolve_projection(&schema, cols)?,
+            None => vec![0],
+        };
+        let schema = schema.project(&indices);
+
+        let source = self.clone();
+        Ok(Box::new(batch_ranges(rows, self.batch_size).map(
+            move |rows| {
+                let values = rows.map(|i| source.value(i));
+                let array: ArrayRef = match source.dtype {
+                    DataType::Int32 => {
+                        Arc::new(Int32Array::from_iter_values(values.map(|v| v as i32)))
+                    }
+                    _ => Arc::new(Int64Array::from_iter_values(values)),
+                };
+                let column = Column::try_from(array)?;
+                let columns = indices.iter().map(|_| column.clone()).collect();
+                Ok(RecordBatch::new(schema.clone(), columns))
+            },
+        )))
+    }
+}
+
+impl DataSource for SequenceDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        Ok(Schema::new(vec![Field::new(
+            &self.name,
+            self.dtype.clone(),
+        )]))
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        self.scan_rows(0..self.len, projection)
+    }
+
+    fn partition_count(&self) -> Result<usize, String> {
+        Ok(self.partitions)
+    }
+
+    fn scan_partition(
+        &self,
+        partition: usize,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        check_partition(partition, self.partitions)?;
+        self.scan_rows(
+            partition_rows(self.len, self.partitions, partition),
+            projection,
+        )
+    }
+
+    fn statistics(&self) -> Result<Statistics, String> {
+        let rows = self.len as usize;
+        let (min_value, max_value) = match self.len {
+            0 => (
+                Scalar::null(self.dtype.clone()),
+                Scalar::null(self.dtype.clone()),
+            ),
+            len => {
+                let (first, last) = (self.value(0), self.value(len - 1));
+                (self.scalar(first.min(last)), self.scalar(first.max(last)))
+            }
+        };
+        let width = match self.dtype {
+            DataType::Int32 => 4,
+            _ => 8,
+        };
+        Ok(Statistics {
+            num_rows: Precision::Exact(rows),
+            total_byte_size: match rows.checked_mul(width) {
+                Some(size) => Precision::Exact(size),
+                None => Precision::Absent,
+            },
+            column_statistics: vec![ColumnStatistics {
+                null_count: Precision::Exact(0),
+                min_value: Precision::Exact(min_value),
+                max_value: Precision::Exact(max_value),
+                distinct_count: Precision::Exact(rows),
+            }],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(source: &SequenceDataSource) -> Vec<Scalar> {
+        source
+            .scan(None)
+            .unwrap()
+            .flat_map(|batch| {
+                let batch = batch.unwrap();
+                (0..batch.row_count())
+                    .map(|i| batch.field(0).get(i))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_range() {
+        let source = SequenceDataSource::range(0, 10, 3)
+            .unwrap()
+            .with_batch_size(2);
+        assert_eq!(source.scan(None).unwrap().count(), 2);
+        let expected: Vec<_> = [0, 3, 6, 9].map(|v| Scalar::Int64(Some(v))).into();
+        assert_eq!(values(&source), expected);
+
+        let down = SequenceDataSource::range(5, 0, -2).unwrap();
+        let expected: Vec<_> = [5, 3, 1].map(|v| Scalar::Int64(Some(v))).into();
+        assert_eq!(values(&down), expected);
+        assert_eq!(
+            down.statistics().unwrap().column_statistics[0]
+                .min_value
+                .get(),
+            Some(&Scalar::Int64(Some(1)))
+        );
+
+        assert!(values(&SequenceDataSource::range(3, 3, 1).unwrap()).is_empty());
+        assert!(values(&SequenceDataSource::range(3, 0, 1).unwrap()).is_empty());
+        assert!(SequenceDataSource::range(0, 1, 0).is_err());
+
+        // The size of a huge range does not fit in a usize
+        let huge = SequenceDataSource::range(0, i64::MAX, 1).unwrap();
+        let stats = huge.statistics().unwrap();
+        assert_eq!(stats.num_rows, Precision::Exact(i64::MAX as usize));
+        assert_eq!(stats.total_byte_size, Precision::Absent);
+    }
+
+    #[test]
+    fn test_partitions() {
+        let source = SequenceDataSource::range(0, 1_000_000_000, 1)
+            .unwrap()
+            .with_partitions(4)
+            .with_batch_size(10);
+        assert_eq!(
+            source.statistics().unwrap().num_rows,
+            Precision::Exact(1_000_000_000)
+        );
+
+        // Partitions are generated lazily from their own offset
+        let batch = source
+            .scan_partition(3, None)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.row_count(), 10);
+        assert_eq!(batch.field(0).get(0), Scalar::Int64(Some(750_000_000)));
+        assert!(source.scan_partition(4, None).is_err());
+    }
+
+    #[test]
+    fn test_dates() {
+        let source = SequenceDataSource::dates("2024-02-27", "2024-03-01", 1).unwrap();
+        assert_eq!(
+            source.schema().unwrap().fields()[0].dtype(),
+            &DataType::Int32
+        );
+        let expected: Vec<_> = (19780..19784).map(|v| Scalar::Int32(Some(v))).collect();
+        assert_eq!(values(&source), expected);
+
+        assert_eq!(
+            SequenceDataSource::dates("2024-02-30", "2024-03-01", 1).unwrap_err(),
+            "invalid date: 2024-02-30"
+        );
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let hour = Duration::from_secs(3600);
+        let source =
+            SequenceDataSource::timestamps("2024-01-01T00:00:00Z", "2024-01-01T02:30:00Z", hour)
+                .unwrap()
+                .with_column_name("ts");
+        let start = 1_704_067_200_000_000;
+        let expected: Vec<_> = (0..3)
+            .map(|h| Scalar::Int64(Some(start + h * 3_600_000_000)))
+            .collect();
+        assert_eq!(values(&source), expected);
+        assert_eq!(source.schema().unwrap().fields()[0].name(), "ts");
+    }
+}
@@ -0,0 +1,936 @@
+//! Deterministic TPC-H style benchmark data.
+//!
+//! The eight tables follow the TPC-H schema, cardinalities and value
+//! distributions, but text columns use a simplified grammar and the rows are
+//! not identical to those of the official `dbgen`, so query answers differ
+//! from the published ones. Every row is a pure function of its key, so the
+//! data is identical on every machine and for any batch size or partitioning.
+//!
+//! Dates are days since the Unix epoch as `Int32` and decimal columns are
+//! `Float64` rounded to cents, as the type system has neither.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Float64Builder, Int32Builder, Int64Builder, StringBuilder};
+use dbms_dtype::{Column, DataType, Field, RecordBatch, Schema};
+
+use super::{batch_ranges, mix, partition_rows};
+use crate::{
+    BatchIterator, DEFAULT_BATCH_SIZE, DataSource, Precision, SinkFormat, Statistics,
+    check_partition, resolve_projection,
+};
+
+/// The tables of the TPC-H schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TpchTable {
+    Region,
+    Nation,
+    Supplier,
+    Part,
+    PartSupp,
+    Customer,
+    Orders,
+    LineItem,
+}
+
+impl TpchTable {
+    pub const ALL: [TpchTable; 8] = [
+        Self::Region,
+        Self::Nation,
+        Self::Supplier,
+        Self::Part,
+        Self::PartSupp,
+        Self::Customer,
+        Self::Orders,
+        Self::LineItem,
+    ];
+
+    /// Returns the table name, e.g. `lineitem`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Region => "region",
+            Self::Nation => "nation",
+            Self::Supplier => "supplier",
+            Self::Part => "part",
+            Self::PartSupp => "partsupp",
+            Self::Customer => "customer",
+            Self::Orders => "orders",
+            Self::LineItem => "lineitem",
+        }
+    }
+
+    pub fn schema(&self) -> Schema {
+        use DataType::{Float64, Int32, Int64, Utf8};
+        let columns: &[(&str, DataType)] = match self {
+            Self::Region => &[
+                ("r_regionkey", Int64),
+                ("r_name", Utf8),
+                ("r_comment", Utf8),
+            ],
+            Self::Nation => &[
+                ("n_nationkey", Int64),
+                ("n_name", Utf8),
+                ("n_regionkey", Int64),
+                ("n_comment", Utf8),
+            ],
+            Self::Supplier => &[
+                ("s_suppkey", Int64),
+                ("s_name", Utf8),
+                ("s_address", Utf8),
+                ("s_nationkey", Int64),
+                ("s_phone", Utf8),
+                ("s_acctbal", Float64),
+                ("s_comment", Utf8),
+            ],
+            Self::Part => &[
+                ("p_partkey", Int64),
+                ("p_name", Utf8),
+                ("p_mfgr", Utf8),
+                ("p_brand", Utf8),
+                ("p_type", Utf8),
+                ("p_size", Int32),
+                ("p_container", Utf8),
+                ("p_retailprice", Float64),
+                ("p_comment", Utf8),
+            ],
+            Self::PartSupp => &[
+                ("ps_partkey", Int64),
+                ("ps_suppkey", Int64),
+                ("ps_availqty", Int32),
+                ("ps_supplycost", Float64),
+                ("ps_comment", Utf8),
+            ],
+            Self::Customer => &[
+                ("c_custkey", Int64),
+                ("c_name", Utf8),
+                ("c_address", Utf8),
+                ("c_nationkey", Int64),
+                ("c_phone", Utf8),
+                ("c_acctbal", Float64),
+                ("c_mktsegment", Utf8),
+                ("c_comment", Utf8),
+            ],
+            Self::Orders => &[
+                ("o_orderkey", Int64),
+                ("o_custkey", Int64),
+                ("o_orderstatus", Utf8),
+                ("o_totalprice", Float64),
+                ("o_orderdate", Int32),
+                ("o_orderpriority", Utf8),
+                ("o_clerk", Utf8),
+                ("o_shippriority", Int32),
+                ("o_comment", Utf8),
+            ],
+            Self::LineItem => &[
+                ("l_orderkey", Int64),
+                ("l_partkey", Int64),
+                ("l_suppkey", Int64),
+                ("l_linenumber", Int32),
+                ("l_quantity", Float64),
+                ("l_extendedprice", Float64),
+                ("l_discount", Float64),
+                ("l_tax", Float64),
+                ("l_returnflag", Utf8),
+                ("l_linestatus", Utf8),
+                ("l_shipdate", Int32),
+                ("l_commitdate", Int32),
+                ("l_receiptdate", Int32),
+                ("l_shipinstruct", Utf8),
+                ("l_shipmode", Utf8),
+                ("l_comment", Utf8),
+            ],
+        };
+        Schema::new(
+            columns
+                .iter()
+                .map(|(name, dtype)| Field::new(*name, dtype.clone()))
+                .collect(),
+        )
+    }
+}
+
+/// Days since the epoch of 1992-01-01, the first order date.
+const START_DATE: i32 = 8035;
+/// Days since the epoch of 1995-06-17, the date the data was "taken".
+const CURRENT_DATE: i32 = 9298;
+/// Days since the epoch of 1998-12-31, the last ship date.
+const END_DATE: i32 = 10591;
+
+const REGIONS: [&str; 5] = ["AFRICA", "AMERICA", "ASIA", "EUROPE", "MIDDLE EAST"];
+const NATIONS: [(&str, i64); 25] = [
+    ("ALGERIA", 0),
+    ("ARGENTINA", 1),
+    ("BRAZIL", 1),
+    ("CANADA", 1),
+    ("EGYPT", 4),
+    ("ETHIOPIA", 0),
+    ("FRANCE", 3),
+    ("GERMANY", 3),
+    ("INDIA", 2),
+    ("INDONESIA", 2),
+    ("IRAN", 4),
+    ("IRAQ", 4),
+    ("JAPAN", 2),
+    ("JORDAN", 4),
+    ("KENYA", 0),
+    ("MOROCCO", 0),
+    ("MOZAMBIQUE", 0),
+    ("PERU", 1),
+    ("CHINA", 2),
+    ("ROMANIA", 3),
+    ("SAUDI ARABIA", 4),
+    ("VIETNAM", 2),
+    ("RUSSIA", 3),
+    ("UNITED KINGDOM", 3),
+    ("UNITED STATES", 1),
+];
+const COLORS: [&str; 32] = [
+    "almond",
+    "antique",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanched",
+    "blue",
+    "blush",
+    "brown",
+    "burlywood",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflower",
+    "cream",
+    "cyan",
+    "dark",
+    "deep",
+    "dim",
+    "dodger",
+    "drab",
+    "firebrick",
+    "forest",
+    "frosted",
+    "gainsboro",
+    "ghost",
+    "goldenrod",
+    "green",
+    "grey",
+    "honeydew",
+];
+const TYPE_SIZES: [&str; 6] = ["STANDARD", "SMALL", "MEDIUM", "LARGE", "ECONOMY", "PROMO"];
+const TYPE_FINISHES: [&str; 5] = ["ANODIZED", "BURNISHED", "PLATED", "POLISHED", "BRUSHED"];
+const TYPE_METALS: [&str; 5] = ["TIN", "NICKEL", "BRASS", "STEEL", "COPPER"];
+const CONTAINER_SIZES: [&str; 5] = ["SM", "LG", "MED", "JUMBO", "WRAP"];
+const CONTAINER_KINDS: [&str; 8] = ["CASE", "BOX", "BAG", "JAR", "PKG", "PACK", "CAN", "DRUM"];
+const SEGMENTS: [&str; 5] = [
+    "AUTOMOBILE",
+    "BUILDING",
+    "FURNITURE",
+    "MACHINERY",
+    "HOUSEHOLD",
+];
+const PRIORITIES: [&str; 5] = ["1-URGENT", "2-HIGH", "3-MEDIUM", "4-NOT SPECIFIED", "5-LOW"];
+const INSTRUCTIONS: [&str; 4] = [
+    "DELIVER IN PERSON",
+    "COLLECT COD",
+    "NONE",
+    "TAKE BACK RETURN",
+];
+const MODES: [&str; 7] = ["REG AIR", "AIR", "RAIL", "SHIP", "TRUCK", "MAIL", "FOB"];
+const WORDS: [&str; 24] = [
+    "furiously",
+    "quickly",
+    "carefully",
+    "blithely",
+    "slyly",
+    "final",
+    "regular",
+    "express",
+    "pending",
+    "ironic",
+    "bold",
+    "silent",
+    "deposits",
+    "requests",
+    "accounts",
+    "packages",
+    "theodolites",
+    "instructions",
+    "foxes",
+    "pinto",
+    "beans",
+    "sleep",
+    "haggle",
+    "nag",
+];
+const ADDRESS_CHARS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ,";
+
+/// Random numbers for one row, determined by the table and row.
+struct Rng {
+    seed: u64,
+}
+
+impl Rng {
+    fn new(table: TpchTable, row: u64) -> Self {
+        Self {
+            seed: mix(mix(table as u64) ^ row),
+        }
+    }
+
+    /// Returns a uniform integer in `lo..=hi` for the `draw`th use.
+    fn int(&self, draw: u64, lo: i64, hi: i64) -> i64 {
+        let span = (hi - lo + 1) as u64;
+        lo + (mix(self.seed ^ mix(draw)) % span) as i64
+    }
+
+    fn pick<'a>(&self, draw: u64, choices: &[&'a str]) -> &'a str {
+        choices[self.int(draw, 0, choices.len() as i64 - 1) as usize]
+    }
+
+    /// Returns an amount in cents between `lo` and `hi` as a float.
+    fn money(&self, draw: u64, lo: i64, hi: i64) -> f64 {
+        self.int(draw, lo, hi) as f64 / 100.0
+    }
+
+    /// Returns a comment of a few words.
+    fn text(&self, draw: u64, min_words: i64, max_words: i64) -> String {
+        let words = self.int(draw, min_words, max_words) as u64;
+        (0..words)
+            .map(|i| self.pick(draw * 64 + i + 1, &WORDS))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn address(&self, draw: u64) -> String {
+        let len = self.int(draw, 10, 40) as u64;
+        (0..len)
+            .map(|i| {
+                let c = self.int(draw * 64 + i + 1, 0, ADDRESS_CHARS.len() as i64 - 1);
+                ADDRESS_CHARS[c as usize] as char
+            })
+            .collect()
+    }
+
+    fn phone(&self, draw: u64, nation: i64) -> String {
+        format!(
+            "{}-{}-{}-{}",
+            nation + 10,
+            self.int(draw, 100, 999),
+            self.int(draw + 1, 100, 999),
+            self.int(draw + 2, 1000, 9999)
+        )
+    }
+}
+
+fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Row counts of the scaled tables.
+#[derive(Debug, Clone, Copy)]
+struct Scale {
+    suppliers: u64,
+    parts: u64,
+    customers: u64,
+    orders: u64,
+    clerks: u64,
+}
+
+impl Scale {
+    fn new(scale_factor: f64) -> Self {
+        let scaled = |base: f64| ((base * scale_factor).round() as u64).max(1);
+        Self {
+            suppliers: scaled(10_000.0),
+            parts: scaled(200_000.0),
+            customers: scaled(150_000.0),
+            orders: scaled(1_500_000.0),
+            clerks: scaled(1_000.0),
+        }
+    }
+
+    /// Returns the `i`th supplier of a part, for `i` in `0..4`.
+    fn part_supplier(&self, part: i64, i: i64) -> i64 {
+        let s = self.suppliers as i64;
+        (part + i * (s / 4 + (part - 1) / s)) % s + 1
+    }
+
+    fn retail_price(part: i64) -> f64 {
+        (90_000 + (part / 10) % 20_001 + 100 * (part % 1000)) as f64 / 100.0
+    }
+
+    /// Returns the key of the `i`th order; only 8 of every 32 keys are used.
+    fn order_key(i: u64) -> i64 {
+        ((i / 8) * 32 + i % 8 + 1) as i64
+    }
+
+    /// Returns the number of line items of the `i`th order.
+    fn line_count(i: u64) -> u64 {
+        Rng::new(TpchTable::Orders, i).int(0, 1, 7) as u64
+    }
+}
+
+/// One line item of an order.
+struct Line {
+    part: i64,
+    supplier: i64,
+    number: i32,
+    quantity: f64,
+    price: f64,
+    discount: f64,
+    tax: f64,
+    return_flag: &'static str,
+    status: &'static str,
+    ship_date: i32,
+    commit_date: i32,
+    receipt_date: i32,
+    instruction: &'static str,
+    mode: &'static str,
+    comment: String,
+}
+
+/// An order with its line items.
+struct Order {
+    key: i64,
+    customer: i64,
+    status: &'static str,
+    total: f64,
+    date: i32,
+    priority: &'static str,
+    clerk: String,
+    comment: String,
+    lines: Vec<Line>,
+}
+
+impl Scale {
+    fn order(&self, i: u64) -> Order {
+        let rng = Rng::new(TpchTable::Orders, i);
+        // Customers whose key is a multiple of three place no orders
+        let active = self.customers - self.customers / 3;
+        let r = rng.int(1, 0, active as i64 - 1);
+        let date = rng.int(2, START_DATE as i64, (END_DATE - 151) as i64) as i32;
+
+        let lines: Vec<Line> = (0..Self::line_count(i))
+            .map(|n| {
+                let rng = Rng::new(TpchTable::LineItem, i * 8 + n);
+                let part = rng.int(0, 1, self.parts as i64);
+                let quantity = rng.int(1, 1, 50) as f64;
+                let ship_date = date + rng.int(2, 1, 121) as i32;
+                let receipt_date = ship_date + rng.int(3, 1, 30) as i32;
+                let return_flag = match receipt_date <= CURRENT_DATE {
+                    true => rng.pick(4, &["R", "A"]),
+                    false => "N",
+                };
+                Line {
+                    part,
+                    supplier: self.part_supplier(part, rng.int(5, 0, 3)),
+                    number: n as i32 + 1,
+                    quantity,
+                    price: round_cents(quantity * Self::retail_price(part)),
+                    discount: rng.int(6, 0, 10) as f64 / 100.0,
+                    tax: rng.int(7, 0, 8) as f64 / 100.0,
+                    return_flag,
+                    status: if ship_date > CURRENT_DATE { "O" } else { "F" },
+                    ship_date,
+                    commit_date: date + rng.int(8, 30, 90) as i32,
+                    receipt_date,
+                    instruction: rng.pick(9, &INSTRUCTIONS),
+                    mode: rng.pick(10, &MODES),
+                    comment: rng.text(11, 2, 6),
+                }
+            })
+            .collect();
+
+        let status = match (
+            lines.iter().all(|l| l.status == "F"),
+            lines.iter().all(|l| l.status == "O"),
+        ) {
+            (true, _) => "F",
+            (_, true) => "O",
+            _ => "P",
+        };
+        let total = lines
+            .iter()
+            .map(|l| l.price * (1.0 + l.tax) * (1.0 - l.discount))
+            .sum();
+        Order {
+            key: Self::order_key(i),
+            customer: r + r / 2 + 1,
+            status,
+            total: round_cents(total),
+            date,
+            priority: rng.pick(3, &PRIORITIES),
+            clerk: format!("Clerk#{:09}", rng.int(4, 1, self.clerks as i64)),
+            comment: rng.text(5, 3, 10),
+            lines,
+        }
+    }
+}
+
+/// Accumulates the values of every column of a table for one batch.
+enum ColumnBuilder {
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float64(Float64Builder),
+    Utf8(StringBuilder),
+}
+
+struct Rows {
+    columns: Vec<ColumnBuilder>,
+    count: usize,
+}
+
+impl Rows {
+    fn new(schema: &Schema) -> Self {
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| match field.dtype() {
+                DataType::Int32 => ColumnBuilder::Int32(Int32Builder::new()),
+                DataType::Float64 => ColumnBuilder::Float64(Float64Builder::new()),
+                DataType::Utf8 => ColumnBuilder::Utf8(StringBuilder::new()),
+                _ => ColumnBuilder::Int64(Int64Builder::new()),
+            })
+            .collect();
+        Self { columns, count: 0 }
+    }
+
+    /// Appends a row, given as one value per column.
+    fn push(&mut self, values: &[Value]) {
+        for (column, value) in self.columns.iter_mut().zip(values) {
+            match (column, value) {
+                (ColumnBuilder::Int32(b), Value::Int32(v)) => b.append_value(*v),
+                (ColumnBuilder::Int64(b), Value::Int64(v)) => b.append_value(*v),
+                (ColumnBuilder::Float64(b), Value::Float64(v)) => b.append_value(*v),
+                (ColumnBuilder::Utf8(b), Value::Str(v)) => b.append_value(v),
+                _ => unreachable!("value does not match column type"),
+            }
+        }
+        self.count += 1;
+    }
+
+    fn finish(self, schema: &Schema, indices: &[usize]) -> Result<RecordBatch, String> {
+        let arrays: Vec<ArrayRef> = self
+            .columns
+            .into_iter()
+            .map(|column| -> ArrayRef {
+                match column {
+                    ColumnBuilder::Int32(mut b) => Arc::new(b.finish()),
+                    ColumnBuilder::Int64(mut b) => Arc::new(b.finish()),
+                    ColumnBuilder::Float64(mut b) => Arc::new(b.finish()),
+                    ColumnBuilder::Utf8(mut b) => Arc::new(b.finish()),
+                }
+            })
+            .collect();
+        let columns = indices
+            .iter()
+            .map(|&i| Column::try_from(arrays[i].clone()))
+            .collect::<Result<_, _>>()?;
+        Ok(RecordBatch::new(schema.project(indices), columns))
+    }
+}
+
+enum Value {
+    Int32(i32),
+    Int64(i64),
+    Float64(f64),
+    Str(String),
+}
+
+impl From<i32> for Value {
+    fn from(v: i32) -> Self {
+        Self::Int32(v)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Self::Int64(v)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Self::Float64(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Self::Str(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Self::Str(v)
+    }
+}
+
+/// Builds a row from values of different types.
+macro_rules! row {
+    ($($value:expr),* $(,)?) => {
+        [$(Value::from($value)),*]
+    };
+}
+
+/// A data source generating one TPC-H table at a scale factor.
+///
+/// The line items of an order are generated together, so `lineitem` is
+/// partitioned by order and its batches may hold a few more rows than the
+/// batch size.
+#[derive(Debug, Clone)]
+pub struct TpchDataSource {
+    table: TpchTable,
+    scale_factor: f64,
+    scale: Scale,
+    batch_size: usize,
+    partitions: usize,
+}
+
+impl TpchDataSource {
+    pub fn new(table: TpchTable, scale_factor: f64) -> Result<Self, String> {
+        if scale_factor.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater) {
+            return Err(format!("scale factor must be positive: {}", scale_factor));
+        }
+        Ok(Self {
+            table,
+            scale_factor,
+            scale: Scale::new(scale_factor),
+            batch_size: DEFAULT_BATCH_SIZE,
+            partitions: 1,
+        })
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Splits the table into this many partitions of contiguous keys.
+    pub fn with_partitions(mut self, partitions: usize) -> Self {
+        self.partitions = partitions.max(1);
+        self
+    }
+
+    pub fn table(&self) -> TpchTable {
+        self.table
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Returns the number of units rows are generated from: orders for
+    /// `lineitem`, and rows for every other table.
+    fn units(&self) -> u64 {
+        match self.table {
+            TpchTable::Region => REGIONS.len() as u64,
+            TpchTable::Nation => NATIONS.len() as u64,
+            TpchTable::Supplier => self.scale.suppliers,
+            TpchTable::Part => self.scale.parts,
+            TpchTable::PartSupp => self.scale.parts * 4,
+            TpchTable::Customer => self.scale.customers,
+            TpchTable::Orders | TpchTable::LineItem => self.scale.orders,
+        }
+    }
+
+    /// Appends the rows generated from unit `i`.
+    fn generate(&self, i: u64, rows: &mut Rows) {
+        let rng = Rng::new(self.table, i);
+        let key = i as i64 + 1;
+        match self.table {
+            TpchTable::Region => {
+                rows.push(&row![i as i64, REGIONS[i as usize], rng.text(0, 4, 12)])
+            }
+            TpchTable::Nation => {
+                let (name, region) = NATIONS[i as usize];
+                rows.push(&row![i as i64, name, region, rng.text(0, 4, 12)])
+            }
+            TpchTable::Supplier => {
+                let nation = rng.int(1, 0, 24);
+                rows.push(&row![
+                    key,
+                    format!("Supplier#{:09}", key),
+                    rng.address(0),
+                    nation,
+                    rng.phone(2, nation),
+                    rng.money(5, -99_999, 999_999),
+                    rng.text(6, 4, 12),
+                ])
+            }
+            TpchTable::Part => {
+                let m = rng.int(6, 1, 5);
+                let name: Vec<_> = (0..5).map(|n| rng.pick(n, &COLORS)).collect();
+                rows.push(&row![
+                    key,
+                    name.join(" "),
+                    format!("Manufacturer#{}", m),
+                    format!("Brand#{}{}", m, rng.int(7, 1, 5)),
+                    format!(
+                        "{} {} {}",
+                        rng.pick(8, &TYPE_SIZES),
+                        rng.pick(9, &TYPE_FINISHES),
+                        rng.pick(10, &TYPE_METALS)
+                    ),
+                    rng.int(11, 1, 50) as i32,
+                    format!(
+                        "{} {}",
+                        rng.pick(12, &CONTAINER_SIZES),
+                        rng.pick(13, &CONTAINER_KINDS)
+                    ),
+                    Scale::retail_price(key),
+                    rng.text(14, 2, 6),
+                ])
+            }
+            TpchTable::PartSupp => {
+                let part = (i / 4) as i64 + 1;
+                rows.push(&row![
+                    part,
+                    self.scale.part_supplier(part, (i % 4) as i64),
+                    rng.int(0, 1, 9999) as i32,
+                    rng.money(1, 100, 100_000),
+                    rng.text(2, 8, 20),
+                ])
+            }
+            TpchTable::Customer => {
+                let nation = rng.int(1, 0, 24);
+                rows.push(&row![
+                    key,
+                    format!("Customer#{:09}", key),
+                    rng.address(0),
+                    nation,
+                    rng.phone(2, nation),
+                    rng.money(5, -99_999, 999_999),
+                    rng.pick(6, &SEGMENTS),
+                    rng.text(7, 4, 12),
+                ])
+            }
+            TpchTable::Orders => {
+                let order = self.scale.order(i);
+                rows.push(&row![
+                    order.key,
+                    order.customer,
+                    order.status,
+                    order.total,
+                    order.date,
+                    order.priority,
+                    order.clerk,
+                    0,
+                    order.comment,
+                ])
+            }
+            TpchTable::LineItem => {
+                let order = self.scale.order(i);
+                for line in order.lines {
+                    rows.push(&row![
+                        order.key,
+                        line.part,
+                        line.supplier,
+                        line.number,
+                        line.quantity,
+                        line.price,
+                        line.discount,
+                        line.tax,
+                        line.return_flag,
+                        line.status,
+                        line.ship_date,
+                        line.commit_date,
+                        line.receipt_date,
+                        line.instruction,
+                        line.mode,
+                        line.comment,
+                    ])
+                }
+            }
+        }
+    }
+
+    fn scan_units(
+        &self,
+        units: std::ops::Range<u64>,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let schema = self.table.schema();
+        let indices = match projection {
+            Some(cols) => resolve_projection(&schema, cols)?,
+            None => (0..schema.fields().len()).collect(),
+        };
+        // Line items average four per order
+        let batch_size = match self.table {
+            TpchTable::LineItem => self.batch_size.div_ceil(4),
+            _ => self.batch_size,
+        };
+
+        let source = self.clone();
+        Ok(Box::new(batch_ranges(units, batch_size).map(
+            move |units| {
+                let mut rows = Rows::new(&schema);
+                for i in units {
+                    source.generate(i, &mut rows);
+                }
+                rows.finish(&schema, &indices)
+            },
+        )))
+    }
+}
+
+impl DataSource for TpchDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        Ok(self.table.schema())
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        self.scan_units(0..self.units(), projection)
+    }
+
+    fn partition_count(&self) -> Result<usize, String> {
+        Ok(self.partitions)
+    }
+
+    fn scan_partition(
+        &self,
+        partition: usize,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        check_partition(partition, self.partitions)?;
+        self.scan_units(
+            partition_rows(self.units(), self.partitions, partition),
+            projection,
+        )
+    }
+
+    fn statistics(&self) -> Result<Statistics, String> {
+        let rows = match self.table {
+            TpchTable::LineItem => (0..self.scale.orders).map(Scale::line_count).sum(),
+            _ => self.units(),
+        };
+        Ok(Statistics {
+            num_rows: Precision::Exact(rows as usize),
+            ..Statistics::unknown(&self.table.schema())
+        })
+    }
+}
+
+/// Writes every table at a scale factor into `dir`, one file per table named
+/// after it, e.g. `lineitem.parquet`.
+pub fn write_tpch(
+    dir: impl AsRef<Path>,
+    scale_factor: f64,
+    format: &SinkFormat,
+) -> Result<(), String> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    for table in TpchTable::ALL {
+        let source = TpchDataSource::new(table, scale_factor)?;
+        let path = dir.join(format!("{}.{}", table.name(), format.extension()));
+        format
+            .sink(&path)
+            .write(&table.schema(), source.scan(None)?)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParquetDataSource, ParquetWriteOptions, conformance};
+    use dbms_dtype::Scalar;
+
+    const SCALE: f64 = 0.001;
+
+    fn row_count(source: &TpchDataSource) -> usize {
+        source
+            .scan(None)
+            .unwrap()
+            .map(|b| b.unwrap().row_count())
+            .sum()
+    }
+
+    #[test]
+    fn test_cardinalities() {
+        let count = |table| row_count(&TpchDataSource::new(table, SCALE).unwrap());
+        assert_eq!(count(TpchTable::Region), 5);
+        assert_eq!(count(TpchTable::Nation), 25);
+        assert_eq!(count(TpchTable::Supplier), 10);
+        assert_eq!(count(TpchTable::Part), 200);
+        assert_eq!(count(TpchTable::PartSupp), 800);
+        assert_eq!(count(TpchTable::Customer), 150);
+        assert_eq!(count(TpchTable::Orders), 1500);
+
+        let lineitem = TpchDataSource::new(TpchTable::LineItem, SCALE).unwrap();
+        let lines = row_count(&lineitem);
+        assert!((5000..7000).contains(&lines), "{} line items", lines);
+        assert_eq!(
+            lineitem.statistics().unwrap().num_rows,
+            Precision::Exact(lines)
+        );
+
+        assert!(TpchDataSource::new(TpchTable::Part, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let source = TpchDataSource::new(TpchTable::LineItem, SCALE).unwrap();
+        let first = |source: &TpchDataSource| {
+            let batch = source.scan(None).unwrap().next().unwrap().unwrap();
+            (0..batch.column_count())
+                .map(|c| batch.field(c).get(0))
+                .collect::<Vec<_>>()
+        };
+        let expected = first(&source);
+        assert_eq!(first(&source.clone().with_batch_size(3)), expected);
+        assert_eq!(expected[0], Scalar::Int64(Some(1)));
+    }
+
+    #[test]
+    fn test_consistency() {
+        let orders: Vec<_> = TpchDataSource::new(TpchTable::Orders, SCALE)
+            .unwrap()
+            .scan(Some(&["o_orderkey", "o_custkey", "o_totalprice"]))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let batch = &orders[0];
+        for row in 0..batch.row_count() {
+            let Scalar::Int64(Some(customer)) = batch.field(1).get(row) else {
+                panic!("null customer");
+            };
+            assert!(customer % 3 != 0 && (1..=150).contains(&customer));
+        }
+        // Order keys are sparse: 8 of every 32
+        assert_eq!(batch.field(0).get(8), Scalar::Int64(Some(33)));
+
+        // Each part has four distinct suppliers
+        let scale = Scale::new(SCALE);
+        let mut suppliers: Vec<_> = (0..4).map(|i| scale.part_supplier(7, i)).collect();
+        suppliers.sort();
+        suppliers.dedup();
+        assert_eq!(suppliers.len(), 4);
+    }
+
+    #[test]
+    fn test_write_parquet() {
+        let dir = std::env::temp_dir().join(format!("dbms-tpch-{}", std::process::id()));
+        let format = SinkFormat::Parquet(ParquetWriteOptions::default());
+        write_tpch(&dir, SCALE, &format).unwrap();
+
+        let nation = ParquetDataSource::new(dir.join("nation.parquet"), 1024);
+        assert_eq!(nation.schema().unwrap(), TpchTable::Nation.schema());
+        assert_eq!(nation.statistics().unwrap().num_rows, Precision::Exact(25));
+        assert!(dir.join("lineitem.parquet").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_conformance() {
+        let source = TpchDataSource::new(TpchTable::Customer, SCALE)
+            .unwrap()
+            .with_batch_size(64)
+            .with_partitions(4);
+        conformance::check(&source);
+    }
+}
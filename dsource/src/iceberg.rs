@@ -0,0 +1,1087 @@
+//! Apache Iceberg table reader.
+//!
+//! An Iceberg table is described by a metadata JSON file listing its
+//! schemas, partition specs and snapshots. Each snapshot points to a
+//! manifest list, an Avro file naming the manifests, which are Avro files
+//! naming the data files. Only Parquet data files are read, and snapshots
+//! with delete files are rejected.
+//!
+//! Paths in the metadata are absolute, so they are resolved relative to the
+//! table location recorded in the metadata. The table can therefore be read
+//! from wherever it has been copied to.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use apache_avro::Reader;
+use apache_avro::types::Value as AvroValue;
+use dbms_dtype::{DataType, Field, Scalar, Schema};
+use serde_json::Value;
+
+use crate::object_store::local_object;
+use crate::pruning::may_match_all;
+use crate::{
+    BatchIterator, ColumnFilter, ColumnMatching, ColumnStatistics, DataSource, ObjectStore,
+    ParquetDataSource, Precision, Statistics, check_partition, resolve_projection,
+};
+
+/// Which snapshot of a table to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SnapshotSelection {
+    Current,
+    Id(i64),
+    /// The latest snapshot committed at or before a time in milliseconds
+    /// since the epoch.
+    AsOf(i64),
+}
+
+/// A data source over an Iceberg table at its current or an earlier
+/// snapshot.
+///
+/// Columns are matched to the columns of each data file by field id, so
+/// files written before a column was renamed or added are read with the
+/// snapshot's schema. Files that cannot hold rows matching the filters,
+/// judged by identity partition values and column bounds, are skipped.
+///
+/// The latest metadata file is looked up on every call, but the manifests
+/// are read again only when it changes.
+pub struct IcebergDataSource {
+    store: Arc<dyn ObjectStore>,
+    location: String,
+    snapshot: SnapshotSelection,
+    batch_size: usize,
+    filters: Vec<ColumnFilter>,
+    plan: Mutex<Option<CachedPlan>>,
+}
+
+/// A scan plan and the metadata file it was made from.
+struct CachedPlan {
+    metadata_location: String,
+    plan: Arc<ScanPlan>,
+}
+
+impl IcebergDataSource {
+    /// Creates a source over the Iceberg table in a local directory.
+    pub fn new(path: impl Into<PathBuf>, batch_size: usize) -> Self {
+        let (store, location) = local_object(&path.into());
+        Self::from_store(store, location, batch_size)
+    }
+
+    /// Creates a source over the Iceberg table under `location` in `store`.
+    pub fn from_store(
+        store: Arc<dyn ObjectStore>,
+        location: impl Into<String>,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            store,
+            location: location.into(),
+            snapshot: SnapshotSelection::Current,
+            batch_size,
+            filters: Vec::new(),
+            plan: Mutex::new(None),
+        }
+    }
+
+    /// Reads the snapshot with the given id instead of the current one.
+    pub fn with_snapshot_id(mut self, snapshot_id: i64) -> Self {
+        self.snapshot = SnapshotSelection::Id(snapshot_id);
+        self.plan = Mutex::new(None);
+        self
+    }
+
+    /// Reads the table as it was at a time in milliseconds since the epoch.
+    pub fn with_timestamp(mut self, timestamp_ms: i64) -> Self {
+        self.snapshot = SnapshotSelection::AsOf(timestamp_ms);
+        self.plan = Mutex::new(None);
+        self
+    }
+
+    /// Skips files that cannot hold rows matching every filter. Rows of the
+    /// files read are not filtered.
+    pub fn with_filters(mut self, filters: Vec<ColumnFilter>) -> Self {
+        self.filters = filters;
+        self.plan = Mutex::new(None);
+        self
+    }
+
+    /// Returns the id of the snapshot that is read, or `None` for a table
+    /// without snapshots.
+    pub fn snapshot_id(&self) -> Result<Option<i64>, String> {
+        Ok(self.scan_plan()?.snapshot_id)
+    }
+
+    /// Returns the locations of the data files that are read, after pruning.
+    pub fn data_files(&self) -> Result<Vec<String>, String> {
+        Ok(self
+            .scan_plan()?
+            .files
+            .iter()
+            .map(|file| file.location.clone())
+            .collect())
+    }
+
+    /// Returns the location of `path` relative to the table root.
+    fn child(&self, path: &str) -> String {
+        match self.location.trim_end_matches('/') {
+            "" => path.to_string(),
+            root => format!("{}/{}", root, path),
+        }
+    }
+
+    /// Returns the location of the latest metadata file, named by
+    /// `version-hint.text` or else the highest numbered one.
+    fn metadata_location(&self) -> Result<String, String> {
+        if let Ok(hint) = self.store.get(&self.child("metadata/version-hint.text"))
+            && let Ok(version) = String::from_utf8_lossy(&hint).trim().parse::<u64>()
+        {
+            return Ok(self.child(&format!("metadata/v{}.metadata.json", version)));
+        }
+
+        let prefix = self.child("metadata/");
+        self.store
+            .list(&prefix)?
+            .into_iter()
+            .filter_map(|meta| {
+                let name = meta.location.strip_prefix(&prefix)?;
+                let stem = name.strip_suffix(".metadata.json")?;
+                let digits = stem.trim_start_matches('v').split(['.', '-']).next()?;
+                Some((digits.parse::<u64>().ok()?, meta.location))
+            })
+            .max()
+            .map(|(_, location)| location)
+            .ok_or_else(|| format!("{}: not an Iceberg table", self.location))
+    }
+
+    /// Resolves an absolute path from the metadata against the table root.
+    fn resolve(&self, table_location: &str, path: &str) -> Result<String, String> {
+        let strip_scheme = |path: &str| {
+            let path = path.strip_prefix("file:").unwrap_or(path);
+            path.trim_start_matches('/').to_string()
+        };
+        let root = strip_scheme(table_location);
+        let path = strip_scheme(path);
+        match path.strip_prefix(root.trim_end_matches('/')) {
+            Some(relative) if relative.starts_with('/') => {
+                Ok(self.child(relative.trim_start_matches('/')))
+            }
+            _ => Err(format!(
+                "{}: file is outside the table location {}",
+                path, table_location
+            )),
+        }
+    }
+
+    /// Reads the metadata, selects a snapshot and lists its pruned files,
+    /// reusing the previous plan if the metadata file is the same. Metadata
+    /// files are never rewritten, so the plan cannot be stale.
+    fn scan_plan(&self) -> Result<Arc<ScanPlan>, String> {
+        let location = self.metadata_location()?;
+        let mut cached = self.plan.lock().map_err(|e| e.to_string())?;
+        if let Some(c) = cached.as_ref()
+            && c.metadata_location == location
+        {
+            return Ok(c.plan.clone());
+        }
+
+        let data = self.store.get(&location)?;
+        let metadata: Value =
+            serde_json::from_slice(&data).map_err(|e| format!("{}: {}", location, e))?;
+        let table_location = metadata["location"]
+            .as_str()
+            .ok_or_else(|| format!("{}: metadata has no location", location))?;
+
+        let snapshots = metadata["snapshots"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let snapshot = match self.snapshot {
+            SnapshotSelection::Current => match metadata["current-snapshot-id"].as_i64() {
+                Some(id) if id >= 0 => Some(find_snapshot(&snapshots, id)?),
+                _ => None,
+            },
+            SnapshotSelection::Id(id) => Some(find_snapshot(&snapshots, id)?),
+            SnapshotSelection::AsOf(timestamp) => Some(
+                snapshots
+                    .iter()
+                    .filter(|s| s["timestamp-ms"].as_i64().is_some_and(|t| t <= timestamp))
+                    .max_by_key(|s| s["timestamp-ms"].as_i64())
+                    .ok_or_else(|| format!("no snapshot at or before {}", timestamp))?,
+            ),
+        };
+
+        let schema_id = snapshot
+            .and_then(|s| s["schema-id"].as_i64())
+            .or_else(|| metadata["current-schema-id"].as_i64());
+        let schema = match metadata["schemas"].as_array() {
+            Some(schemas) => schemas
+                .iter()
+                .find(|s| schema_id.is_none() || s["schema-id"].as_i64() == schema_id)
+                .ok_or_else(|| format!("{}: schema {:?} not found", location, schema_id))?,
+            None => &metadata["schema"],
+        };
+        let table = TableSchema::parse(schema)?;
+        let specs = PartitionSpec::parse_all(&metadata["partition-specs"], &table)?;
+
+        let mut files = Vec::new();
+        if let Some(snapshot) = snapshot {
+            let manifest_list = snapshot["manifest-list"]
+                .as_str()
+                .ok_or("snapshot has no manifest list; v1 manifest arrays are not supported")?;
+            for manifest in read_avro(&*self.store, &self.resolve(table_location, manifest_list)?)?
+            {
+                if long(field(&manifest, "content")).unwrap_or(0) != 0 {
+                    return Err("delete files are not supported".to_string());
+                }
+                let spec = long(field(&manifest, "partition_spec_id")).unwrap_or(0);
+                let spec = specs.get(&spec).cloned().unwrap_or_default();
+                if !self.may_match_manifest(&manifest, &spec, &table) {
+                    continue;
+                }
+                let path =
+                    string(field(&manifest, "manifest_path")).ok_or("manifest has no path")?;
+                for entry in read_avro(&*self.store, &self.resolve(table_location, path)?)? {
+                    // Entries of status 2 record deleted files
+                    if long(field(&entry, "status")) == Some(2) {
+                        continue;
+                    }
+                    let data_file = field(&entry, "data_file").ok_or("entry has no data file")?;
+                    let file = DataFile::try_new(data_file, &spec, &table, |path| {
+                        self.resolve(table_location, path)
+                    })?;
+                    if may_match_all(&self.filters, &file.statistics, |name| table.index_of(name)) {
+                        files.push(file);
+                    }
+                }
+            }
+        }
+
+        let plan = Arc::new(ScanPlan {
+            snapshot_id: snapshot.and_then(|s| s["snapshot-id"].as_i64()),
+            schema: table.schema,
+            column_matching: ColumnMatching::ByFieldId(table.ids),
+            files,
+        });
+        *cached = Some(CachedPlan {
+            metadata_location: location,
+            plan: plan.clone(),
+        });
+        Ok(plan)
+    }
+
+    /// Returns false if the partition bounds of a manifest show that none of
+    /// its files can match the filters.
+    fn may_match_manifest(
+        &self,
+        manifest: &AvroValue,
+        spec: &PartitionSpec,
+        table: &TableSchema,
+    ) -> bool {
+        let Some(AvroValue::Array(summaries)) = field(manifest, "partitions") else {
+            return true;
+        };
+        let mut statistics = Statistics::unknown(&table.schema);
+        for (summary, source) in summaries.iter().zip(&spec.identity_sources) {
+            let Some(column) = source else {
+                continue;
+            };
+            let dtype = table.schema.fields()[*column].dtype();
+            let bound =
+                |name| match bytes(field(summary, name)).and_then(|b| decode_bound(b, dtype)) {
+                    Some(value) => Precision::Inexact(value),
+                    None => Precision::Absent,
+                };
+            statistics.column_statistics[*column] = ColumnStatistics {
+                null_count: match field(summary, "contains_null") {
+                    Some(AvroValue::Boolean(false)) => Precision::Exact(0),
+                    _ => Precision::Absent,
+                },
+                min_value: bound("lower_bound"),
+                max_value: bound("upper_bound"),
+                distinct_count: Precision::Absent,
+            };
+        }
+        may_match_all(&self.filters, &statistics, |name| table.index_of(name))
+    }
+}
+
+impl DataSource for IcebergDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        Ok(self.scan_plan()?.schema.clone())
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        self.scan_plan()?
+            .read(&self.store, self.batch_size, None, projection)
+    }
+
+    /// Each data file is a partition.
+    fn partition_count(&self) -> Result<usize, String> {
+        Ok(self.scan_plan()?.files.len().max(1))
+    }
+
+    fn scan_partition(
+        &self,
+        partition: usize,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let plan = self.scan_plan()?;
+        check_partition(partition, plan.files.len().max(1))?;
+        plan.read(&self.store, self.batch_size, Some(partition), projection)
+    }
+
+    /// Combines the statistics recorded in the manifests for each file,
+    /// without reading any data.
+    fn statistics(&self) -> Result<Statistics, String> {
+        let plan = self.scan_plan()?;
+        let empty = Statistics {
+            num_rows: Precision::Exact(0),
+            total_byte_size: Precision::Exact(0),
+            column_statistics: vec![ColumnStatistics::default(); plan.schema.fields().len()],
+        };
+        Ok(plan
+            .files
+            .iter()
+            .map(|file| file.statistics.clone())
+            .reduce(Statistics::merge)
+            .unwrap_or(empty))
+    }
+}
+
+fn find_snapshot(snapshots: &[Value], id: i64) -> Result<&Value, String> {
+    snapshots
+        .iter()
+        .find(|s| s["snapshot-id"].as_i64() == Some(id))
+        .ok_or_else(|| format!("snapshot {} not found", id))
+}
+
+/// The files of a snapshot to read, with the schema to read them with.
+struct ScanPlan {
+    snapshot_id: Option<i64>,
+    schema: Schema,
+    column_matching: ColumnMatching,
+    files: Vec<DataFile>,
+}
+
+impl ScanPlan {
+    /// Reads every file, or only the file of one partition.
+    fn read(
+        &self,
+        store: &Arc<dyn ObjectStore>,
+        batch_size: usize,
+        partition: Option<usize>,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let sources: Vec<_> = self
+            .files
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| partition.is_none_or(|p| p == *i))
+            .map(|(_, file)| {
+                ParquetDataSource::from_store(store.clone(), &file.location, batch_size)
+                    .with_table_schema(self.schema.clone())
+                    .with_column_matching(self.column_matching.clone())
+            })
+            .collect();
+        // Projections are checked even if there are no files
+        if let Some(cols) = projection {
+            resolve_projection(&self.schema, cols)?;
+        }
+        let projection: Option<Vec<String>> =
+            projection.map(|cols| cols.iter().map(|c| c.to_string()).collect());
+        Ok(Box::new(sources.into_iter().flat_map(
+            move |source| -> BatchIterator {
+                let cols: Option<Vec<&str>> = projection
+                    .as_ref()
+                    .map(|cols| cols.iter().map(String::as_str).collect());
+                match source.scan(cols.as_deref()) {
+                    Ok(batches) => batches,
+                    Err(e) => Box::new(std::iter::once(Err(e))),
+                }
+            },
+        )))
+    }
+}
+
+/// A table schema with the field id of each column.
+struct TableSchema {
+    schema: Schema,
+    ids: HashMap<String, i32>,
+}
+
+impl TableSchema {
+    fn parse(schema: &Value) -> Result<Self, String> {
+        let fields = schema["fields"].as_array().ok_or("schema has no fields")?;
+        let mut columns = Vec::with_capacity(fields.len());
+        let mut ids = HashMap::new();
+        for field in fields {
+            let name = field["name"].as_str().ok_or("schema field has no name")?;
+            let id = field["id"]
+                .as_i64()
+                .ok_or_else(|| format!("column {} has no field id", name))?;
+            let dtype = match field["type"].as_str() {
+                Some("boolean") => DataType::Boolean,
+                Some("int") => DataType::Int32,
+                Some("long") => DataType::Int64,
+                Some("float") => DataType::Float32,
+                Some("double") => DataType::Float64,
+                Some("string") => DataType::Utf8,
+                Some("binary") => DataType::Binary,
+                _ => {
+                    return Err(format!(
+                        "unsupported Iceberg type for column {}: {}",
+                        name, field["type"]
+                    ));
+                }
+            };
+            columns.push(Field::new(name, dtype));
+            ids.insert(name.to_string(), id as i32);
+        }
+        Ok(Self {
+            schema: Schema::new(columns),
+            ids,
+        })
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.schema.fields().iter().position(|f| f.name() == name)
+    }
+
+    fn index_of_id(&self, id: i64) -> Option<usize> {
+        let (name, _) = self.ids.iter().find(|(_, i)| **i as i64 == id)?;
+        self.index_of(name)
+    }
+}
+
+/// The partition fields of a spec, as far as they are used for pruning.
+#[derive(Debug, Clone, Default)]
+struct PartitionSpec {
+    /// Name of each partition field, in order.
+    names: Vec<String>,
+    /// The table column each partition field holds the value of, for fields
+    /// with the identity transform.
+    identity_sources: Vec<Option<usize>>,
+}
+
+impl PartitionSpec {
+    fn parse_all(specs: &Value, table: &TableSchema) -> Result<HashMap<i64, Self>, String> {
+        let mut parsed = HashMap::new();
+        for spec in specs.as_array().into_iter().flatten() {
+            let id = spec["spec-id"].as_i64().ok_or("partition spec has no id")?;
+            let fields = spec["fields"].as_array().cloned().unwrap_or_default();
+            parsed.insert(
+                id,
+                Self {
+                    names: fields
+                        .iter()
+                        .map(|f| f["name"].as_str().unwrap_or_default().to_string())
+                        .collect(),
+                    identity_sources: fields
+                        .iter()
+                        .map(|f| match f["transform"].as_str() {
+                            Some("identity") => table.index_of_id(f["source-id"].as_i64()?),
+                            _ => None,
+                        })
+                        .collect(),
+                },
+            );
+        }
+        Ok(parsed)
+    }
+}
+
+/// A live data file of a snapshot.
+struct DataFile {
+    location: String,
+    statistics: Statistics,
+}
+
+impl DataFile {
+    fn try_new(
+        data_file: &AvroValue,
+        spec: &PartitionSpec,
+        table: &TableSchema,
+        resolve: impl Fn(&str) -> Result<String, String>,
+    ) -> Result<Self, String> {
+        let path = string(field(data_file, "file_path")).ok_or("data file has no path")?;
+        if long(field(data_file, "content")).unwrap_or(0) != 0 {
+            return Err("delete files are not supported".to_string());
+        }
+        match string(field(data_file, "file_format")) {
+            Some(format) if format.eq_ignore_ascii_case("parquet") => {}
+            format => {
+                return Err(format!(
+                    "{}: unsupported data file format {}",
+                    path,
+                    format.unwrap_or_default()
+                ));
+            }
+        }
+
+        let num_rows = match long(field(data_file, "record_count")) {
+            Some(rows) => Precision::Exact(rows as usize),
+            None => Precision::Absent,
+        };
+        let null_counts = int_map(field(data_file, "null_value_counts"));
+        let lower_bounds = int_map(field(data_file, "lower_bounds"));
+        let upper_bounds = int_map(field(data_file, "upper_bounds"));
+
+        let mut column_statistics = Vec::with_capacity(table.schema.fields().len());
+        for column in table.schema.fields() {
+            let id = table.ids[column.name()] as i64;
+            let dtype = column.dtype();
+            let bound = |bounds: &HashMap<i64, &AvroValue>| {
+                match bounds
+                    .get(&id)
+                    .and_then(|v| bytes(Some(v)))
+                    .and_then(|b| decode_bound(b, dtype))
+                {
+                    // Long strings may be truncated
+                    Some(value) if *dtype == DataType::Utf8 => Precision::Inexact(value),
+                    Some(value) => Precision::Exact(value),
+                    None => Precision::Absent,
+                }
+            };
+            column_statistics.push(ColumnStatistics {
+                null_count: match null_counts.get(&id).and_then(|v| long(Some(v))) {
+                    Some(nulls) => Precision::Exact(nulls as usize),
+                    None => Precision::Absent,
+                },
+                min_value: bound(&lower_bounds),
+                max_value: bound(&upper_bounds),
+                distinct_count: Precision::Absent,
+            });
+        }
+
+        // Identity partition values bound their source columns exactly
+        let partition = field(data_file, "partition");
+        for (name, source) in spec.names.iter().zip(&spec.identity_sources) {
+            let Some(column) = *source else {
+                continue;
+            };
+            let dtype = table.schema.fields()[column].dtype().clone();
+            let value = match field_or_null(partition, name) {
+                Some(value) => avro_scalar(value, &dtype)
+                    .ok_or_else(|| format!("{}: invalid partition value for {}", path, name))?,
+                None => Scalar::null(dtype.clone()),
+            };
+            let is_null = value == Scalar::null(dtype);
+            column_statistics[column] = ColumnStatistics {
+                null_count: match is_null {
+                    true => num_rows.clone(),
+                    false => Precision::Exact(0),
+                },
+                min_value: Precision::Exact(value.clone()),
+                max_value: Precision::Exact(value),
+                distinct_count: Precision::Exact(usize::from(!is_null)),
+            };
+        }
+
+        let total_byte_size = match long(field(data_file, "file_size_in_bytes")) {
+            Some(size) => Precision::Exact(size as usize),
+            None => Precision::Absent,
+        };
+        Ok(Self {
+            location: resolve(path)?,
+            statistics: Statistics {
+                num_rows,
+                total_byte_size,
+                column_statistics,
+            },
+        })
+    }
+}
+
+/// Reads every record of an Avro file.
+fn read_avro(store: &dyn ObjectStore, location: &str) -> Result<Vec<AvroValue>, String> {
+    let data = store.get(location)?;
+    let reader = Reader::new(&data[..]).map_err(|e| format!("{}: {}", location, e))?;
+    reader
+        .map(|record| record.map_err(|e| format!("{}: {}", location, e)))
+        .collect()
+}
+
+/// Strips a union, leaving the value of its branch.
+fn unwrap(value: &AvroValue) -> &AvroValue {
+    match value {
+        AvroValue::Union(_, inner) => inner,
+        value => value,
+    }
+}
+
+/// Returns a field of a record, or `None` if it is missing or null.
+fn field<'a>(record: &'a AvroValue, name: &str) -> Option<&'a AvroValue> {
+    field_or_null(Some(record), name)
+}
+
+fn field_or_null<'a>(record: Option<&'a AvroValue>, name: &str) -> Option<&'a AvroValue> {
+    let AvroValue::Record(fields) = unwrap(record?) else {
+        return None;
+    };
+    let (_, value) = fields.iter().find(|(field, _)| field == name)?;
+    Some(unwrap(value)).filter(|value| **value != AvroValue::Null)
+}
+
+fn long(value: Option<&AvroValue>) -> Option<i64> {
+    match unwrap(value?) {
+        AvroValue::Int(v) => Some(*v as i64),
+        AvroValue::Long(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn string(value: Option<&AvroValue>) -> Option<&str> {
+    match unwrap(value?) {
+        AvroValue::String(v) | AvroValue::Enum(_, v) => Some(v),
+        _ => None,
+    }
+}
+
+fn bytes(value: Option<&AvroValue>) -> Option<&[u8]> {
+    match unwrap(value?) {
+        AvroValue::Bytes(v) | AvroValue::Fixed(_, v) => Some(v),
+        _ => None,
+    }
+}
+
+/// Reads a map keyed by field id, which Avro stores as an array of
+/// key-value records.
+fn int_map(value: Option<&AvroValue>) -> HashMap<i64, &AvroValue> {
+    let mut map = HashMap::new();
+    if let Some(AvroValue::Array(entries)) = value {
+        for entry in entries {
+            if let (Some(key), Some(value)) = (long(field(entry, "key")), field(entry, "value")) {
+                map.insert(key, value);
+            }
+        }
+    }
+    map
+}
+
+/// Converts a partition value to a scalar of the column type.
+fn avro_scalar(value: &AvroValue, dtype: &DataType) -> Option<Scalar> {
+    Some(match (dtype, unwrap(value)) {
+        (DataType::Boolean, AvroValue::Boolean(v)) => Scalar::Boolean(Some(*v)),
+        (DataType::Int32, AvroValue::Int(v)) => Scalar::Int32(Some(*v)),
+        (DataType::Int64, AvroValue::Int(v)) => Scalar::Int64(Some(*v as i64)),
+        (DataType::Int64, AvroValue::Long(v)) => Scalar::Int64(Some(*v)),
+        (DataType::Float32, AvroValue::Float(v)) => Scalar::Float32(Some(*v)),
+        (DataType::Float64, AvroValue::Float(v)) => Scalar::Float64(Some(*v as f64)),
+        (DataType::Float64, AvroValue::Double(v)) => Scalar::Float64(Some(*v)),
+        (DataType::Utf8, AvroValue::String(v)) => Scalar::Utf8(Some(v.clone())),
+        (DataType::Binary, AvroValue::Bytes(v)) => Scalar::Binary(Some(v.clone())),
+        _ => return None,
+    })
+}
+
+/// Decodes a bound in Iceberg's single-value binary serialization. Bounds of
+/// a column widened from `int` or `float` keep the narrower encoding.
+fn decode_bound(bytes: &[u8], dtype: &DataType) -> Option<Scalar> {
+    Some(match (dtype, bytes.len()) {
+        (DataType::Boolean, 1) => Scalar::Boolean(Some(bytes[0] != 0)),
+        (DataType::Int32, 4) => Scalar::Int32(Some(i32::from_le_bytes(bytes.try_into().ok()?))),
+        (DataType::Int64, 4) => {
+            Scalar::Int64(Some(i32::from_le_bytes(bytes.try_into().ok()?) as i64))
+        }
+        (DataType::Int64, 8) => Scalar::Int64(Some(i64::from_le_bytes(bytes.try_into().ok()?))),
+        (DataType::Float32, 4) => Scalar::Float32(Some(f32::from_le_bytes(bytes.try_into().ok()?))),
+        (DataType::Float64, 4) => {
+            Scalar::Float64(Some(f32::from_le_bytes(bytes.try_into().ok()?) as f64))
+        }
+        (DataType::Float64, 8) => Scalar::Float64(Some(f64::from_le_bytes(bytes.try_into().ok()?))),
+        (DataType::Utf8, _) => Scalar::Utf8(Some(String::from_utf8(bytes.to_vec()).ok()?)),
+        (DataType::Binary, _) => Scalar::Binary(Some(bytes.to_vec())),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FilterOp, InMemoryObjectStore, conformance};
+    use apache_avro::Writer;
+    use apache_avro::schema::Schema as AvroSchema;
+    use arrow::array::{ArrayRef, Float64Array, Int64Array, StringArray};
+    use arrow::datatypes::{Field as ArrowField, Schema as ArrowSchema};
+    use arrow::record_batch::RecordBatch as ArrowRecordBatch;
+    use parquet::arrow::{ArrowWriter, PARQUET_FIELD_ID_META_KEY};
+    use serde_json::json;
+
+    const ROOT: &str = "file:///warehouse/db/events";
+
+    const MANIFEST_LIST: &str = r#"{"type": "record", "name": "manifest_file", "fields": [
+        {"name": "manifest_path", "type": "string"},
+        {"name": "partition_spec_id", "type": "int"},
+        {"name": "content", "type": "int"},
+        {"name": "partitions", "type": ["null", {"type": "array", "items": {
+            "type": "record", "name": "r508", "fields": [
+                {"name": "contains_null", "type": "boolean"},
+                {"name": "lower_bound", "type": ["null", "bytes"]},
+                {"name": "upper_bound", "type": ["null", "bytes"]}
+            ]}}]}
+    ]}"#;
+
+    const MANIFEST: &str = r#"{"type": "record", "name": "manifest_entry", "fields": [
+        {"name": "status", "type": "int"},
+        {"name": "data_file", "type": {"type": "record", "name": "r2", "fields": [
+            {"name": "content", "type": "int"},
+            {"name": "file_path", "type": "string"},
+            {"name": "file_format", "type": "string"},
+            {"name": "partition", "type": {"type": "record", "name": "r102", "fields": [
+                {"name": "region", "type": ["null", "string"]}
+            ]}},
+            {"name": "record_count", "type": "long"},
+            {"name": "file_size_in_bytes", "type": "long"},
+            {"name": "lower_bounds", "type": ["null", {"type": "array", "items": {
+                "type": "record", "name": "k126_v127", "fields": [
+                    {"name": "key", "type": "int"}, {"name": "value", "type": "bytes"}
+                ]}}]},
+            {"name": "upper_bounds", "type": ["null", {"type": "array", "items": {
+                "type": "record", "name": "k129_v130", "fields": [
+                    {"name": "key", "type": "int"}, {"name": "value", "type": "bytes"}
+                ]}}]}
+        ]}}
+    ]}"#;
+
+    fn write_avro(store: &InMemoryObjectStore, path: &str, schema: &str, records: Vec<AvroValue>) {
+        let schema = AvroSchema::parse_str(schema).unwrap();
+        let mut writer = Writer::new(&schema, Vec::new());
+        for record in records {
+            writer.append(record).unwrap();
+        }
+        store
+            .put(
+                &format!("events/{}", path),
+                writer.into_inner().unwrap().into(),
+            )
+            .unwrap();
+    }
+
+    fn record(fields: Vec<(&str, AvroValue)>) -> AvroValue {
+        AvroValue::Record(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        )
+    }
+
+    fn some(value: AvroValue) -> AvroValue {
+        AvroValue::Union(1, Box::new(value))
+    }
+
+    /// The bounds of the `id` column, field id 1.
+    fn id_bound(id: i64) -> AvroValue {
+        some(AvroValue::Array(vec![record(vec![
+            ("key", AvroValue::Int(1)),
+            ("value", AvroValue::Bytes(id.to_le_bytes().to_vec())),
+        ])]))
+    }
+
+    /// Writes a data file with the given columns and field ids, returning a
+    /// manifest entry for it.
+    fn data_file(
+        store: &InMemoryObjectStore,
+        name: &str,
+        region: &str,
+        columns: Vec<(&str, i32, ArrayRef)>,
+        status: i32,
+    ) -> AvroValue {
+        let fields: Vec<_> = columns
+            .iter()
+            .map(|(name, id, array)| {
+                ArrowField::new(*name, array.data_type().clone(), true).with_metadata(
+                    HashMap::from([(PARQUET_FIELD_ID_META_KEY.to_string(), id.to_string())]),
+                )
+            })
+            .collect();
+        let schema = Arc::new(ArrowSchema::new(fields));
+        let arrays: Vec<ArrayRef> = columns.iter().map(|(_, _, a)| a.clone()).collect();
+        let batch = ArrowRecordBatch::try_new(schema.clone(), arrays).unwrap();
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let (min, max) = (ids.value(0), ids.value(ids.len() - 1));
+
+        let mut data = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut data, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let path = format!("data/region={}/{}.parquet", region, name);
+        store.put(&format!("events/{}", path), data.into()).unwrap();
+
+        record(vec![
+            ("status", AvroValue::Int(status)),
+            (
+                "data_file",
+                record(vec![
+                    ("content", AvroValue::Int(0)),
+                    ("file_path", AvroValue::String(format!("{}/{}", ROOT, path))),
+                    ("file_format", AvroValue::String("PARQUET".to_string())),
+                    (
+                        "partition",
+                        record(vec![(
+                            "region",
+                            some(AvroValue::String(region.to_string())),
+                        )]),
+                    ),
+                    ("record_count", AvroValue::Long(batch.num_rows() as i64)),
+                    ("file_size_in_bytes", AvroValue::Long(1)),
+                    ("lower_bounds", id_bound(min)),
+                    ("upper_bounds", id_bound(max)),
+                ]),
+            ),
+        ])
+    }
+
+    /// Writes a manifest list naming manifests of files in a single region.
+    fn manifest_list(store: &InMemoryObjectStore, snapshot: i64, manifests: &[(&str, i32, &str)]) {
+        let records = manifests
+            .iter()
+            .map(|(name, content, region)| {
+                let bound = || some(AvroValue::Bytes(region.as_bytes().to_vec()));
+                let summary = record(vec![
+                    ("contains_null", AvroValue::Boolean(false)),
+                    ("lower_bound", bound()),
+                    ("upper_bound", bound()),
+                ]);
+                record(vec![
+                    (
+                        "manifest_path",
+                        AvroValue::String(format!("{}/metadata/{}", ROOT, name)),
+                    ),
+                    ("partition_spec_id", AvroValue::Int(0)),
+                    ("content", AvroValue::Int(*content)),
+                    ("partitions", some(AvroValue::Array(vec![summary]))),
+                ])
+            })
+            .collect();
+        let path = format!("metadata/snap-{}.avro", snapshot);
+        write_avro(store, &path, MANIFEST_LIST, records);
+    }
+
+    /// A table with three snapshots: an append, an append after renaming
+    /// `name` to `label` and adding `score`, and a delete of the first file.
+    fn table() -> Arc<InMemoryObjectStore> {
+        let store = Arc::new(InMemoryObjectStore::new());
+        let a = |status| {
+            data_file(
+                &store,
+                "a",
+                "eu",
+                vec![
+                    ("id", 1, Arc::new(Int64Array::from(vec![1, 2]))),
+                    ("name", 2, Arc::new(StringArray::from(vec!["n1", "n2"]))),
+                ],
+                status,
+            )
+        };
+        let b = data_file(
+            &store,
+            "b",
+            "us",
+            vec![
+                ("id", 1, Arc::new(Int64Array::from(vec![3, 4, 5]))),
+                (
+                    "label",
+                    2,
+                    Arc::new(StringArray::from(vec!["n3", "n4", "n5"])),
+                ),
+                (
+                    "score",
+                    4,
+                    Arc::new(Float64Array::from(vec![0.5, 1.5, 2.5])),
+                ),
+            ],
+            1,
+        );
+        write_avro(&store, "metadata/m1.avro", MANIFEST, vec![a(1)]);
+        write_avro(&store, "metadata/m2.avro", MANIFEST, vec![b]);
+        write_avro(&store, "metadata/m3.avro", MANIFEST, vec![a(2)]);
+        manifest_list(&store, 1, &[("m1.avro", 0, "eu")]);
+        manifest_list(&store, 2, &[("m1.avro", 0, "eu"), ("m2.avro", 0, "us")]);
+        manifest_list(&store, 3, &[("m3.avro", 0, "eu"), ("m2.avro", 0, "us")]);
+
+        let column =
+            |id, name, dtype| json!({"id": id, "name": name, "required": false, "type": dtype});
+        let snapshot = |id: i64, schema_id| {
+            json!({
+                "snapshot-id": id,
+                "timestamp-ms": id * 1000,
+                "schema-id": schema_id,
+                "manifest-list": format!("{}/metadata/snap-{}.avro", ROOT, id),
+            })
+        };
+        let metadata = json!({
+            "format-version": 2,
+            "location": ROOT,
+            "current-schema-id": 1,
+            "schemas": [
+                {"schema-id": 0, "type": "struct", "fields": [
+                    column(1, "id", "long"),
+                    column(2, "name", "string"),
+                    column(3, "region", "string"),
+                ]},
+                {"schema-id": 1, "type": "struct", "fields": [
+                    column(1, "id", "long"),
+                    column(2, "label", "string"),
+                    column(3, "region", "string"),
+                    column(4, "score", "double"),
+                ]},
+            ],
+            "default-spec-id": 0,
+            "partition-specs": [{"spec-id": 0, "fields": [
+                {"name": "region", "transform": "identity", "source-id": 3, "field-id": 1000}
+            ]}],
+            "current-snapshot-id": 3,
+            "snapshots": [snapshot(1, 0), snapshot(2, 1), snapshot(3, 1)],
+        });
+        store
+            .put(
+                "events/metadata/v3.metadata.json",
+                metadata.to_string().into(),
+            )
+            .unwrap();
+        store
+            .put("events/metadata/version-hint.text", "3".into())
+            .unwrap();
+        store
+    }
+
+    fn rows(source: &IcebergDataSource, columns: &[&str]) -> Vec<Vec<Scalar>> {
+        let mut rows: Vec<_> = source
+            .scan(Some(columns))
+            .unwrap()
+            .flat_map(|batch| {
+                let batch = batch.unwrap();
+                (0..batch.row_count())
+                    .map(|i| {
+                        (0..batch.column_count())
+                            .map(|c| batch.field(c).get(i))
+                            .collect()
+                    })
+                    .collect::<Vec<Vec<_>>>()
+            })
+            .collect();
+        rows.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        rows
+    }
+
+    fn int64(v: i64) -> Scalar {
+        Scalar::Int64(Some(v))
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let store = table();
+        let current = IcebergDataSource::from_store(store.clone(), "events", 1024);
+        assert_eq!(current.snapshot_id().unwrap(), Some(3));
+        assert_eq!(
+            current.data_files().unwrap(),
+            ["events/data/region=us/b.parquet"]
+        );
+        assert_eq!(
+            rows(&current, &["id"]),
+            [[int64(3)], [int64(4)], [int64(5)]]
+        );
+
+        // Historical snapshots are read with their own schema
+        let first =
+            IcebergDataSource::from_store(store.clone(), "events", 1024).with_timestamp(1500);
+        assert_eq!(first.snapshot_id().unwrap(), Some(1));
+        assert_eq!(first.schema().unwrap().fields()[1].name(), "name");
+        assert_eq!(rows(&first, &["id"]), [[int64(1)], [int64(2)]]);
+
+        let err = IcebergDataSource::from_store(store, "events", 1024)
+            .with_snapshot_id(9)
+            .schema()
+            .unwrap_err();
+        assert_eq!(err, "snapshot 9 not found");
+    }
+
+    #[test]
+    fn test_plan_cache() {
+        let store = table();
+        let source = IcebergDataSource::from_store(store.clone(), "events", 1024);
+        assert_eq!(source.partition_count().unwrap(), 1);
+
+        // The manifests are not read again while the metadata is unchanged
+        store.put("events/metadata/m2.avro", "".into()).unwrap();
+        assert_eq!(rows(&source, &["id"]).len(), 3);
+
+        // A new metadata file is planned from scratch
+        let metadata = store.get("events/metadata/v3.metadata.json").unwrap();
+        store
+            .put("events/metadata/v4.metadata.json", metadata)
+            .unwrap();
+        store
+            .put("events/metadata/version-hint.text", "4".into())
+            .unwrap();
+        assert!(source.snapshot_id().is_err());
+    }
+
+    #[test]
+    fn test_schema_evolution() {
+        let source = IcebergDataSource::from_store(table(), "events", 1024).with_snapshot_id(2);
+        let label = |s: &str| Scalar::Utf8(Some(s.to_string()));
+
+        // The renamed column is found by id, and the added one is null in old files
+        let rows = rows(&source, &["id", "label", "score"]);
+        assert_eq!(rows[0], [int64(1), label("n1"), Scalar::Float64(None)]);
+        assert_eq!(rows[4], [int64(5), label("n5"), Scalar::Float64(Some(2.5))]);
+    }
+
+    #[test]
+    fn test_pruning() {
+        let source = |filter| {
+            IcebergDataSource::from_store(table(), "events", 1024)
+                .with_snapshot_id(2)
+                .with_filters(vec![filter])
+        };
+        let eu = ColumnFilter::new("region", FilterOp::Eq(Scalar::Utf8(Some("eu".to_string()))));
+        assert_eq!(
+            source(eu).data_files().unwrap(),
+            ["events/data/region=eu/a.parquet"]
+        );
+
+        let large = source(ColumnFilter::new("id", FilterOp::Gt(int64(2))));
+        assert_eq!(
+            large.data_files().unwrap(),
+            ["events/data/region=us/b.parquet"]
+        );
+        assert_eq!(large.statistics().unwrap().num_rows, Precision::Exact(3));
+    }
+
+    #[test]
+    fn test_delete_files() {
+        let store = table();
+        manifest_list(&store, 3, &[("m2.avro", 0, "us"), ("m3.avro", 1, "eu")]);
+        let err = IcebergDataSource::from_store(store, "events", 1024)
+            .schema()
+            .unwrap_err();
+        assert_eq!(err, "delete files are not supported");
+    }
+
+    #[test]
+    fn test_conformance() {
+        conformance::check(
+            &IcebergDataSource::from_store(table(), "events", 1).with_snapshot_id(2),
+        );
+    }
+
+    #[test]
+    fn test_decode_bound() {
+        assert_eq!(
+            decode_bound(&7i32.to_le_bytes(), &DataType::Int64),
+            Some(int64(7))
+        );
+        assert_eq!(
+            decode_bound(b"abc", &DataType::Utf8),
+            Some(Scalar::Utf8(Some("abc".to_string())))
+        );
+        assert_eq!(decode_bound(&[1, 2], &DataType::Int32), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Arrow IPC data source implementation.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arrow::buffer::Buffer;
+use arrow::datatypes::SchemaRef;
+use arrow::ipc::convert::fb_to_schema;
+use arrow::ipc::reader::{FileDecoder, FileReader, StreamReader, read_footer_length};
+use arrow::ipc::{Block, root_as_footer};
+use arrow::record_batch::RecordBatch as ArrowRecordBatch;
+use bytes::Bytes;
+use dbms_dtype::Schema;
+use memmap2::Mmap;
+
+use crate::{BatchIterator, DataSource, resolve_projection};
+
+/// Magic bytes at the start of an Arrow IPC file.
+const FILE_MAGIC: &[u8; 6] = b"ARROW1";
+
+/// The Arrow IPC encoding of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpcFormat {
+    /// The random-access file format (Feather v2), with a footer indexing each batch.
+    File,
+    /// The streaming format, read sequentially from the start.
+    Stream,
+}
+
+/// A data source that reads from Arrow IPC files or streams.
+pub struct ArrowIpcDataSource {
+    path: PathBuf,
+    format: Option<IpcFormat>,
+    memory_map: bool,
+}
+
+impl ArrowIpcDataSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            format: None,
+            memory_map: false,
+        }
+    }
+
+    /// Sets the IPC format, which is otherwise detected from the file's magic bytes.
+    pub fn with_format(mut self, format: IpcFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Memory-maps IPC files so that batches reference the mapped pages
+    /// instead of being copied. Has no effect on streams.
+    pub fn with_memory_map(mut self, memory_map: bool) -> Self {
+        self.memory_map = memory_map;
+        self
+    }
+
+    /// Returns the IPC format of the file, detecting it if not set.
+    fn format(&self) -> Result<IpcFormat, String> {
+        if let Some(format) = self.format {
+            return Ok(format);
+        }
+        let mut magic = [0u8; 6];
+        let mut file = File::open(&self.path).map_err(|e| e.to_string())?;
+        match file.read_exact(&mut magic) {
+            Ok(()) if &magic == FILE_MAGIC => Ok(IpcFormat::File),
+            _ => Ok(IpcFormat::Stream),
+        }
+    }
+}
+
+impl DataSource for ArrowIpcDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        let file = File::open(&self.path).map_err(|e| e.to_string())?;
+        let schema = match self.format()? {
+            IpcFormat::File => FileReader::try_new(file, None)
+                .map_err(|e| e.to_string())?
+                .schema(),
+            IpcFormat::Stream => StreamReader::try_new(BufReader::new(file), None)
+                .map_err(|e| e.to_string())?
+                .schema(),
+        };
+        Schema::try_from(schema.as_ref())
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        let indices = match projection {
+            Some(cols) => Some(resolve_projection(&self.schema()?, cols)?),
+            None => None,
+        };
+
+        let file = File::open(&self.path).map_err(|e| e.to_string())?;
+        let batches: Box<dyn Iterator<Item = Result<ArrowRecordBatch, String>> + Send> =
+            match self.format()? {
+                IpcFormat::File if self.memory_map => {
+                    Box::new(MappedFileReader::try_new(file, indices)?)
+                }
+                IpcFormat::File => Box::new(
+                    FileReader::try_new(file, indices)
+                        .map_err(|e| e.to_string())?
+                        .map(|batch| batch.map_err(|e| e.to_string())),
+                ),
+                IpcFormat::Stream => Box::new(
+                    StreamReader::try_new(BufReader::new(file), indices)
+                        .map_err(|e| e.to_string())?
+                        .map(|batch| batch.map_err(|e| e.to_string())),
+                ),
+            };
+
+        Ok(Box::new(batches.map(|batch| batch?.try_into())))
+    }
+}
+
+/// Reads record batches from a memory-mapped IPC file without copying them.
+struct MappedFileReader {
+    buffer: Buffer,
+    decoder: FileDecoder,
+    blocks: std::vec::IntoIter<Block>,
+}
+
+impl MappedFileReader {
+    fn try_new(file: File, projection: Option<Vec<usize>>) -> Result<Self, String> {
+        // SAFETY: the mapping is read-only; as with any mmap, the file must not be
+        // truncated or modified by another process while batches are alive.
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| e.to_string())?;
+        let buffer = Buffer::from(Bytes::from_owner(mmap));
+
+        if buffer.len() < FILE_MAGIC.len() * 2 + 10 {
+            return Err("file is too small to be an Arrow IPC file".to_string());
+        }
+        let trailer_start = buffer.len() - 10;
+        let footer_len = read_footer_length(buffer[trailer_start..].try_into().unwrap())
+            .map_err(|e| e.to_string())?;
+        let footer_start = trailer_start
+            .checked_sub(footer_len)
+            .ok_or_else(|| "IPC file footer is longer than the file".to_string())?;
+        let footer =
+            root_as_footer(&buffer[footer_start..trailer_start]).map_err(|e| e.to_string())?;
+
+        let fb_schema = footer
+            .schema()
+            .ok_or_else(|| "IPC file footer has no schema".to_string())?;
+        let schema: SchemaRef = Arc::new(fb_to_schema(fb_schema));
+        let mut decoder = FileDecoder::new(schema, footer.version());
+        if let Some(projection) = projection {
+            decoder = decoder.with_projection(projection);
+        }
+
+        for block in footer.dictionaries().iter().flatten() {
+            let data = block_data(&buffer, block)?;
+            decoder
+                .read_dictionary(block, &data)
+                .map_err(|e| e.to_string())?;
+        }
+
+        let blocks: Vec<Block> = footer
+            .recordBatches()
+            .map(|b| b.iter().copied().collect())
+            .unwrap_or_default();
+
+        Ok(Self {
+            buffer,
+            decoder,
+            blocks: blocks.into_iter(),
+        })
+    }
+}
+
+/// Returns the slice of the file holding a block's metadata and body, or an
+/// error if the block lies outside the file.
+fn block_data(buffer: &Buffer, block: &Block) -> Result<Buffer, String> {
+    let out_of_bounds = || "IPC block lies outside the file".to_string();
+    let offset = usize::try_from(block.offset()).map_err(|_| out_of_bounds())?;
+    let len = usize::try_from(block.metaDataLength())
+        .ok()
+        .zip(usize::try_from(block.bodyLength()).ok())
+        .and_then(|(metadata, body)| metadata.checked_add(body))
+        .ok_or_else(out_of_bounds)?;
+    match offset.checked_add(len) {
+        Some(end) if end <= buffer.len() => Ok(buffer.slice_with_length(offset, len)),
+        _ => Err(out_of_bounds()),
+    }
+}
+
+impl Iterator for MappedFileReader {
+    type Item = Result<ArrowRecordBatch, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let block = self.blocks.next()?;
+        let data = match block_data(&self.buffer, &block) {
+            Ok(data) => data,
+            Err(e) => return Some(Err(e)),
+        };
+        self.decoder
+            .read_record_batch(&block, &data)
+            .map_err(|e| e.to_string())
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance;
+    use dbms_dtype::Scalar;
+    use std::path::PathBuf;
+
+    fn test_data_path(relative: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test/data")
+            .join(relative)
+    }
+
+    #[test]
+    fn test_schema() {
+        for file in ["ipc/simple.arrow", "ipc/simple.arrows"] {
+            let source = ArrowIpcDataSource::new(test_data_path(file));
+            let schema = source.schema().unwrap();
+
+            assert_eq!(schema.fields().len(), 3, "{}", file);
+            assert_eq!(schema.fields()[0].name(), "id");
+            assert_eq!(schema.fields()[1].name(), "name");
+            assert_eq!(schema.fields()[2].name(), "age");
+        }
+    }
+
+    #[test]
+    fn test_format_detection() {
+        let source = ArrowIpcDataSource::new(test_data_path("ipc/simple.arrow"));
+        assert_eq!(source.format().unwrap(), IpcFormat::File);
+
+        let source = ArrowIpcDataSource::new(test_data_path("ipc/simple.arrows"));
+        assert_eq!(source.format().unwrap(), IpcFormat::Stream);
+    }
+
+    #[test]
+    fn test_scan() {
+        let sources = [
+            ArrowIpcDataSource::new(test_data_path("ipc/simple.arrow")),
+            ArrowIpcDataSource::new(test_data_path("ipc/simple.arrow")).with_memory_map(true),
+            ArrowIpcDataSource::new(test_data_path("ipc/simple.arrows")),
+        ];
+        for source in sources {
+            let batches: Vec<_> = source.scan(None).unwrap().collect();
+
+            assert_eq!(batches.len(), 1);
+            let batch = batches[0].as_ref().unwrap();
+            assert_eq!(batch.row_count(), 3);
+            assert_eq!(batch.column_count(), 3);
+            assert_eq!(batch.field(1).get(1), Scalar::Utf8(Some("Bob".to_string())));
+        }
+    }
+
+    #[test]
+    fn test_projection() {
+        let sources = [
+            ArrowIpcDataSource::new(test_data_path("ipc/simple.arrow")),
+            ArrowIpcDataSource::new(test_data_path("ipc/simple.arrow")).with_memory_map(true),
+            ArrowIpcDataSource::new(test_data_path("ipc/simple.arrows")),
+        ];
+        for source in sources {
+            let batches: Vec<_> = source.scan(Some(&["age", "name"])).unwrap().collect();
+
+            assert_eq!(batches.len(), 1);
+            let batch = batches[0].as_ref().unwrap();
+            assert_eq!(batch.column_count(), 2);
+            assert_eq!(batch.schema().fields()[0].name(), "age");
+            assert_eq!(batch.schema().fields()[1].name(), "name");
+            assert_eq!(batch.field(0).get(0), Scalar::Int64(Some(30)));
+        }
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        let data = std::fs::read(test_data_path("ipc/simple.arrow")).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("dbms-ipc-truncated-{}.arrow", std::process::id()));
+        let scan = |bytes: &[u8]| {
+            std::fs::write(&path, bytes).unwrap();
+            let source = ArrowIpcDataSource::new(&path)
+                .with_format(IpcFormat::File)
+                .with_memory_map(true);
+            source
+                .scan(None)
+                .and_then(|batches| batches.collect::<Result<Vec<_>, _>>())
+                .map(|batches| batches.len())
+        };
+
+        // A trailer declaring a footer longer than the file
+        let mut corrupt = data.clone();
+        let trailer = corrupt.len() - 10;
+        corrupt[trailer..trailer + 4].copy_from_slice(&(data.len() as i32 * 2).to_le_bytes());
+        assert_eq!(
+            scan(&corrupt).unwrap_err(),
+            "IPC file footer is longer than the file"
+        );
+
+        // Cutting bytes out of the middle leaves blocks pointing elsewhere,
+        // or past the end of the file
+        let cut = |len: usize| [&data[..8], &data[8 + len..]].concat();
+        for len in [16, 64, 256] {
+            assert!(scan(&cut(len)).is_err(), "cut {}", len);
+        }
+        assert_eq!(
+            scan(&cut(512)).unwrap_err(),
+            "IPC block lies outside the file"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_conformance() {
+        conformance::check(&ArrowIpcDataSource::new(test_data_path("ipc/simple.arrow")));
+        conformance::check(
+            &ArrowIpcDataSource::new(test_data_path("ipc/simple.arrow")).with_memory_map(true),
+        );
+        conformance::check(&ArrowIpcDataSource::new(test_data_path(
+            "ipc/simple.arrows",
+        )));
+    }
+}
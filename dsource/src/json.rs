@@ -0,0 +1,575 @@
+//! Newline-delimited JSON data source implementation.
+//!
+//! Each non-empty line holds one JSON object. Nested objects are flattened into
+//! dotted column names, so `{"a": {"b": 1}}` yields a column `a.b`, and arrays
+//! are read as their JSON text until the type system supports nested types.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines, Read};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arrow::array::ArrayRef;
+use arrow::datatypes::{DataType as ArrowDataType, Schema as ArrowSchema, SchemaRef};
+use arrow::json::reader::{Decoder, ReaderBuilder, infer_json_schema_from_iterator};
+use arrow::record_batch::RecordBatch as ArrowRecordBatch;
+use dbms_dtype::{DataType, Field, RecordBatch, Schema};
+use serde_json::{Map, Value};
+
+use crate::bad_records::{BadRecordHandler, RecordDecoder, TextRecord};
+use crate::{
+    BadRecordPolicy, BatchIterator, DataSource, FileCompression, ScanRejects, resolve_projection,
+};
+
+/// Number of lines sampled during schema inference by default.
+pub const DEFAULT_JSON_SCHEMA_INFER_MAX_RECORDS: usize = 1000;
+
+/// Options controlling how NDJSON files are read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// Maximum number of records sampled during schema inference, or all if unset.
+    pub schema_infer_max_records: Option<usize>,
+    /// Compression of the file, inferred from its extension if unset.
+    pub compression: Option<FileCompression>,
+    /// What a scan does with records that cannot be parsed.
+    pub bad_records: BadRecordPolicy,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        Self {
+            schema_infer_max_records: Some(DEFAULT_JSON_SCHEMA_INFER_MAX_RECORDS),
+            compression: None,
+            bad_records: BadRecordPolicy::Fail,
+        }
+    }
+}
+
+impl JsonOptions {
+    pub fn with_schema_infer_max_records(mut self, max_records: usize) -> Self {
+        self.schema_infer_max_records = Some(max_records);
+        self
+    }
+
+    pub fn with_compression(mut self, compression: FileCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    pub fn with_bad_records(mut self, policy: BadRecordPolicy) -> Self {
+        self.bad_records = policy;
+        self
+    }
+}
+
+/// A data source that reads from newline-delimited JSON files.
+pub struct JsonDataSource {
+    path: PathBuf,
+    schema: Option<Schema>,
+    batch_size: usize,
+    options: JsonOptions,
+}
+
+impl JsonDataSource {
+    pub fn new(path: impl Into<PathBuf>, schema: Option<Schema>, batch_size: usize) -> Self {
+        Self {
+            path: path.into(),
+            schema,
+            batch_size,
+            options: JsonOptions::default(),
+        }
+    }
+
+    /// Sets the options used to read the file.
+    pub fn with_options(mut self, options: JsonOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Opens the file for reading line by line, decompressing it if necessary.
+    fn open(&self) -> Result<JsonLines, String> {
+        let file = File::open(&self.path).map_err(|e| e.to_string())?;
+        let compression = self
+            .options
+            .compression
+            .unwrap_or_else(|| FileCompression::from_path(&self.path));
+        Ok(JsonLines {
+            lines: BufReader::new(compression.decode(file)?).lines(),
+            line: 0,
+        })
+    }
+}
+
+impl DataSource for JsonDataSource {
+    fn schema(&self) -> Result<Schema, String> {
+        if let Some(schema) = &self.schema {
+            return Ok(schema.clone());
+        }
+
+        // Infer schema from a sample of flattened records, passing over
+        // malformed ones unless they fail scans
+        let max_records = self.options.schema_infer_max_records.unwrap_or(usize::MAX);
+        let tolerant = self.options.bad_records != BadRecordPolicy::Fail;
+        let mut lines = self.open()?;
+        let mut records = Vec::new();
+        while records.len() < max_records {
+            let Some(next) = lines.next_record() else {
+                break;
+            };
+            match next? {
+                (_, _, Ok(record)) => records.push(Value::Object(record)),
+                (_, _, Err(_)) if tolerant => {}
+                (line, _, Err(e)) => return Err(format!("line {}: {}", line, e)),
+            }
+        }
+        let arrow_schema =
+            infer_json_schema_from_iterator(records.iter().map(Ok)).map_err(|e| e.to_string())?;
+
+        let fields = arrow_schema
+            .fields()
+            .iter()
+            .map(|f| {
+                // Columns that were null in every sampled record default to Utf8
+                let dtype = match f.data_type() {
+                    ArrowDataType::Null => DataType::Utf8,
+                    other => DataType::try_from(other.clone())?,
+                };
+                Ok(Field::new(f.name(), dtype))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Schema::new(fields))
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        Ok(self.scan_with_rejects(projection)?.0)
+    }
+
+    fn scan_with_rejects(
+        &self,
+        projection: Option<&[&str]>,
+    ) -> Result<(BatchIterator, ScanRejects), String> {
+        let schema = self.schema()?;
+        let schema = match projection {
+            Some(cols) => schema.project(&resolve_projection(&schema, cols)?),
+            None => schema,
+        };
+        let arrow_schema: SchemaRef = Arc::new(schema.clone().into());
+        if self.options.bad_records != BadRecordPolicy::Fail {
+            let handler = BadRecordHandler::new(&self.options.bad_records)?;
+            let rejects = handler.rejects();
+            let batches = JsonRecordIterator {
+                lines: self.open()?,
+                decoder: JsonRecordDecoder {
+                    schema,
+                    arrow_schema,
+                },
+                batch_size: self.batch_size.max(1),
+                handler,
+                done: false,
+            };
+            return Ok((Box::new(batches), rejects));
+        }
+
+        let decoder = decoder(arrow_schema, self.batch_size)?;
+        let batches = JsonBatchIterator {
+            records: self.open()?,
+            decoder,
+            batch_size: self.batch_size,
+        };
+        Ok((Box::new(batches), ScanRejects::default()))
+    }
+}
+
+/// Yields the flattened JSON object on each non-empty line.
+struct JsonLines {
+    lines: Lines<BufReader<Box<dyn Read + Send>>>,
+    line: usize,
+}
+
+/// A non-empty line: its number, its text, and its flattened object or why
+/// it does not hold one.
+type JsonLine = (usize, String, Result<Map<String, Value>, String>);
+
+impl JsonLines {
+    fn next_record(&mut self) -> Option<Result<JsonLine, String>> {
+        loop {
+            let text = match self.lines.next()? {
+                Ok(text) => text,
+                Err(e) => return Some(Err(e.to_string())),
+            };
+            self.line += 1;
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            let record = match serde_json::from_str(&text) {
+                Ok(Value::Object(object)) => Ok(flatten(object)),
+                Ok(other) => Err(format!("expected a JSON object, found {}", other)),
+                Err(e) => Err(e.to_string()),
+            };
+            return Some(Ok((self.line, text, record)));
+        }
+    }
+}
+
+impl Iterator for JsonLines {
+    type Item = Result<Map<String, Value>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.next_record()? {
+            Ok((line, _, record)) => record.map_err(|e| format!("line {}: {}", line, e)),
+            Err(e) => Err(e),
+        })
+    }
+}
+
+/// Flattens nested objects into dotted keys and arrays into their JSON text.
+fn flatten(object: Map<String, Value>) -> Map<String, Value> {
+    fn flatten_into(
+        out: &mut Map<String, Value>,
+        prefix: Option<&str>,
+        object: Map<String, Value>,
+    ) {
+        for (key, value) in object {
+            let name = match prefix {
+                Some(prefix) => format!("{}.{}", prefix, key),
+                None => key,
+            };
+            match value {
+                Value::Object(inner) => flatten_into(out, Some(&name), inner),
+                Value::Array(_) => {
+                    out.insert(name, Value::String(value.to_string()));
+                }
+                scalar => {
+                    out.insert(name, scalar);
+                }
+            }
+        }
+    }
+
+    let mut out = Map::new();
+    flatten_into(&mut out, None, object);
+    out
+}
+
+struct JsonBatchIterator {
+    records: JsonLines,
+    decoder: Decoder,
+    batch_size: usize,
+}
+
+impl Iterator for JsonBatchIterator {
+    type Item = Result<RecordBatch, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rows = match self
+            .records
+            .by_ref()
+            .take(self.batch_size)
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(rows) if rows.is_empty() => return None,
+            Ok(rows) => rows,
+            Err(e) => return Some(Err(e)),
+        };
+
+        if let Err(e) = self.decoder.serialize(&rows) {
+            return Some(Err(e.to_string()));
+        }
+        match self.decoder.flush() {
+            Ok(Some(batch)) => Some(batch.try_into()),
+            Ok(None) => None,
+            Err(e) => Some(Err(e.to_string())),
+        }
+    }
+}
+
+/// Reads records one at a time, so malformed records can be handled by a
+/// bad-record policy instead of failing the batch they are in.
+struct JsonRecordIterator {
+    lines: JsonLines,
+    decoder: JsonRecordDecoder,
+    batch_size: usize,
+    handler: BadRecordHandler,
+    done: bool,
+}
+
+impl JsonRecordIterator {
+    /// Reads up to a batch of records, noting lines without an object.
+    fn read_records(&mut self) -> Result<Vec<TextRecord<JsonLine>>, String> {
+        let mut records = Vec::with_capacity(self.batch_size);
+        while records.len() < self.batch_size {
+            let Some(next) = self.lines.next_record() else {
+                self.done = true;
+                break;
+            };
+            let record = next?;
+            let reason = record.2.as_ref().err().cloned();
+            records.push(TextRecord {
+                record,
+                unreadable: reason.is_some(),
+                reason,
+            });
+        }
+        Ok(records)
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, String> {
+        while !self.done {
+            let records = self.read_records()?;
+            let schema = &self.decoder.schema;
+            if let Some(batch) = self.handler.build_batch(&self.decoder, schema, records)? {
+                return Ok(Some(batch));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Decodes records with the same Arrow JSON decoder as other scans.
+struct JsonRecordDecoder {
+    /// Projected schema.
+    schema: Schema,
+    arrow_schema: SchemaRef,
+}
+
+impl RecordDecoder for JsonRecordDecoder {
+    type Record = JsonLine;
+
+    fn line(&self, record: &JsonLine) -> usize {
+        record.0
+    }
+
+    fn text(&self, record: &JsonLine) -> Result<String, String> {
+        Ok(record.1.clone())
+    }
+
+    fn decode(&self, records: &[&JsonLine]) -> Result<ArrowRecordBatch, String> {
+        let objects: Vec<_> = records
+            .iter()
+            .filter_map(|record| record.2.as_ref().ok())
+            .collect();
+        decode(self.arrow_schema.clone(), &objects)
+    }
+
+    fn decode_field(&self, record: &JsonLine, column: usize) -> Result<Option<ArrayRef>, String> {
+        let field = &self.schema.fields()[column];
+        let value = match record.2.as_ref().ok().and_then(|o| o.get(field.name())) {
+            None | Some(Value::Null) => return Ok(None),
+            Some(value) => value,
+        };
+
+        let object = Map::from_iter([(field.name().to_string(), value.clone())]);
+        let schema = Arc::new(ArrowSchema::new(vec![
+            self.arrow_schema.field(column).clone(),
+        ]));
+        match decode(schema, &[&object]) {
+            Ok(batch) => Ok(Some(batch.column(0).clone())),
+            Err(_) => {
+                let text = match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                Err(format!(
+                    "column {}: cannot parse '{}' as {:?}",
+                    field.name(),
+                    text,
+                    field.dtype()
+                ))
+            }
+        }
+    }
+}
+
+/// Builds the decoder of scans. Fields not in the (projected) schema are
+/// ignored.
+fn decoder(schema: SchemaRef, batch_size: usize) -> Result<Decoder, String> {
+    ReaderBuilder::new(schema)
+        .with_batch_size(batch_size)
+        .with_coerce_primitive(true)
+        .build_decoder()
+        .map_err(|e| e.to_string())
+}
+
+/// Decodes objects into a single batch.
+fn decode(schema: SchemaRef, objects: &[&Map<String, Value>]) -> Result<ArrowRecordBatch, String> {
+    let mut decoder = decoder(schema, objects.len().max(1))?;
+    decoder.serialize(objects).map_err(|e| e.to_string())?;
+    decoder
+        .flush()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "no records to decode".to_string())
+}
+
+impl Iterator for JsonRecordIterator {
+    type Item = Result<RecordBatch, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.next_batch();
+        if batch.is_err() {
+            self.done = true;
+        }
+        batch.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conformance;
+    use dbms_dtype::Scalar;
+    use std::path::PathBuf;
+
+    fn test_data_path(relative: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test/data")
+            .join(relative)
+    }
+
+    #[test]
+    fn test_schema_inference() {
+        let path = test_data_path("json/simple.ndjson");
+        let source = JsonDataSource::new(path, None, 1024);
+        let schema = source.schema().unwrap();
+
+        let fields: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name(), f.dtype().clone()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("id", DataType::Int64),
+                ("name", DataType::Utf8),
+                ("age", DataType::Float64),
+                ("address.city", DataType::Utf8),
+                ("address.zip", DataType::Utf8),
+                ("tags", DataType::Utf8),
+                ("extra", DataType::Utf8),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan() {
+        let path = test_data_path("json/simple.ndjson");
+        let source = JsonDataSource::new(path, None, 2);
+        let batches: Vec<_> = source.scan(None).unwrap().collect();
+
+        assert_eq!(batches.len(), 2);
+        let batch = batches[0].as_ref().unwrap();
+        assert_eq!(batch.row_count(), 2);
+        assert_eq!(batch.column_count(), 7);
+        assert_eq!(
+            batch.field(3).get(1),
+            Scalar::Utf8(Some("Berlin".to_string()))
+        );
+        assert_eq!(
+            batch.field(5).get(0),
+            Scalar::Utf8(Some(r#"["a","b"]"#.to_string()))
+        );
+        assert_eq!(batches[1].as_ref().unwrap().row_count(), 1);
+    }
+
+    #[test]
+    fn test_projection() {
+        let path = test_data_path("json/simple.ndjson");
+        let source = JsonDataSource::new(path, None, 1024);
+        let batches: Vec<_> = source
+            .scan(Some(&["address.city", "id"]))
+            .unwrap()
+            .collect();
+
+        assert_eq!(batches.len(), 1);
+        let batch = batches[0].as_ref().unwrap();
+        assert_eq!(batch.row_count(), 3);
+        assert_eq!(batch.column_count(), 2);
+        assert_eq!(batch.schema().fields()[0].name(), "address.city");
+        assert_eq!(batch.schema().fields()[1].name(), "id");
+        assert_eq!(batch.field(0).get(2), Scalar::Utf8(None));
+    }
+
+    #[test]
+    fn test_explicit_schema() {
+        // Numbers are coerced when the schema declares a string column
+        let path = test_data_path("json/simple.ndjson.gz");
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Utf8),
+            Field::new("age", DataType::Float64),
+        ]);
+        let source = JsonDataSource::new(path, Some(schema), 1024);
+        let batches: Vec<_> = source.scan(None).unwrap().collect();
+
+        let batch = batches[0].as_ref().unwrap();
+        assert_eq!(batch.row_count(), 3);
+        assert_eq!(batch.field(0).get(0), Scalar::Utf8(Some("1".to_string())));
+        assert_eq!(batch.field(1).get(2), Scalar::Float64(Some(35.5)));
+    }
+
+    #[test]
+    fn test_invalid_line() {
+        let path =
+            std::env::temp_dir().join(format!("dbms-json-invalid-{}.ndjson", std::process::id()));
+        std::fs::write(&path, "{\"a\":1}\n[1,2]\n").unwrap();
+        let source = JsonDataSource::new(path.clone(), None, 1024);
+
+        let err = source.schema().unwrap_err();
+        assert!(err.starts_with("line 2:"), "{}", err);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_bad_records() {
+        let path =
+            std::env::temp_dir().join(format!("dbms-json-bad-{}.ndjson", std::process::id()));
+        let data = "{\"a\":1,\"b\":\"x\"}\n{\"a\":\"two\"}\nnot json\n\n{\"a\":4,\"b\":5}\n";
+        std::fs::write(&path, data).unwrap();
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64),
+            Field::new("b", DataType::Utf8),
+        ]);
+        let source = |policy| {
+            let options = JsonOptions::default().with_bad_records(policy);
+            JsonDataSource::new(path.clone(), Some(schema.clone()), 1024).with_options(options)
+        };
+
+        let err = source(BadRecordPolicy::Fail)
+            .scan(None)
+            .unwrap()
+            .find_map(Result::err)
+            .unwrap();
+        assert!(err.starts_with("line 3:"), "{}", err);
+
+        let (batches, rejects) = source(BadRecordPolicy::Skip)
+            .scan_with_rejects(None)
+            .unwrap();
+        let batch = batches.map(Result::unwrap).next().unwrap();
+        assert_eq!(batch.row_count(), 2);
+        assert_eq!(batch.field(1).get(1), Scalar::Utf8(Some("5".to_string())));
+        let lines: Vec<_> = rejects.records().iter().map(|r| r.line).collect();
+        assert_eq!(lines, [2, 3]);
+        assert_eq!(
+            rejects.records()[0].reason,
+            "column a: cannot parse 'two' as Int64"
+        );
+
+        // Lines without an object are left out even when null-filling
+        let (batches, rejects) = source(BadRecordPolicy::NullFill)
+            .scan_with_rejects(None)
+            .unwrap();
+        let batch = batches.map(Result::unwrap).next().unwrap();
+        assert_eq!(batch.row_count(), 3);
+        assert_eq!(batch.field(0).get(1), Scalar::Int64(None));
+        assert_eq!(rejects.count(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_conformance() {
+        let path = test_data_path("json/simple.ndjson");
+        conformance::check(&JsonDataSource::new(path, None, 2));
+    }
+}
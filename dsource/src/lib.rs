@@ -1,14 +1,72 @@
 //! Data sources for the DBMS query engine.
 
+mod avro;
+mod bad_records;
+mod catalog;
+mod compression;
+#[cfg(test)]
+mod conformance;
 mod csv;
+mod delta;
+mod fixed_width;
+mod generator;
+mod iceberg;
+mod ipc;
+mod json;
 mod memory;
+mod object_store;
 mod parquet;
+mod pruning;
+mod sample;
+mod sink;
+mod statistics;
+mod stream;
 
-pub use csv::CsvDataSource;
+pub use avro::AvroDataSource;
+pub use bad_records::{BadRecordPolicy, MAX_KEPT_REJECTS, RejectedRecord, ScanRejects};
+pub use catalog::{
+    Catalog, DEFAULT_CATALOG, DEFAULT_SCHEMA, ExternalTable, FunctionDescription, FunctionKind,
+    INFORMATION_SCHEMA, SYSTEM_SCHEMA, SchemaReference, TableDescription, TableFormat,
+    TableReference, TableSource,
+};
+pub use compression::FileCompression;
+pub use csv::{
+    CsvDataSource, CsvOptions, DEFAULT_CSV_PARTITION_SIZE, InferenceDiagnostic, SchemaInference,
+};
+pub use delta::DeltaDataSource;
+pub use fixed_width::{FixedWidthColumn, FixedWidthDataSource, FixedWidthOptions, Trim};
+pub use generator::{
+    ColumnOptions, Distribution, RandomDataSource, SequenceDataSource, TpchDataSource, TpchTable,
+    write_tpch,
+};
+pub use iceberg::IcebergDataSource;
+pub use ipc::{ArrowIpcDataSource, IpcFormat};
+pub use json::{DEFAULT_JSON_SCHEMA_INFER_MAX_RECORDS, JsonDataSource, JsonOptions};
 pub use memory::InMemoryDataSource;
-pub use parquet::ParquetDataSource;
+pub use object_store::{
+    InMemoryObjectStore, LocalFileSystem, ObjectMeta, ObjectReader, ObjectStore,
+};
+pub use parquet::{ColumnMatching, ParquetDataSource};
+pub use pruning::{ColumnFilter, FilterOp};
+pub use sample::{Sample, SampleMethod};
+pub use sink::{
+    ArrowIpcSink, BatchWriter, CsvSink, CsvWriteOptions, DEFAULT_MAX_OPEN_FILES,
+    DEFAULT_PARTITION_NAME, DataSink, JsonSink, ParquetCompression, ParquetSink,
+    ParquetWriteOptions, PartitionedSink, SinkFormat,
+};
+pub use statistics::{ColumnStatistics, Precision, Statistics};
+pub use stream::BatchStream;
 
-use dbms_dtype::{RecordBatch, Schema};
+use arrow::array::BooleanArray;
+use dbms_dtype::{Column, RecordBatch, Schema};
+
+/// Number of rows per batch read by sources that are not given a batch size,
+/// such as generators and external tables without a `batch_size` option.
+pub const DEFAULT_BATCH_SIZE: usize = 8192;
+
+/// An iterator over the record batches produced by a scan, which may be
+/// moved to another thread.
+pub type BatchIterator = Box<dyn Iterator<Item = Result<RecordBatch, String>> + Send>;
 
 /// A data source that can be scanned to produce record batches.
 pub trait DataSource {
@@ -16,5 +74,117 @@ pub trait DataSource {
     fn schema(&self) -> Result<Schema, String>;
 
     /// Scans the data source, optionally projecting to a subset of columns.
-    fn scan(&self, projection: Option<&[&str]>) -> Result<Box<dyn Iterator<Item = Result<RecordBatch, String>>>, String>;
+    ///
+    /// Projected columns are returned in the requested order. Unknown and
+    /// duplicate column names are rejected.
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String>;
+
+    /// Scans the data source like `scan`, also returning the records rejected
+    /// under the source's bad-record policy as the scan goes on.
+    ///
+    /// By default no records are rejected; sources of text files with a
+    /// policy other than failing override this.
+    fn scan_with_rejects(
+        &self,
+        projection: Option<&[&str]>,
+    ) -> Result<(BatchIterator, ScanRejects), String> {
+        Ok((self.scan(projection)?, ScanRejects::default()))
+    }
+
+    /// Scans the data source asynchronously, with the same projection rules
+    /// as `scan`. Batches are only read as fast as the stream is consumed.
+    ///
+    /// By default the synchronous scan runs on Tokio's blocking thread pool.
+    /// Sources that read through an object store also need the runtime, so
+    /// they fail if called outside one; only sources whose stream never
+    /// blocks may start it without a runtime.
+    fn scan_stream(&self, projection: Option<&[&str]>) -> Result<BatchStream, String> {
+        stream::blocking_stream(self.scan(projection)?)
+    }
+
+    /// Scans a random sample of the rows, with the same projection rules as
+    /// `scan`.
+    ///
+    /// By default the rows of a full scan are sampled, and block sampling
+    /// keeps or skips whole batches. Sources that can skip reading blocks
+    /// outside the sample override this.
+    fn scan_sample(
+        &self,
+        sample: &Sample,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        Ok(sample::sample_batches(self.scan(projection)?, *sample))
+    }
+
+    /// Returns the number of partitions that can be scanned independently.
+    fn partition_count(&self) -> Result<usize, String> {
+        Ok(1)
+    }
+
+    /// Scans a single partition, optionally projecting to a subset of columns.
+    ///
+    /// Scanning every partition from `0` to `partition_count()` yields the same
+    /// rows as `scan`, though not necessarily in the same order across partitions.
+    fn scan_partition(
+        &self,
+        partition: usize,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        check_partition(partition, 1)?;
+        self.scan(projection)
+    }
+
+    /// Returns statistics about the data, for use in cost estimation.
+    ///
+    /// Sources that cannot compute statistics cheaply report them as unknown.
+    fn statistics(&self) -> Result<Statistics, String> {
+        Ok(Statistics::unknown(&self.schema()?))
+    }
+}
+
+/// Returns an error if `partition` is not below `count`.
+pub(crate) fn check_partition(partition: usize, count: usize) -> Result<(), String> {
+    if partition >= count {
+        return Err(format!(
+            "partition {} out of range for {} partitions",
+            partition, count
+        ));
+    }
+    Ok(())
+}
+
+/// Resolves projected column names to their indices in `schema`, in the
+/// requested order, rejecting unknown and duplicate names.
+pub(crate) fn resolve_projection(
+    schema: &Schema,
+    projection: &[&str],
+) -> Result<Vec<usize>, String> {
+    let mut indices = Vec::with_capacity(projection.len());
+    for name in projection {
+        let index = schema
+            .fields()
+            .iter()
+            .position(|f| f.name() == *name)
+            .ok_or_else(|| format!("column not found: {}", name))?;
+        if indices.contains(&index) {
+            return Err(format!("duplicate column in projection: {}", name));
+        }
+        indices.push(index);
+    }
+    Ok(indices)
+}
+
+/// Returns the rows of a batch for which `mask` is true.
+pub(crate) fn filter_batch(
+    batch: &RecordBatch,
+    mask: &BooleanArray,
+) -> Result<RecordBatch, String> {
+    let columns = (0..batch.column_count())
+        .map(|i| {
+            let array = arrow::compute::filter(&batch.field(i).to_array(), mask)
+                .map_err(|e| e.to_string())?;
+            Column::try_from(array)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(RecordBatch::new(batch.schema().clone(), columns))
 }
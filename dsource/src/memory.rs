@@ -1,19 +1,174 @@
 //! In-memory data source implementation.
 
-use dbms_dtype::{RecordBatch, Schema};
+use std::sync::{Arc, PoisonError, RwLock, RwLockWriteGuard};
 
-use crate::DataSource;
+use arrow::array::{Array, ArrayRef, BooleanArray};
+use dbms_dtype::{RecordBatch, Scalar, Schema};
 
-/// A data source that stores data in memory.
-#[derive(Debug, Clone)]
+use crate::sample::sample_batches;
+use crate::statistics::{distinct_count, merge_max, merge_min, min_max};
+use crate::{
+    BatchIterator, BatchStream, ColumnStatistics, DataSource, Precision, Sample, SampleMethod,
+    Statistics, check_partition, filter_batch, resolve_projection,
+};
+
+/// A table held in memory, which can be appended to, overwritten and
+/// deleted from while it is being scanned.
+///
+/// The batches are shared through reference counting: a scan takes a
+/// snapshot of the table and sees exactly its rows, however the table
+/// changes afterwards, and no batch is copied by scanning it. Writers copy
+/// the list of batches only while a snapshot of it is still in use.
+#[derive(Debug)]
 pub struct InMemoryDataSource {
     schema: Schema,
-    batches: Vec<RecordBatch>,
+    batches: RwLock<Arc<Vec<RecordBatch>>>,
+}
+
+impl Clone for InMemoryDataSource {
+    /// Creates an independent table holding the current rows.
+    fn clone(&self) -> Self {
+        let batches = self.current();
+        Self {
+            schema: self.schema.clone(),
+            batches: RwLock::new(batches),
+        }
+    }
 }
 
 impl InMemoryDataSource {
     pub fn new(schema: Schema, batches: Vec<RecordBatch>) -> Self {
-        Self { schema, batches }
+        Self {
+            schema,
+            batches: RwLock::new(Arc::new(batches)),
+        }
+    }
+
+    /// Returns the batches the table currently holds.
+    pub fn snapshot(&self) -> Result<Arc<Vec<RecordBatch>>, String> {
+        Ok(self.current())
+    }
+
+    /// Appends batches, returning the number of rows appended.
+    pub fn append(&self, batches: Vec<RecordBatch>) -> Result<usize, String> {
+        self.check_schema(&batches)?;
+        let rows = batches.iter().map(|b| b.row_count()).sum();
+        self.update(|current| current.extend(batches))?;
+        Ok(rows)
+    }
+
+    /// Replaces every row with the given batches.
+    pub fn overwrite(&self, batches: Vec<RecordBatch>) -> Result<(), String> {
+        self.check_schema(&batches)?;
+        *self.write() = Arc::new(batches);
+        Ok(())
+    }
+
+    /// Removes every row.
+    pub fn truncate(&self) -> Result<(), String> {
+        self.overwrite(Vec::new())
+    }
+
+    /// Deletes the rows for which `predicate` returns true, returning the
+    /// number of rows deleted. Rows for which it returns null are kept.
+    ///
+    /// The predicate sees each batch with every column of the table. It runs
+    /// on a snapshot without holding the table's lock, so it blocks neither
+    /// scans nor writers and may itself read the table. If the table changed
+    /// while it ran, it is run again on the new snapshot.
+    pub fn delete(
+        &self,
+        predicate: impl Fn(&RecordBatch) -> Result<BooleanArray, String>,
+    ) -> Result<usize, String> {
+        loop {
+            let snapshot = self.snapshot()?;
+            let mut kept = Vec::with_capacity(snapshot.len());
+            let mut deleted = 0;
+            for batch in snapshot.iter() {
+                let mask = predicate(batch)?;
+                if mask.len() != batch.row_count() {
+                    return Err(format!(
+                        "delete predicate returned {} values for {} rows",
+                        mask.len(),
+                        batch.row_count()
+                    ));
+                }
+                let keep: BooleanArray = mask.iter().map(|v| Some(v != Some(true))).collect();
+                deleted += batch.row_count() - keep.true_count();
+                match keep.true_count() {
+                    0 => {}
+                    n if n == batch.row_count() => kept.push(batch.clone()),
+                    _ => kept.push(filter_batch(batch, &keep)?),
+                }
+            }
+            if deleted == 0 {
+                return Ok(0);
+            }
+
+            // Writers replace the list rather than changing it in place while
+            // the snapshot is held, so an unchanged pointer means no writes
+            let mut batches = self.write();
+            if Arc::ptr_eq(&batches, &snapshot) {
+                *batches = Arc::new(kept);
+                return Ok(deleted);
+            }
+        }
+    }
+
+    /// Returns the batches, even if a writer panicked while holding the lock:
+    /// writers replace the list whole or append to it, so it is never left
+    /// half changed.
+    fn current(&self) -> Arc<Vec<RecordBatch>> {
+        self.batches
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Locks the batches for writing, even if a writer panicked while holding
+    /// the lock, as `current` does.
+    fn write(&self) -> RwLockWriteGuard<'_, Arc<Vec<RecordBatch>>> {
+        self.batches.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn check_schema(&self, batches: &[RecordBatch]) -> Result<(), String> {
+        match batches.iter().find(|b| b.schema() != &self.schema) {
+            Some(_) => Err("batch schema does not match table schema".to_string()),
+            None => Ok(()),
+        }
+    }
+
+    /// Changes the batches, copying the list only if a scan still holds it.
+    fn update(&self, f: impl FnOnce(&mut Vec<RecordBatch>)) -> Result<(), String> {
+        let mut batches = self.write();
+        f(Arc::make_mut(&mut batches));
+        Ok(())
+    }
+
+    /// Reads the batches of a snapshot at the given positions, projected to
+    /// a subset of columns.
+    fn read(
+        &self,
+        batches: Arc<Vec<RecordBatch>>,
+        positions: impl Iterator<Item = usize> + Send + 'static,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let indices = match projection {
+            Some(cols) => Some(resolve_projection(&self.schema, cols)?),
+            None => None,
+        };
+        let projected_schema = indices.as_ref().map(|indices| self.schema.project(indices));
+
+        Ok(Box::new(positions.map(move |i| {
+            let batch = &batches[i];
+            Ok(match (&indices, &projected_schema) {
+                (Some(indices), Some(schema)) => {
+                    let columns = indices.iter().map(|&i| batch.field(i).clone()).collect();
+                    RecordBatch::new(schema.clone(), columns)
+                }
+                _ => batch.clone(),
+            })
+        })))
     }
 }
 
@@ -22,45 +177,95 @@ impl DataSource for InMemoryDataSource {
         Ok(self.schema.clone())
     }
 
-    fn scan(
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        let batches = self.snapshot()?;
+        let count = batches.len();
+        self.read(batches, 0..count, projection)
+    }
+
+    /// Batches are already in memory, so the stream never blocks.
+    fn scan_stream(&self, projection: Option<&[&str]>) -> Result<BatchStream, String> {
+        Ok(Box::pin(futures::stream::iter(self.scan(projection)?)))
+    }
+
+    /// Block samples are drawn from batches, so only sampled batches are
+    /// projected.
+    fn scan_sample(
+        &self,
+        sample: &Sample,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        if sample.method() != SampleMethod::Block {
+            return Ok(sample_batches(self.scan(projection)?, *sample));
+        }
+        let batches = self.snapshot()?;
+        let sample = *sample;
+        let positions = (0..batches.len()).filter(move |&i| sample.keeps(i as u64));
+        self.read(batches, positions, projection)
+    }
+
+    /// Each batch is a partition. Partitions are numbered by the batches the
+    /// table holds when each is scanned.
+    fn partition_count(&self) -> Result<usize, String> {
+        Ok(self.snapshot()?.len().max(1))
+    }
+
+    fn scan_partition(
         &self,
+        partition: usize,
         projection: Option<&[&str]>,
-    ) -> Result<Box<dyn Iterator<Item = Result<RecordBatch, String>>>, String> {
-        let batches = match projection {
-            None => self.batches.clone(),
-            Some(cols) => {
-                let indices: Vec<usize> = cols
-                    .iter()
-                    .map(|name| {
-                        self.schema
-                            .fields()
-                            .iter()
-                            .position(|f| f.name() == *name)
-                            .ok_or_else(|| format!("column not found: {}", name))
-                    })
-                    .collect::<Result<Vec<_>, _>>()?;
-
-                let projected_schema = self.schema.project(&indices);
-
-                self.batches
-                    .iter()
-                    .map(|batch| {
-                        let projected_columns: Vec<_> =
-                            indices.iter().map(|&i| batch.field(i).clone()).collect();
-                        RecordBatch::new(projected_schema.clone(), projected_columns)
-                    })
-                    .collect()
+    ) -> Result<BatchIterator, String> {
+        let batches = self.snapshot()?;
+        check_partition(partition, batches.len().max(1))?;
+        // Without any batches the single partition is empty
+        let count = batches.len();
+        let positions = (partition..partition + 1).filter(move |&i| i < count);
+        self.read(batches, positions, projection)
+    }
+
+    fn statistics(&self) -> Result<Statistics, String> {
+        let batches = self.snapshot()?;
+        let mut total_byte_size = 0;
+        let mut column_statistics = Vec::with_capacity(self.schema.fields().len());
+        for (i, field) in self.schema.fields().iter().enumerate() {
+            let arrays: Vec<ArrayRef> = batches.iter().map(|b| b.field(i).to_array()).collect();
+
+            let mut lo = Scalar::null(field.dtype().clone());
+            let mut hi = lo.clone();
+            for array in &arrays {
+                let (min, max) = min_max(array)?;
+                lo = merge_min(lo, min);
+                hi = merge_max(hi, max);
             }
-        };
+            total_byte_size += arrays
+                .iter()
+                .map(|a| a.get_array_memory_size())
+                .sum::<usize>();
+
+            column_statistics.push(ColumnStatistics {
+                null_count: Precision::Exact(arrays.iter().map(|a| a.null_count()).sum()),
+                min_value: Precision::Exact(lo),
+                max_value: Precision::Exact(hi),
+                distinct_count: Precision::Exact(distinct_count(&arrays)?),
+            });
+        }
 
-        Ok(Box::new(batches.into_iter().map(Ok)))
+        Ok(Statistics {
+            num_rows: Precision::Exact(batches.iter().map(|b| b.row_count()).sum()),
+            total_byte_size: Precision::Exact(total_byte_size),
+            column_statistics,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::conformance;
+    use arrow::array::Int64Array;
     use dbms_dtype::{Column, DataType, Field, Scalar};
+    use std::panic;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     fn test_schema() -> Schema {
         Schema::new(vec![
@@ -115,4 +320,218 @@ mod tests {
         assert_eq!(batch.schema().fields()[0].name(), "name");
         assert_eq!(batch.schema().fields()[1].name(), "age");
     }
+
+    #[test]
+    fn test_statistics() {
+        let source = InMemoryDataSource::new(test_schema(), test_batches());
+        let stats = source.statistics().unwrap();
+
+        assert_eq!(stats.num_rows, Precision::Exact(3));
+        assert!(stats.total_byte_size.is_exact());
+        let age = &stats.column_statistics[2];
+        assert_eq!(age.null_count, Precision::Exact(0));
+        assert_eq!(age.min_value, Precision::Exact(Scalar::Int64(Some(30))));
+        assert_eq!(age.max_value, Precision::Exact(Scalar::Int64(Some(30))));
+        assert_eq!(age.distinct_count, Precision::Exact(1));
+    }
+
+    #[test]
+    fn test_partitions() {
+        let mut batches = test_batches();
+        batches.extend(test_batches());
+        let source = InMemoryDataSource::new(test_schema(), batches);
+        assert_eq!(source.partition_count().unwrap(), 2);
+
+        let batches: Vec<_> = source.scan_partition(1, Some(&["name"])).unwrap().collect();
+        assert_eq!(batches.len(), 1);
+        let batch = batches[0].as_ref().unwrap();
+        assert_eq!(batch.row_count(), 3);
+        assert_eq!(batch.column_count(), 1);
+
+        assert!(source.scan_partition(2, None).is_err());
+    }
+
+    fn ids(values: Vec<i64>) -> RecordBatch {
+        let schema = Schema::new(vec![Field::new("id", DataType::Int64)]);
+        let array: ArrayRef = Arc::new(Int64Array::from(values));
+        RecordBatch::new(schema, vec![Column::try_from(array).unwrap()])
+    }
+
+    fn scanned_ids(batches: BatchIterator) -> Vec<Scalar> {
+        batches
+            .flat_map(|batch| {
+                let batch = batch.unwrap();
+                (0..batch.row_count())
+                    .map(|i| batch.field(0).get(i))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn int64s(values: &[i64]) -> Vec<Scalar> {
+        values.iter().map(|&v| Scalar::Int64(Some(v))).collect()
+    }
+
+    #[test]
+    fn test_writes() {
+        let table = InMemoryDataSource::new(ids(vec![]).schema().clone(), vec![ids(vec![1, 2])]);
+        assert_eq!(
+            table.append(vec![ids(vec![3]), ids(vec![4, 5])]).unwrap(),
+            3
+        );
+        assert_eq!(
+            scanned_ids(table.scan(None).unwrap()),
+            int64s(&[1, 2, 3, 4, 5])
+        );
+        assert_eq!(table.partition_count().unwrap(), 3);
+
+        let deleted = table
+            .delete(|batch| {
+                let ids = batch.field(0).to_array();
+                let ids = ids.as_any().downcast_ref::<Int64Array>().unwrap();
+                Ok(ids.iter().map(|id| id.map(|id| id % 2 == 0)).collect())
+            })
+            .unwrap();
+        assert_eq!(deleted, 2);
+        assert_eq!(scanned_ids(table.scan(None).unwrap()), int64s(&[1, 3, 5]));
+
+        table.overwrite(vec![ids(vec![7])]).unwrap();
+        assert_eq!(scanned_ids(table.scan(None).unwrap()), int64s(&[7]));
+        table.truncate().unwrap();
+        assert!(scanned_ids(table.scan(None).unwrap()).is_empty());
+        assert_eq!(table.statistics().unwrap().num_rows, Precision::Exact(0));
+
+        let err = table.append(test_batches()).unwrap_err();
+        assert_eq!(err, "batch schema does not match table schema");
+    }
+
+    #[test]
+    fn test_snapshot_isolation() {
+        let batch = ids(vec![1, 2]);
+        let table = InMemoryDataSource::new(batch.schema().clone(), vec![batch.clone()]);
+
+        // A scan keeps seeing the rows of the table when it started
+        let scan = table.scan(None).unwrap();
+        table.append(vec![ids(vec![3])]).unwrap();
+        table
+            .delete(|b| Ok(BooleanArray::from(vec![true; b.row_count()])))
+            .unwrap();
+        let scanned: Vec<_> = scan.map(Result::unwrap).collect();
+        assert_eq!(
+            scanned_ids(Box::new(scanned.clone().into_iter().map(Ok))),
+            int64s(&[1, 2])
+        );
+        assert!(scanned_ids(table.scan(None).unwrap()).is_empty());
+
+        // Scanned batches share their arrays with the table
+        assert!(Arc::ptr_eq(
+            &scanned[0].field(0).to_array(),
+            &batch.field(0).to_array()
+        ));
+    }
+
+    #[test]
+    fn test_concurrent_appends() {
+        let table = InMemoryDataSource::new(ids(vec![]).schema().clone(), Vec::new());
+        std::thread::scope(|scope| {
+            for t in 0..4 {
+                let table = &table;
+                scope.spawn(move || {
+                    for i in 0..100 {
+                        table.append(vec![ids(vec![t * 100 + i])]).unwrap();
+                        table.scan(None).unwrap().for_each(|b| drop(b.unwrap()));
+                    }
+                });
+            }
+        });
+        let mut scanned = scanned_ids(table.scan(None).unwrap());
+        scanned.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(scanned, int64s(&(0..400).collect::<Vec<_>>()));
+    }
+
+    #[test]
+    fn test_delete_without_lock() {
+        let table = InMemoryDataSource::new(ids(vec![]).schema().clone(), vec![ids(vec![1, 2])]);
+        let is_even = |batch: &RecordBatch| {
+            let ids = batch.field(0).to_array();
+            let ids = ids.as_any().downcast_ref::<Int64Array>().unwrap();
+            ids.iter().map(|id| id.map(|id| id % 2 == 0)).collect()
+        };
+
+        // The predicate can write to the table; the rows it appends are
+        // seen when the predicate is run again
+        let calls = AtomicUsize::new(0);
+        let deleted = table
+            .delete(|batch| {
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    table.append(vec![ids(vec![4])])?;
+                }
+                Ok(is_even(batch))
+            })
+            .unwrap();
+        assert_eq!(deleted, 2);
+        assert_eq!(scanned_ids(table.scan(None).unwrap()), int64s(&[1]));
+
+        // A panicking predicate leaves the table usable
+        let panicked = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            table.delete(|_| panic!("predicate failed"))
+        }));
+        assert!(panicked.is_err());
+        table.append(vec![ids(vec![3])]).unwrap();
+        assert_eq!(table.clone().partition_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_poisoned_lock() {
+        let table = InMemoryDataSource::new(ids(vec![]).schema().clone(), vec![ids(vec![1, 2])]);
+        let panicked = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            table.update(|_| panic!("writer failed"))
+        }));
+        assert!(panicked.is_err());
+        assert!(table.batches.is_poisoned());
+
+        // Readers and writers go on with the rows the table held
+        assert_eq!(table.snapshot().unwrap().len(), 1);
+        assert_eq!(
+            scanned_ids(table.clone().scan(None).unwrap()),
+            int64s(&[1, 2])
+        );
+        table.append(vec![ids(vec![3])]).unwrap();
+        assert_eq!(scanned_ids(table.scan(None).unwrap()), int64s(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_sample() {
+        let ids = |i: i64| {
+            let schema = Schema::new(vec![Field::new("id", DataType::Int64)]);
+            let column = Column::from_literal(Scalar::Int64(Some(i)), 100);
+            RecordBatch::new(schema, vec![column])
+        };
+        let source = InMemoryDataSource::new(ids(0).schema().clone(), (0..100).map(ids).collect());
+        let rows = |sample: &Sample| -> usize {
+            source
+                .scan_sample(sample, None)
+                .unwrap()
+                .map(|b| b.unwrap().row_count())
+                .sum()
+        };
+
+        // Batches are kept whole
+        let block = Sample::block(0.2).unwrap();
+        let kept = (0..100).filter(|&i| block.keeps(i)).count();
+        assert_eq!(rows(&block), kept * 100);
+
+        let bernoulli = Sample::bernoulli(0.01).unwrap().with_seed(1);
+        let sampled = rows(&bernoulli);
+        assert!((50..150).contains(&sampled), "{} rows", sampled);
+        assert_eq!(rows(&bernoulli), sampled);
+        assert_ne!(rows(&bernoulli.with_seed(2)), 0);
+    }
+
+    #[test]
+    fn test_conformance() {
+        let mut batches = test_batches();
+        batches.extend(test_batches());
+        conformance::check(&InMemoryDataSource::new(test_schema(), batches));
+    }
 }
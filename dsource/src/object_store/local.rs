@@ -0,0 +1,180 @@
+//! Object store over a directory of the local filesystem.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use bytes::Bytes;
+
+use super::{ObjectMeta, ObjectStore};
+
+/// An object store whose objects are the files under a root directory.
+#[derive(Debug, Clone)]
+pub struct LocalFileSystem {
+    root: PathBuf,
+}
+
+impl LocalFileSystem {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the path of a location, which must be relative and stay
+    /// under the root.
+    fn path(&self, location: &str) -> Result<PathBuf, String> {
+        let relative = Path::new(location);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(format!("invalid location: {}", location));
+        }
+        Ok(self.root.join(relative))
+    }
+
+    /// Appends the metadata of every file under `dir` whose location starts
+    /// with `prefix` to `out`. Symbolic links to directories are not
+    /// followed.
+    fn walk(
+        &self,
+        dir: &Path,
+        location: &str,
+        prefix: &str,
+        out: &mut Vec<ObjectMeta>,
+    ) -> Result<(), String> {
+        let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {}", location, e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let child = match location {
+                "" => name,
+                _ => format!("{}/{}", location, name),
+            };
+            let file_type = entry.file_type().map_err(|e| e.to_string())?;
+            if file_type.is_dir() {
+                if format!("{}/", child).starts_with(prefix) {
+                    self.walk(&entry.path(), &child, prefix, out)?;
+                }
+            } else if child.starts_with(prefix) && entry.path().is_file() {
+                out.push(self.head(&child)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ObjectStore for LocalFileSystem {
+    /// Only the directory holding the prefix is walked, and listing a
+    /// directory that does not exist returns nothing.
+    fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, String> {
+        let dir = prefix.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let path = self.path(dir)?;
+        let mut objects = Vec::new();
+        if path.is_dir() {
+            self.walk(&path, dir.trim_end_matches('/'), prefix, &mut objects)?;
+        }
+        objects.sort_by(|a, b| a.location.cmp(&b.location));
+        Ok(objects)
+    }
+
+    fn head(&self, location: &str) -> Result<ObjectMeta, String> {
+        let path = self.path(location)?;
+        let metadata = std::fs::metadata(&path).map_err(|e| format!("{}: {}", location, e))?;
+        if metadata.is_dir() {
+            return Err(format!("{}: is a directory", location));
+        }
+        Ok(ObjectMeta {
+            location: location.to_string(),
+            size: metadata.len(),
+            last_modified: metadata.modified().ok(),
+        })
+    }
+
+    fn get_range(&self, location: &str, range: Range<u64>) -> Result<Bytes, String> {
+        let mut file =
+            File::open(self.path(location)?).map_err(|e| format!("{}: {}", location, e))?;
+        file.seek(SeekFrom::Start(range.start))
+            .map_err(|e| e.to_string())?;
+        let mut buf = Vec::with_capacity(range.end.saturating_sub(range.start) as usize);
+        file.take(range.end.saturating_sub(range.start))
+            .read_to_end(&mut buf)
+            .map_err(|e| e.to_string())?;
+        Ok(buf.into())
+    }
+
+    /// Writes to a temporary file that is renamed into place, so readers
+    /// never observe a partially written object.
+    fn put(&self, location: &str, data: Bytes) -> Result<(), String> {
+        let path = self.path(location)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(format!(".{}.tmp", std::process::id()));
+
+        let mut file = File::create(&tmp).map_err(|e| e.to_string())?;
+        file.write_all(&data).map_err(|e| e.to_string())?;
+        file.sync_all().map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, &path).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_file_system() {
+        let root = std::env::temp_dir().join(format!("dbms-local-store-{}", std::process::id()));
+        let store = LocalFileSystem::new(&root);
+        store.put("b/2.txt", Bytes::from("two")).unwrap();
+        store.put("a.txt", Bytes::from("one")).unwrap();
+        store.put("b/1.txt", Bytes::from("first")).unwrap();
+
+        let locations: Vec<_> = store
+            .list("")
+            .unwrap()
+            .into_iter()
+            .map(|meta| meta.location)
+            .collect();
+        assert_eq!(locations, vec!["a.txt", "b/1.txt", "b/2.txt"]);
+        assert_eq!(store.list("b/").unwrap().len(), 2);
+
+        let meta = store.head("b/1.txt").unwrap();
+        assert_eq!(meta.size, 5);
+        assert!(meta.last_modified.is_some());
+        assert_eq!(store.get_range("b/1.txt", 1..3).unwrap(), "ir");
+        assert_eq!(store.get("a.txt").unwrap(), "one");
+        assert!(store.head("b").is_err());
+        assert!(store.head("missing.txt").is_err());
+
+        // Listing below a missing directory finds nothing
+        assert!(store.list("a.txt/").unwrap().is_empty());
+        assert!(store.list("missing/").unwrap().is_empty());
+
+        // Locations cannot escape the root
+        assert_eq!(
+            store.head("../a.txt").unwrap_err(),
+            "invalid location: ../a.txt"
+        );
+        assert!(store.get("/etc/hostname").is_err());
+        assert!(store.put("b/../../c.txt", Bytes::from("x")).is_err());
+
+        // Symbolic links to directories are not followed
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&root, root.join("loop")).unwrap();
+            std::os::unix::fs::symlink(root.join("a.txt"), root.join("c.txt")).unwrap();
+            let locations: Vec<_> = store
+                .list("")
+                .unwrap()
+                .into_iter()
+                .map(|meta| meta.location)
+                .collect();
+            assert_eq!(locations, vec!["a.txt", "b/1.txt", "b/2.txt", "c.txt"]);
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+//! Object store held in memory, mainly for tests.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+
+use super::{ObjectMeta, ObjectStore};
+
+/// An object store that keeps every object in memory.
+#[derive(Debug, Default)]
+pub struct InMemoryObjectStore {
+    objects: RwLock<BTreeMap<String, (Bytes, SystemTime)>>,
+}
+
+impl InMemoryObjectStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ObjectStore for InMemoryObjectStore {
+    fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, String> {
+        let objects = self.objects.read().map_err(|e| e.to_string())?;
+        Ok(objects
+            .range(prefix.to_string()..)
+            .take_while(|(location, _)| location.starts_with(prefix))
+            .map(|(location, (data, modified))| ObjectMeta {
+                location: location.clone(),
+                size: data.len() as u64,
+                last_modified: Some(*modified),
+            })
+            .collect())
+    }
+
+    fn head(&self, location: &str) -> Result<ObjectMeta, String> {
+        let objects = self.objects.read().map_err(|e| e.to_string())?;
+        let (data, modified) = objects
+            .get(location)
+            .ok_or_else(|| format!("{}: object not found", location))?;
+        Ok(ObjectMeta {
+            location: location.to_string(),
+            size: data.len() as u64,
+            last_modified: Some(*modified),
+        })
+    }
+
+    fn get_range(&self, location: &str, range: Range<u64>) -> Result<Bytes, String> {
+        let objects = self.objects.read().map_err(|e| e.to_string())?;
+        let (data, _) = objects
+            .get(location)
+            .ok_or_else(|| format!("{}: object not found", location))?;
+        let end = (range.end as usize).min(data.len());
+        let start = (range.start as usize).min(end);
+        Ok(data.slice(start..end))
+    }
+
+    fn put(&self, location: &str, data: Bytes) -> Result<(), String> {
+        let mut objects = self.objects.write().map_err(|e| e.to_string())?;
+        objects.insert(location.to_string(), (data, SystemTime::now()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_store() {
+        let store = InMemoryObjectStore::new();
+        store.put("t/a", Bytes::from("aaa")).unwrap();
+        store.put("t/b", Bytes::from("bb")).unwrap();
+        store.put("u", Bytes::from("c")).unwrap();
+
+        let listed = store.list("t/").unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1].location, "t/b");
+        assert_eq!(listed[1].size, 2);
+        assert_eq!(store.get_range("t/a", 1..10).unwrap(), "aa");
+        assert!(store.head("t").is_err());
+    }
+}
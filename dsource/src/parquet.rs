@@ -1,90 +1,514 @@
 //! Parquet data source implementation.
+//!
+//! A source reads a single file or every `.parquet` file under a directory,
+//! through an object store so that only footers and the needed column chunks
+//! are fetched. Each file is adapted to the table schema, so files written
+//! before a column was added or widened can be read alongside newer ones.
+
+mod adapter;
 
-use std::fs::File;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
-use dbms_dtype::{RecordBatch, Schema};
+use arrow::array::{Array, ArrayRef, BooleanArray};
+use arrow::datatypes::DataType as ArrowDataType;
+use dbms_dtype::{RecordBatch, Scalar, Schema};
+use futures::{StreamExt, TryStreamExt};
+use parquet::arrow::arrow_reader::statistics::StatisticsConverter;
 use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+use parquet::arrow::{ParquetRecordBatchStreamBuilder, ProjectionMask};
+use parquet::file::metadata::RowGroupMetaData;
+
+use crate::object_store::local_object;
+use crate::sample::sample_batches;
+use crate::statistics::min_max;
+use crate::stream::current_runtime;
+use crate::{
+    BatchIterator, BatchStream, ColumnStatistics, DataSource, ObjectMeta, ObjectReader,
+    ObjectStore, Precision, Sample, SampleMethod, Statistics, check_partition, resolve_projection,
+};
+
+use adapter::{SchemaAdapter, widen};
 
-use crate::DataSource;
+pub use adapter::ColumnMatching;
 
 /// A data source that reads from Parquet files.
 pub struct ParquetDataSource {
-    path: PathBuf,
+    store: Arc<dyn ObjectStore>,
+    location: String,
     batch_size: usize,
+    table_schema: Option<Schema>,
+    column_matching: ColumnMatching,
+    row_groups: Mutex<Option<CachedRowGroups>>,
+}
+
+/// The row groups of the files last read, with the files they were read from.
+struct CachedRowGroups {
+    files: Vec<ObjectMeta>,
+    row_groups: Arc<Vec<(ObjectReader, usize)>>,
 }
 
 impl ParquetDataSource {
+    /// Creates a source over a Parquet file, or over every `.parquet` file
+    /// under a directory in name order.
     pub fn new(path: impl Into<PathBuf>, batch_size: usize) -> Self {
+        let (store, location) = local_object(&path.into());
+        Self::from_store(store, location, batch_size)
+    }
+
+    /// Creates a source over the object at `location` in `store`, or over
+    /// every `.parquet` object under it if `location` is a prefix.
+    pub fn from_store(
+        store: Arc<dyn ObjectStore>,
+        location: impl Into<String>,
+        batch_size: usize,
+    ) -> Self {
         Self {
-            path: path.into(),
+            store,
+            location: location.into(),
             batch_size,
+            table_schema: None,
+            column_matching: ColumnMatching::default(),
+            row_groups: Mutex::new(None),
         }
     }
+
+    /// Sets the schema that every file is adapted to, which is otherwise the
+    /// schema of the first file. Columns missing from a file are read as
+    /// nulls, and columns of a narrower numeric type are widened.
+    pub fn with_table_schema(mut self, schema: Schema) -> Self {
+        self.table_schema = Some(schema);
+        self
+    }
+
+    /// Sets how table columns are matched to the columns of each file.
+    pub fn with_column_matching(mut self, column_matching: ColumnMatching) -> Self {
+        self.column_matching = column_matching;
+        self
+    }
+
+    /// Returns readers over the files to read, in location order.
+    fn files(&self) -> Result<Vec<ObjectReader>, String> {
+        Ok(self
+            .objects()?
+            .iter()
+            .map(|meta| ObjectReader::new(self.store.clone(), meta))
+            .collect())
+    }
+
+    /// Lists the files to read, in location order.
+    fn objects(&self) -> Result<Vec<ObjectMeta>, String> {
+        let head = match self.location.as_str() {
+            "" => Err("no location".to_string()),
+            location => self.store.head(location),
+        };
+        if let Ok(meta) = head {
+            return Ok(vec![meta]);
+        }
+
+        let prefix = match self.location.trim_end_matches('/') {
+            "" => String::new(),
+            location => format!("{}/", location),
+        };
+        let objects = self.store.list(&prefix)?;
+        if objects.is_empty()
+            && let Err(e) = head
+            && !self.location.is_empty()
+        {
+            return Err(e);
+        }
+        Ok(objects
+            .into_iter()
+            .filter(|meta| meta.location.ends_with(".parquet"))
+            .collect())
+    }
+
+    /// Returns every row group of every file; each is a partition. The
+    /// footers are read again only when the files' sizes or modification
+    /// times change.
+    fn row_groups(&self) -> Result<Arc<Vec<(ObjectReader, usize)>>, String> {
+        let files = self.objects()?;
+        let mut cached = self.row_groups.lock().map_err(|e| e.to_string())?;
+        if let Some(c) = cached.as_ref()
+            && c.files == files
+        {
+            return Ok(c.row_groups.clone());
+        }
+
+        let mut row_groups = Vec::new();
+        for meta in &files {
+            let file = ObjectReader::new(self.store.clone(), meta);
+            let count = open(file.clone())?.metadata().num_row_groups();
+            row_groups.extend((0..count).map(|i| (file.clone(), i)));
+        }
+        let row_groups = Arc::new(row_groups);
+        *cached = Some(CachedRowGroups {
+            files,
+            row_groups: row_groups.clone(),
+        });
+        Ok(row_groups)
+    }
+
+    /// Returns the table schema, projected to the given columns.
+    fn projected_schema(&self, projection: Option<&[&str]>) -> Result<Schema, String> {
+        let schema = self.schema()?;
+        match projection {
+            Some(cols) => Ok(schema.project(&resolve_projection(&schema, cols)?)),
+            None => Ok(schema),
+        }
+    }
+
+    /// Reads the given files, or only the given row groups of each, adapted
+    /// to the projected table schema.
+    fn read(
+        &self,
+        projection: Option<&[&str]>,
+        files: Vec<(ObjectReader, Option<Vec<usize>>)>,
+    ) -> Result<BatchIterator, String> {
+        let table = self.projected_schema(projection)?;
+        let column_matching = self.column_matching.clone();
+        let batch_size = self.batch_size;
+
+        Ok(Box::new(files.into_iter().flat_map(
+            move |(file, row_groups)| -> BatchIterator {
+                match read_file(file, &table, &column_matching, row_groups, batch_size) {
+                    Ok(batches) => batches,
+                    Err(e) => Box::new(std::iter::once(Err(e))),
+                }
+            },
+        )))
+    }
+}
+
+/// Opens a reader builder over a Parquet file, reading its footer.
+fn open(file: ObjectReader) -> Result<ParquetRecordBatchReaderBuilder<ObjectReader>, String> {
+    let location = file.location().to_string();
+    ParquetRecordBatchReaderBuilder::try_new(file).map_err(|e| format!("{}: {}", location, e))
+}
+
+/// Reads a file, or only the given row groups, adapted to `table`.
+fn read_file(
+    file: ObjectReader,
+    table: &Schema,
+    column_matching: &ColumnMatching,
+    row_groups: Option<Vec<usize>>,
+    batch_size: usize,
+) -> Result<BatchIterator, String> {
+    let location = file.location().to_string();
+    let mut builder = open(file)?;
+    let adapter = SchemaAdapter::try_new(table, builder.schema(), column_matching)
+        .map_err(|e| format!("{}: {}", location, e))?;
+
+    let mask = ProjectionMask::roots(
+        builder.parquet_schema(),
+        adapter.file_columns().iter().copied(),
+    );
+    builder = builder.with_projection(mask);
+    if let Some(row_groups) = row_groups {
+        builder = builder.with_row_groups(row_groups);
+    }
+
+    let reader = builder
+        .with_batch_size(batch_size)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    Ok(Box::new(ParquetBatchIterator { reader, adapter }))
+}
+
+/// Reads a file asynchronously, adapted to `table`.
+async fn stream_file(
+    file: ObjectReader,
+    table: Schema,
+    column_matching: ColumnMatching,
+    batch_size: usize,
+) -> Result<BatchStream, String> {
+    let location = file.location().to_string();
+    let builder = ParquetRecordBatchStreamBuilder::new(file)
+        .await
+        .map_err(|e| format!("{}: {}", location, e))?;
+    let adapter = SchemaAdapter::try_new(&table, builder.schema(), &column_matching)
+        .map_err(|e| format!("{}: {}", location, e))?;
+
+    let mask = ProjectionMask::roots(
+        builder.parquet_schema(),
+        adapter.file_columns().iter().copied(),
+    );
+    let batches = builder
+        .with_projection(mask)
+        .with_batch_size(batch_size)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    Ok(Box::pin(batches.map(move |batch| {
+        let batch = batch.map_err(|e| e.to_string())?;
+        adapter.adapt(batch)?.try_into()
+    })))
+}
+
+/// Reads statistics of a file from its footer, adapted to `table`.
+fn file_statistics(
+    file: ObjectReader,
+    table: &Schema,
+    column_matching: &ColumnMatching,
+) -> Result<Statistics, String> {
+    let location = file.location().to_string();
+    let builder = open(file)?;
+    let arrow_schema = builder.schema();
+    let parquet_schema = builder.parquet_schema();
+    let row_groups = builder.metadata().row_groups();
+    let adapter = SchemaAdapter::try_new(table, arrow_schema, column_matching)
+        .map_err(|e| format!("{}: {}", location, e))?;
+    let num_rows = row_groups.iter().map(|rg| rg.num_rows() as usize).sum();
+
+    let mut column_statistics = Vec::with_capacity(table.fields().len());
+    for (i, field) in table.fields().iter().enumerate() {
+        let Some(index) = adapter.file_column(i) else {
+            // Columns missing from the file are read as nulls
+            column_statistics.push(ColumnStatistics {
+                null_count: Precision::Exact(num_rows),
+                min_value: Precision::Exact(Scalar::null(field.dtype().clone())),
+                max_value: Precision::Exact(Scalar::null(field.dtype().clone())),
+                distinct_count: Precision::Exact(0),
+            });
+            continue;
+        };
+        let converter = StatisticsConverter::try_new(
+            arrow_schema.field(index).name(),
+            arrow_schema,
+            parquet_schema,
+        )
+        .map_err(|e| e.to_string())?;
+        column_statistics.push(column_statistics_of(
+            &converter,
+            row_groups,
+            adapter.data_type(i),
+        )?);
+    }
+
+    let total_byte_size = row_groups
+        .iter()
+        .map(|rg| rg.compressed_size() as usize)
+        .sum();
+    Ok(Statistics {
+        num_rows: Precision::Exact(num_rows),
+        total_byte_size: Precision::Exact(total_byte_size),
+        column_statistics,
+    })
+}
+
+/// Combines the row group statistics of a column, with bounds widened to
+/// the table type.
+fn column_statistics_of(
+    converter: &StatisticsConverter,
+    row_groups: &[RowGroupMetaData],
+    data_type: &ArrowDataType,
+) -> Result<ColumnStatistics, String> {
+    let null_counts = converter
+        .row_group_null_counts(row_groups.iter())
+        .map_err(|e| e.to_string())?;
+    let mins = converter
+        .row_group_mins(row_groups.iter())
+        .map_err(|e| e.to_string())?;
+    let maxes = converter
+        .row_group_maxes(row_groups.iter())
+        .map_err(|e| e.to_string())?;
+
+    // A missing bound is only expected for row groups that are all null
+    let complete = |bounds: &ArrayRef| {
+        (0..row_groups.len()).all(|i| {
+            bounds.is_valid(i)
+                || (null_counts.is_valid(i)
+                    && null_counts.value(i) as i64 == row_groups[i].num_rows())
+        })
+    };
+    let all_exact =
+        |flags: BooleanArray| flags.null_count() == 0 && flags.true_count() == flags.len();
+
+    let min_value = if complete(&mins) {
+        let exact = converter
+            .row_group_is_min_value_exact(row_groups.iter())
+            .map_err(|e| e.to_string())?;
+        let precision = Precision::Exact(min_max(&widen(&mins, data_type)?)?.0);
+        if all_exact(exact) {
+            precision
+        } else {
+            precision.to_inexact()
+        }
+    } else {
+        Precision::Absent
+    };
+    let max_value = if complete(&maxes) {
+        let exact = converter
+            .row_group_is_max_value_exact(row_groups.iter())
+            .map_err(|e| e.to_string())?;
+        let precision = Precision::Exact(min_max(&widen(&maxes, data_type)?)?.1);
+        if all_exact(exact) {
+            precision
+        } else {
+            precision.to_inexact()
+        }
+    } else {
+        Precision::Absent
+    };
+
+    let null_count = if null_counts.null_count() == 0 {
+        Precision::Exact(null_counts.values().iter().sum::<u64>() as usize)
+    } else {
+        Precision::Absent
+    };
+
+    // Distinct counts cannot be combined across row groups
+    let distinct_count = match (row_groups, converter.parquet_column_index()) {
+        ([row_group], Some(index)) => row_group
+            .column(index)
+            .statistics()
+            .and_then(|s| s.distinct_count_opt())
+            .map_or(Precision::Absent, |n| Precision::Exact(n as usize)),
+        _ => Precision::Absent,
+    };
+
+    Ok(ColumnStatistics {
+        null_count,
+        min_value,
+        max_value,
+        distinct_count,
+    })
 }
 
 impl DataSource for ParquetDataSource {
     fn schema(&self) -> Result<Schema, String> {
-        let file = File::open(&self.path).map_err(|e| e.to_string())?;
-        let builder =
-            ParquetRecordBatchReaderBuilder::try_new(file).map_err(|e| e.to_string())?;
-        Schema::try_from(builder.schema().as_ref())
+        if let Some(schema) = &self.table_schema {
+            return Ok(schema.clone());
+        }
+        let first = self
+            .files()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("no Parquet files at '{}'", self.location))?;
+        Schema::try_from(open(first)?.schema().as_ref())
+    }
+
+    fn scan(&self, projection: Option<&[&str]>) -> Result<BatchIterator, String> {
+        let files = self.files()?.into_iter().map(|file| (file, None)).collect();
+        self.read(projection, files)
     }
 
-    fn scan(
+    /// Files are read with the asynchronous Parquet reader, one at a time.
+    fn scan_stream(&self, projection: Option<&[&str]>) -> Result<BatchStream, String> {
+        current_runtime()?;
+        let table = self.projected_schema(projection)?;
+        let column_matching = self.column_matching.clone();
+        let batch_size = self.batch_size;
+
+        let files = futures::stream::iter(self.files()?);
+        Ok(Box::pin(
+            files
+                .then(move |file| {
+                    stream_file(file, table.clone(), column_matching.clone(), batch_size)
+                })
+                .try_flatten(),
+        ))
+    }
+
+    /// Block samples are drawn from row groups, so unsampled row groups are
+    /// not read.
+    fn scan_sample(
         &self,
+        sample: &Sample,
         projection: Option<&[&str]>,
-    ) -> Result<Box<dyn Iterator<Item = Result<RecordBatch, String>>>, String> {
-        let file = File::open(&self.path).map_err(|e| e.to_string())?;
-        let mut builder =
-            ParquetRecordBatchReaderBuilder::try_new(file).map_err(|e| e.to_string())?;
-        let arrow_schema = builder.schema().clone();
-
-        // Apply projection if specified
-        if let Some(cols) = projection {
-            let mask: Vec<bool> = arrow_schema
-                .fields()
-                .iter()
-                .map(|f| cols.contains(&f.name().as_str()))
-                .collect();
-            let projection = parquet::arrow::ProjectionMask::leaves(
-                builder.parquet_schema(),
-                mask.iter()
-                    .enumerate()
-                    .filter_map(|(i, &b)| if b { Some(i) } else { None }),
-            );
-            builder = builder.with_projection(projection);
-        }
-
-        let reader = builder
-            .with_batch_size(self.batch_size)
-            .build()
-            .map_err(|e| e.to_string())?;
+    ) -> Result<BatchIterator, String> {
+        if sample.method() != SampleMethod::Block {
+            return Ok(sample_batches(self.scan(projection)?, *sample));
+        }
+        let mut files: Vec<(ObjectReader, Option<Vec<usize>>)> = Vec::new();
+        for (i, (file, row_group)) in self.row_groups()?.iter().enumerate() {
+            if !sample.keeps(i as u64) {
+                continue;
+            }
+            match files.last_mut() {
+                Some((last, Some(row_groups))) if last.location() == file.location() => {
+                    row_groups.push(*row_group)
+                }
+                _ => files.push((file.clone(), Some(vec![*row_group]))),
+            }
+        }
+        self.read(projection, files)
+    }
 
-        Ok(Box::new(ParquetBatchIterator { reader }))
+    /// Each row group of each file is a partition.
+    fn partition_count(&self) -> Result<usize, String> {
+        Ok(self.row_groups()?.len().max(1))
+    }
+
+    fn scan_partition(
+        &self,
+        partition: usize,
+        projection: Option<&[&str]>,
+    ) -> Result<BatchIterator, String> {
+        let row_groups = self.row_groups()?;
+        check_partition(partition, row_groups.len().max(1))?;
+
+        // Without any row groups the single partition is empty
+        let files = row_groups
+            .get(partition)
+            .map(|(file, row_group)| vec![(file.clone(), Some(vec![*row_group]))])
+            .unwrap_or_default();
+        self.read(projection, files)
+    }
+
+    /// Reads statistics from the file footers without scanning any data.
+    fn statistics(&self) -> Result<Statistics, String> {
+        let table = self.schema()?;
+        let mut statistics = Statistics {
+            num_rows: Precision::Exact(0),
+            total_byte_size: Precision::Exact(0),
+            column_statistics: vec![ColumnStatistics::default(); table.fields().len()],
+        };
+        for (i, file) in self.files()?.into_iter().enumerate() {
+            let file = file_statistics(file, &table, &self.column_matching)?;
+            statistics = if i == 0 { file } else { statistics.merge(file) };
+        }
+        Ok(statistics)
     }
 }
 
 struct ParquetBatchIterator {
     reader: ParquetRecordBatchReader,
+    adapter: SchemaAdapter,
 }
 
 impl Iterator for ParquetBatchIterator {
     type Item = Result<RecordBatch, String>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.reader.next() {
-            Some(Ok(batch)) => Some(batch.try_into()),
-            Some(Err(e)) => Some(Err(e.to_string())),
-            None => None,
-        }
+        let batch = match self.reader.next()? {
+            Ok(batch) => batch,
+            Err(e) => return Some(Err(e.to_string())),
+        };
+        Some(self.adapter.adapt(batch).and_then(|batch| batch.try_into()))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::conformance;
+    use crate::{
+        DataSink, InMemoryObjectStore, ObjectMeta, ParquetSink, ParquetWriteOptions,
+        SequenceDataSource,
+    };
+    use arrow::array::{Float64Array, Int32Array, Int64Array, StringArray};
+    use arrow::datatypes::{Field as ArrowField, Schema as ArrowSchema};
+    use arrow::record_batch::RecordBatch as ArrowRecordBatch;
+    use bytes::Bytes;
+    use dbms_dtype::{Column, DataType, Field};
+    use parquet::arrow::{ArrowWriter, PARQUET_FIELD_ID_META_KEY};
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::ops::Range;
     use std::path::PathBuf;
+    use std::sync::Arc;
 
     fn test_data_path(relative: &str) -> PathBuf {
         PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -120,11 +544,332 @@ mod tests {
     fn test_projection() {
         let path = test_data_path("parquet/simple.parquet");
         let source = ParquetDataSource::new(path, 1024);
-        let batches: Vec<_> = source.scan(Some(&["name", "age"])).unwrap().collect();
+        let batches: Vec<_> = source.scan(Some(&["age", "name"])).unwrap().collect();
 
         assert_eq!(batches.len(), 1);
         let batch = batches[0].as_ref().unwrap();
         assert_eq!(batch.row_count(), 3);
         assert_eq!(batch.column_count(), 2);
+        assert_eq!(batch.schema().fields()[0].name(), "age");
+        assert_eq!(batch.schema().fields()[1].name(), "name");
+        assert_eq!(batch.field(0).get(0), Scalar::Int64(Some(30)));
+    }
+
+    #[test]
+    fn test_statistics() {
+        let path = test_data_path("parquet/simple.parquet");
+        let source = ParquetDataSource::new(path, 1024);
+        let stats = source.statistics().unwrap();
+
+        assert_eq!(stats.num_rows, Precision::Exact(3));
+        assert_eq!(stats.column_statistics.len(), 3);
+        let id = &stats.column_statistics[0];
+        assert_eq!(id.null_count, Precision::Exact(0));
+        assert_eq!(id.min_value.get(), Some(&Scalar::Int64(Some(1))));
+        assert_eq!(id.max_value.get(), Some(&Scalar::Int64(Some(3))));
+    }
+
+    #[test]
+    fn test_partitions() {
+        let path = std::env::temp_dir().join(format!(
+            "dbms-parquet-partitions-{}.parquet",
+            std::process::id()
+        ));
+        let schema = Schema::new(vec![Field::new("id", DataType::Int64)]);
+        let batches: Vec<_> = (0..5)
+            .map(|i| {
+                RecordBatch::new(
+                    schema.clone(),
+                    vec![Column::from_literal(Scalar::Int64(Some(i)), 2)],
+                )
+            })
+            .collect();
+        ParquetSink::new(&path)
+            .with_options(ParquetWriteOptions::default().with_max_row_group_size(4))
+            .write(&schema, Box::new(batches.into_iter().map(Ok)))
+            .unwrap();
+
+        let source = ParquetDataSource::new(&path, 1024);
+        assert_eq!(source.partition_count().unwrap(), 3);
+
+        let rows: Vec<usize> = (0..3)
+            .map(|p| {
+                source
+                    .scan_partition(p, None)
+                    .unwrap()
+                    .map(|b| b.unwrap().row_count())
+                    .sum()
+            })
+            .collect();
+        assert_eq!(rows, vec![4, 4, 2]);
+        assert!(source.scan_partition(3, None).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_block_sample() {
+        let path = std::env::temp_dir().join(format!(
+            "dbms-parquet-sample-{}.parquet",
+            std::process::id()
+        ));
+        let ids = SequenceDataSource::range(0, 1000, 1).unwrap();
+        ParquetSink::new(&path)
+            .with_options(ParquetWriteOptions::default().with_max_row_group_size(10))
+            .write(&ids.schema().unwrap(), ids.scan(None).unwrap())
+            .unwrap();
+
+        // Whole row groups of ten consecutive ids are kept
+        let source = ParquetDataSource::new(&path, 1024);
+        let sample = Sample::block(0.1).unwrap().with_seed(42);
+        let mut firsts = Vec::new();
+        for batch in source.scan_sample(&sample, None).unwrap() {
+            let batch = batch.unwrap();
+            for row in (0..batch.row_count()).step_by(10) {
+                let Scalar::Int64(Some(id)) = batch.field(0).get(row) else {
+                    panic!("null id");
+                };
+                firsts.push(id);
+            }
+        }
+        let expected: Vec<i64> = (0..100)
+            .filter(|&i| sample.keeps(i))
+            .map(|i| i as i64 * 10)
+            .collect();
+        assert_eq!(firsts, expected);
+        assert!(
+            (3..30).contains(&firsts.len()),
+            "{} row groups",
+            firsts.len()
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_conformance() {
+        let path = test_data_path("parquet/simple.parquet");
+        conformance::check(&ParquetDataSource::new(path, 2));
+    }
+
+    /// Writes a directory holding an older file, with a narrower `id` and no
+    /// `score`, and a newer file in which `name` was renamed to `user_name`.
+    fn evolved_dataset(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("dbms-parquet-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let field = |name: &str, dtype, id: i32| {
+            ArrowField::new(name, dtype, true).with_metadata(HashMap::from([(
+                PARQUET_FIELD_ID_META_KEY.to_string(),
+                id.to_string(),
+            )]))
+        };
+        let old = ArrowRecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                field("id", ArrowDataType::Int32, 1),
+                field("name", ArrowDataType::Utf8, 2),
+            ])),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec!["a", "b"])),
+            ],
+        )
+        .unwrap();
+        let new = ArrowRecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                field("id", ArrowDataType::Int64, 1),
+                field("user_name", ArrowDataType::Utf8, 2),
+                field("score", ArrowDataType::Float64, 3),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![3])),
+                Arc::new(StringArray::from(vec!["c"])),
+                Arc::new(Float64Array::from(vec![0.5])),
+            ],
+        )
+        .unwrap();
+
+        for (file, batch) in [("part-0.parquet", old), ("part-1.parquet", new)] {
+            let file = File::create(dir.join(file)).unwrap();
+            let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+            writer.write(&batch).unwrap();
+            writer.close().unwrap();
+        }
+        dir
+    }
+
+    fn table_schema() -> Schema {
+        Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::Utf8),
+            Field::new("score", DataType::Float64),
+        ])
+    }
+
+    #[test]
+    fn test_schema_evolution() {
+        let dir = evolved_dataset("evolution");
+        let matching = ColumnMatching::ByFieldId(HashMap::from([("name".to_string(), 2)]));
+        let source = ParquetDataSource::new(&dir, 1024)
+            .with_table_schema(table_schema())
+            .with_column_matching(matching);
+
+        let batches: Vec<_> = source.scan(None).unwrap().map(Result::unwrap).collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].schema(), &table_schema());
+        assert_eq!(batches[0].field(0).get(1), Scalar::Int64(Some(2)));
+        assert_eq!(batches[0].field(2).get(0), Scalar::Float64(None));
+        assert_eq!(
+            batches[1].field(1).get(0),
+            Scalar::Utf8(Some("c".to_string()))
+        );
+        assert_eq!(batches[1].field(2).get(0), Scalar::Float64(Some(0.5)));
+
+        let stats = source.statistics().unwrap();
+        assert_eq!(stats.num_rows, Precision::Exact(3));
+        let id = &stats.column_statistics[0];
+        assert_eq!(id.min_value.get(), Some(&Scalar::Int64(Some(1))));
+        assert_eq!(id.max_value.get(), Some(&Scalar::Int64(Some(3))));
+        assert_eq!(stats.column_statistics[2].null_count, Precision::Exact(2));
+
+        assert_eq!(source.partition_count().unwrap(), 2);
+        conformance::check(&source);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scan_stream() {
+        let dir = evolved_dataset("stream");
+        let source = ParquetDataSource::new(&dir, 1024).with_table_schema(table_schema());
+
+        let batches: Vec<_> = source
+            .scan_stream(Some(&["score", "id"]))
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].field(0).get(0), Scalar::Float64(None));
+        assert_eq!(batches[1].field(1).get(0), Scalar::Int64(Some(3)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_match_by_name() {
+        // Matched by name, the renamed column is missing from the newer file
+        let dir = evolved_dataset("by-name");
+        let source = ParquetDataSource::new(&dir, 1024).with_table_schema(table_schema());
+
+        let batches: Vec<_> = source
+            .scan(Some(&["name"]))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            batches[0].field(0).get(0),
+            Scalar::Utf8(Some("a".to_string()))
+        );
+        assert_eq!(batches[1].field(0).get(0), Scalar::Utf8(None));
+
+        // Without a table schema, the first file's schema is used
+        let source = ParquetDataSource::new(&dir, 1024);
+        assert_eq!(
+            source.schema().unwrap().fields()[0].dtype(),
+            &DataType::Int32
+        );
+        let err = source.scan(None).unwrap().nth(1).unwrap().unwrap_err();
+        assert!(
+            err.ends_with("column id: cannot read Int64 as Int32"),
+            "{}",
+            err
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Records the bytes fetched from an in-memory store.
+    #[derive(Default)]
+    struct RecordingStore {
+        inner: InMemoryObjectStore,
+        fetched: std::sync::atomic::AtomicU64,
+    }
+
+    impl ObjectStore for RecordingStore {
+        fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, String> {
+            self.inner.list(prefix)
+        }
+
+        fn head(&self, location: &str) -> Result<ObjectMeta, String> {
+            self.inner.head(location)
+        }
+
+        fn get_range(&self, location: &str, range: Range<u64>) -> Result<Bytes, String> {
+            let bytes = self.inner.get_range(location, range)?;
+            self.fetched
+                .fetch_add(bytes.len() as u64, std::sync::atomic::Ordering::Relaxed);
+            Ok(bytes)
+        }
+
+        fn put(&self, location: &str, data: Bytes) -> Result<(), String> {
+            self.inner.put(location, data)
+        }
+    }
+
+    #[test]
+    fn test_object_store() {
+        let store = Arc::new(RecordingStore::default());
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", arrow::datatypes::DataType::Int64, false),
+            ArrowField::new("payload", arrow::datatypes::DataType::Utf8, false),
+        ]));
+        for i in 0..2 {
+            let batch = ArrowRecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from_iter_values(0..10000)),
+                    Arc::new(StringArray::from_iter_values(
+                        (0..10000).map(|j| format!("payload-{}-{}", i, j)),
+                    )),
+                ],
+            )
+            .unwrap();
+            let mut data = Vec::new();
+            let mut writer = ArrowWriter::try_new(&mut data, schema.clone(), None).unwrap();
+            writer.write(&batch).unwrap();
+            writer.close().unwrap();
+            store
+                .put(&format!("table/part-{}.parquet", i), data.into())
+                .unwrap();
+        }
+        store.put("table/_SUCCESS", Bytes::new()).unwrap();
+        let size: u64 = store.list("").unwrap().iter().map(|m| m.size).sum();
+
+        // Every .parquet object under the prefix is read
+        let source = ParquetDataSource::from_store(store.clone(), "table", 1024);
+        assert_eq!(source.partition_count().unwrap(), 2);
+
+        // Footers are read once while the files are unchanged
+        store.fetched.store(0, std::sync::atomic::Ordering::Relaxed);
+        assert_eq!(source.partition_count().unwrap(), 2);
+        assert_eq!(store.fetched.load(std::sync::atomic::Ordering::Relaxed), 0);
+
+        // A projected scan fetches the footers and only the projected chunks
+        store.fetched.store(0, std::sync::atomic::Ordering::Relaxed);
+        let rows: usize = source
+            .scan(Some(&["id"]))
+            .unwrap()
+            .map(|batch| batch.unwrap().row_count())
+            .sum();
+        assert_eq!(rows, 20000);
+        let fetched = store.fetched.load(std::sync::atomic::Ordering::Relaxed);
+        assert!(fetched < size / 2, "fetched {} of {} bytes", fetched, size);
+
+        let err = ParquetDataSource::from_store(store, "missing", 1024)
+            .schema()
+            .unwrap_err();
+        assert_eq!(err, "missing: object not found");
     }
 }
@@ -0,0 +1,224 @@
+//! Adapts Parquet files written with older schemas to a table schema.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, new_null_array};
+use arrow::compute::cast;
+use arrow::datatypes::{
+    DataType as ArrowDataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef,
+};
+use arrow::record_batch::{RecordBatch as ArrowRecordBatch, RecordBatchOptions};
+use dbms_dtype::Schema;
+use parquet::arrow::PARQUET_FIELD_ID_META_KEY;
+
+/// How table columns are matched to the columns of each Parquet file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ColumnMatching {
+    /// Columns are matched by name.
+    #[default]
+    ByName,
+    /// Columns are matched by the Parquet field id given for each table
+    /// column, so renamed columns are still found. Table columns without an
+    /// id are matched by name.
+    ByFieldId(HashMap<String, i32>),
+}
+
+impl ColumnMatching {
+    /// Returns the index of the file column holding the table column `name`.
+    fn find(&self, name: &str, file: &ArrowSchema) -> Option<usize> {
+        let mut fields = file.fields().iter();
+        match self {
+            Self::ByFieldId(ids) if ids.contains_key(name) => {
+                fields.position(|f| field_id(f) == Some(ids[name]))
+            }
+            _ => fields.position(|f| f.name() == name),
+        }
+    }
+}
+
+/// Returns the Parquet field id recorded in an Arrow field's metadata.
+fn field_id(field: &ArrowField) -> Option<i32> {
+    field
+        .metadata()
+        .get(PARQUET_FIELD_ID_META_KEY)?
+        .parse()
+        .ok()
+}
+
+/// Returns true if every value of type `from` is exactly representable as `to`.
+fn is_widening(from: &ArrowDataType, to: &ArrowDataType) -> bool {
+    use ArrowDataType::*;
+    matches!(
+        (from, to),
+        (Int8, Int16 | Int32 | Int64 | Float32 | Float64)
+            | (Int16, Int32 | Int64 | Float32 | Float64)
+            | (Int32, Int64 | Float64)
+            | (
+                UInt8,
+                UInt16 | UInt32 | UInt64 | Int16 | Int32 | Int64 | Float32 | Float64
+            )
+            | (UInt16, UInt32 | UInt64 | Int32 | Int64 | Float32 | Float64)
+            | (UInt32, UInt64 | Int64 | Float64)
+            | (Float32, Float64)
+    )
+}
+
+/// Maps the columns of one file onto (a projection of) the table schema.
+pub(super) struct SchemaAdapter {
+    schema: SchemaRef,
+    /// Position of each table column among the file columns read, if present.
+    sources: Vec<Option<usize>>,
+    /// Root indices of the file columns to read, in file order.
+    file_columns: Vec<usize>,
+}
+
+impl SchemaAdapter {
+    pub(super) fn try_new(
+        table: &Schema,
+        file: &ArrowSchema,
+        matching: &ColumnMatching,
+    ) -> Result<Self, String> {
+        let schema: SchemaRef = Arc::new(table.clone().into());
+
+        let mut matched = Vec::with_capacity(schema.fields().len());
+        for field in schema.fields() {
+            let index = matching.find(field.name(), file);
+            if let Some(i) = index {
+                let file_type = file.field(i).data_type();
+                if file_type != field.data_type() && !is_widening(file_type, field.data_type()) {
+                    return Err(format!(
+                        "column {}: cannot read {} as {}",
+                        field.name(),
+                        file_type,
+                        field.data_type()
+                    ));
+                }
+            }
+            matched.push(index);
+        }
+
+        let mut file_columns: Vec<usize> = matched.iter().flatten().copied().collect();
+        file_columns.sort_unstable();
+        file_columns.dedup();
+        let sources = matched
+            .iter()
+            .map(|index| index.map(|i| file_columns.binary_search(&i).unwrap()))
+            .collect();
+
+        Ok(Self {
+            schema,
+            sources,
+            file_columns,
+        })
+    }
+
+    /// Returns the root indices of the file columns to read, in file order.
+    pub(super) fn file_columns(&self) -> &[usize] {
+        &self.file_columns
+    }
+
+    /// Returns the index in the file of the `i`th table column, if present.
+    pub(super) fn file_column(&self, i: usize) -> Option<usize> {
+        self.sources[i].map(|p| self.file_columns[p])
+    }
+
+    /// Returns the Arrow type of the `i`th table column.
+    pub(super) fn data_type(&self, i: usize) -> &ArrowDataType {
+        self.schema.field(i).data_type()
+    }
+
+    /// Converts a batch of the file columns read into a batch of the table
+    /// schema, widening types and filling missing columns with nulls.
+    pub(super) fn adapt(&self, batch: ArrowRecordBatch) -> Result<ArrowRecordBatch, String> {
+        let columns = self
+            .schema
+            .fields()
+            .iter()
+            .zip(&self.sources)
+            .map(|(field, source)| match source {
+                Some(i) => widen(batch.column(*i), field.data_type()),
+                None => Ok(new_null_array(field.data_type(), batch.num_rows())),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+        ArrowRecordBatch::try_new_with_options(self.schema.clone(), columns, &options)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Casts an array to a type that was checked to be at least as wide.
+pub(super) fn widen(array: &ArrayRef, to: &ArrowDataType) -> Result<ArrayRef, String> {
+    if array.data_type() == to {
+        return Ok(array.clone());
+    }
+    cast(array, to).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Array, Int32Array, StringArray};
+    use dbms_dtype::{DataType, Field};
+
+    fn file_schema() -> ArrowSchema {
+        let with_id = |name: &str, dtype, id: i32| {
+            ArrowField::new(name, dtype, true).with_metadata(HashMap::from([(
+                PARQUET_FIELD_ID_META_KEY.to_string(),
+                id.to_string(),
+            )]))
+        };
+        ArrowSchema::new(vec![
+            with_id("id", ArrowDataType::Int32, 1),
+            with_id("user_name", ArrowDataType::Utf8, 2),
+        ])
+    }
+
+    #[test]
+    fn test_adapt() {
+        let table = Schema::new(vec![
+            Field::new("score", DataType::Float64),
+            Field::new("id", DataType::Int64),
+        ]);
+        let adapter =
+            SchemaAdapter::try_new(&table, &file_schema(), &ColumnMatching::ByName).unwrap();
+        assert_eq!(adapter.file_columns(), &[0]);
+        assert_eq!(adapter.file_column(0), None);
+        assert_eq!(adapter.file_column(1), Some(0));
+
+        let batch = ArrowRecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![file_schema().field(0).clone()])),
+            vec![Arc::new(Int32Array::from(vec![1, 2]))],
+        )
+        .unwrap();
+        let adapted = adapter.adapt(batch).unwrap();
+        assert_eq!(adapted.column(0).null_count(), 2);
+        assert_eq!(adapted.column(1).data_type(), &ArrowDataType::Int64);
+    }
+
+    #[test]
+    fn test_match_by_field_id() {
+        let table = Schema::new(vec![Field::new("name", DataType::Utf8)]);
+        let matching = ColumnMatching::ByFieldId(HashMap::from([("name".to_string(), 2)]));
+        let adapter = SchemaAdapter::try_new(&table, &file_schema(), &matching).unwrap();
+        assert_eq!(adapter.file_column(0), Some(1));
+
+        let batch = ArrowRecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![file_schema().field(1).clone()])),
+            vec![Arc::new(StringArray::from(vec!["a"]))],
+        )
+        .unwrap();
+        let adapted = adapter.adapt(batch).unwrap();
+        assert_eq!(adapted.schema().field(0).name(), "name");
+    }
+
+    #[test]
+    fn test_narrowing_rejected() {
+        let table = Schema::new(vec![Field::new("id", DataType::Int16)]);
+        let err = SchemaAdapter::try_new(&table, &file_schema(), &ColumnMatching::ByName)
+            .err()
+            .unwrap();
+        assert_eq!(err, "column id: cannot read Int32 as Int16");
+    }
+}
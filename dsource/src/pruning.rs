@@ -0,0 +1,170 @@
+//! Filters on column values used to skip files by their statistics.
+//!
+//! A filter only decides whether a file *may* hold matching rows; rows of
+//! the files that are read are returned unfiltered, so the query must still
+//! apply its predicate.
+
+use std::cmp::Ordering;
+
+use dbms_dtype::Scalar;
+
+use crate::{ColumnStatistics, Precision, Statistics};
+
+/// A comparison of a column against a literal value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterOp {
+    Eq(Scalar),
+    NotEq(Scalar),
+    Lt(Scalar),
+    LtEq(Scalar),
+    Gt(Scalar),
+    GtEq(Scalar),
+    IsNull,
+    IsNotNull,
+}
+
+/// A filter on one column. A list of filters matches the rows matching all
+/// of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnFilter {
+    pub column: String,
+    pub op: FilterOp,
+}
+
+impl ColumnFilter {
+    pub fn new(column: impl Into<String>, op: FilterOp) -> Self {
+        Self {
+            column: column.into(),
+            op,
+        }
+    }
+
+    /// Returns false only if no row described by the statistics can match.
+    ///
+    /// Estimated bounds are used as bounds, except estimated string maxima:
+    /// those may be truncated, and a truncated string sorts before the value
+    /// it was cut from. Comparisons between values of different types are
+    /// assumed to match.
+    pub fn may_match(&self, statistics: &ColumnStatistics, num_rows: &Precision<usize>) -> bool {
+        let nulls = statistics.null_count.get().copied();
+        let all_null = match (nulls, num_rows.get()) {
+            (Some(nulls), Some(&rows)) => nulls == rows,
+            _ => false,
+        };
+        let min = statistics.min_value.get();
+        let max = match &statistics.max_value {
+            Precision::Inexact(Scalar::Utf8(_)) => None,
+            max => max.get(),
+        };
+        // A null bound means the column has no non-null values
+        if let FilterOp::Eq(_)
+        | FilterOp::NotEq(_)
+        | FilterOp::Lt(_)
+        | FilterOp::LtEq(_)
+        | FilterOp::Gt(_)
+        | FilterOp::GtEq(_) = &self.op
+            && (all_null || min.is_some_and(is_null) || max.is_some_and(is_null))
+        {
+            return false;
+        }
+
+        let compare = |bound: Option<&Scalar>, value: &Scalar, matches: &[Ordering]| match bound {
+            Some(bound) if bound.dtype() == value.dtype() => bound
+                .partial_cmp(value)
+                .is_none_or(|ordering| matches.contains(&ordering)),
+            _ => true,
+        };
+        match &self.op {
+            FilterOp::Eq(value) => {
+                compare(min, value, &[Ordering::Less, Ordering::Equal])
+                    && compare(max, value, &[Ordering::Greater, Ordering::Equal])
+            }
+            FilterOp::NotEq(value) => !(min == Some(value) && max == Some(value)),
+            FilterOp::Lt(value) => compare(min, value, &[Ordering::Less]),
+            FilterOp::LtEq(value) => compare(min, value, &[Ordering::Less, Ordering::Equal]),
+            FilterOp::Gt(value) => compare(max, value, &[Ordering::Greater]),
+            FilterOp::GtEq(value) => compare(max, value, &[Ordering::Greater, Ordering::Equal]),
+            FilterOp::IsNull => nulls != Some(0),
+            FilterOp::IsNotNull => !all_null,
+        }
+    }
+}
+
+fn is_null(value: &Scalar) -> bool {
+    *value == Scalar::null(value.dtype())
+}
+
+/// Returns true if a set of rows with the given statistics may match every
+/// filter. Filters on columns without statistics always may match.
+pub(crate) fn may_match_all(
+    filters: &[ColumnFilter],
+    statistics: &Statistics,
+    column_index: impl Fn(&str) -> Option<usize>,
+) -> bool {
+    filters
+        .iter()
+        .all(|filter| match column_index(&filter.column) {
+            Some(i) => filter.may_match(&statistics.column_statistics[i], &statistics.num_rows),
+            None => true,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(min: i64, max: i64, nulls: usize) -> ColumnStatistics {
+        ColumnStatistics {
+            null_count: Precision::Exact(nulls),
+            min_value: Precision::Exact(Scalar::Int64(Some(min))),
+            max_value: Precision::Exact(Scalar::Int64(Some(max))),
+            distinct_count: Precision::Absent,
+        }
+    }
+
+    #[test]
+    fn test_may_match() {
+        let rows = Precision::Exact(10);
+        let v = |v| Scalar::Int64(Some(v));
+        let check =
+            |op, stats: &ColumnStatistics| ColumnFilter::new("a", op).may_match(stats, &rows);
+        let s = stats(3, 7, 0);
+
+        assert!(check(FilterOp::Eq(v(5)), &s));
+        assert!(!check(FilterOp::Eq(v(8)), &s));
+        assert!(!check(FilterOp::Lt(v(3)), &s));
+        assert!(check(FilterOp::LtEq(v(3)), &s));
+        assert!(!check(FilterOp::Gt(v(7)), &s));
+        assert!(check(FilterOp::GtEq(v(7)), &s));
+        assert!(!check(FilterOp::IsNull, &s));
+        assert!(check(FilterOp::IsNotNull, &s));
+        assert!(!check(FilterOp::NotEq(v(4)), &stats(4, 4, 0)));
+        assert!(check(FilterOp::Eq(Scalar::Utf8(Some("x".to_string()))), &s));
+
+        // Every value is null
+        let nulls = ColumnStatistics {
+            null_count: Precision::Exact(10),
+            min_value: Precision::Exact(Scalar::Int64(None)),
+            max_value: Precision::Exact(Scalar::Int64(None)),
+            distinct_count: Precision::Exact(0),
+        };
+        assert!(!check(FilterOp::Eq(v(1)), &nulls));
+        assert!(!check(FilterOp::IsNotNull, &nulls));
+        assert!(check(FilterOp::IsNull, &nulls));
+
+        // A truncated string maximum sorts before the values it stands for
+        let truncated = ColumnStatistics {
+            null_count: Precision::Exact(0),
+            min_value: Precision::Inexact(Scalar::Utf8(Some("abc".to_string()))),
+            max_value: Precision::Inexact(Scalar::Utf8(Some("abc".to_string()))),
+            distinct_count: Precision::Absent,
+        };
+        let s = |v: &str| Scalar::Utf8(Some(v.to_string()));
+        assert!(check(FilterOp::Gt(s("abcd")), &truncated));
+        assert!(check(FilterOp::NotEq(s("abc")), &truncated));
+        assert!(!check(FilterOp::Lt(s("abc")), &truncated));
+
+        // Nothing is known
+        assert!(check(FilterOp::Eq(v(1)), &ColumnStatistics::default()));
+    }
+}
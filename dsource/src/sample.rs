@@ -0,0 +1,134 @@
+//! Sampling scans, which return a random subset of the rows of a source.
+//!
+//! Whether a row or block is sampled is a hash of the seed and its position
+//! in the scan, so the same seed gives the same sample of unchanged data.
+
+use arrow::array::BooleanArray;
+
+use crate::generator::mix;
+use crate::{BatchIterator, filter_batch};
+
+/// How rows are chosen for a sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleMethod {
+    /// Each row is kept independently with the sample fraction as
+    /// probability. Every row is still read.
+    Bernoulli,
+    /// Whole blocks of rows, such as Parquet row groups or in-memory batches,
+    /// are kept or skipped. Skipped blocks are not read, but the sample is
+    /// only as random as the order of rows within blocks.
+    Block,
+}
+
+/// A description of a sample: its method, fraction of rows and seed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    method: SampleMethod,
+    fraction: f64,
+    seed: u64,
+}
+
+impl Sample {
+    /// Creates a sample keeping each row with probability `fraction`.
+    pub fn bernoulli(fraction: f64) -> Result<Self, String> {
+        Self::new(SampleMethod::Bernoulli, fraction)
+    }
+
+    /// Creates a sample keeping each block with probability `fraction`.
+    pub fn block(fraction: f64) -> Result<Self, String> {
+        Self::new(SampleMethod::Block, fraction)
+    }
+
+    fn new(method: SampleMethod, fraction: f64) -> Result<Self, String> {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(format!(
+                "sample fraction must be between 0 and 1: {}",
+                fraction
+            ));
+        }
+        Ok(Self {
+            method,
+            fraction,
+            seed: 0,
+        })
+    }
+
+    /// Sets the seed choosing the sample.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn method(&self) -> SampleMethod {
+        self.method
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns true if the row or block at `index` is in the sample.
+    pub(crate) fn keeps(&self, index: u64) -> bool {
+        if self.fraction >= 1.0 {
+            return true;
+        }
+        // The cast saturates, and a fraction of 0 keeps nothing
+        let threshold = (self.fraction * 2f64.powi(64)) as u64;
+        mix(self.seed ^ mix(index)) < threshold
+    }
+}
+
+/// Samples the batches of a scan, treating each batch as a block.
+pub(crate) fn sample_batches(batches: BatchIterator, sample: Sample) -> BatchIterator {
+    match sample.method {
+        SampleMethod::Block => Box::new(
+            batches
+                .enumerate()
+                .filter(move |(i, _)| sample.keeps(*i as u64))
+                .map(|(_, batch)| batch),
+        ),
+        SampleMethod::Bernoulli => {
+            let mut offset = 0;
+            Box::new(batches.filter_map(move |batch| {
+                let batch = match batch {
+                    Ok(batch) => batch,
+                    Err(e) => return Some(Err(e)),
+                };
+                let rows = batch.row_count() as u64;
+                let mask: BooleanArray = (offset..offset + rows)
+                    .map(|row| Some(sample.keeps(row)))
+                    .collect();
+                offset += rows;
+                match mask.true_count() {
+                    0 => None,
+                    n if n as u64 == rows => Some(Ok(batch)),
+                    _ => Some(filter_batch(&batch, &mask)),
+                }
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps() {
+        let sample = Sample::bernoulli(0.1).unwrap().with_seed(7);
+        let kept = (0..100_000).filter(|&i| sample.keeps(i)).count();
+        assert!((9_000..11_000).contains(&kept), "{} kept", kept);
+
+        assert!((0..1000).all(|i| Sample::block(1.0).unwrap().keeps(i)));
+        assert!(!(0..1000).any(|i| Sample::block(0.0).unwrap().keeps(i)));
+        assert_eq!(
+            Sample::bernoulli(1.5).unwrap_err(),
+            "sample fraction must be between 0 and 1: 1.5"
+        );
+        assert!(Sample::bernoulli(f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Data sinks that persist record batches to files.
+
+mod csv;
+mod ipc;
+mod json;
+mod parquet;
+mod partitioned;
+
+pub use self::csv::{CsvSink, CsvWriteOptions};
+pub use self::ipc::ArrowIpcSink;
+pub use self::json::JsonSink;
+pub use self::parquet::{ParquetCompression, ParquetSink, ParquetWriteOptions};
+pub use self::partitioned::{
+    DEFAULT_MAX_OPEN_FILES, DEFAULT_PARTITION_NAME, PartitionedSink, SinkFormat,
+};
+
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use arrow::record_batch::RecordBatch as ArrowRecordBatch;
+use dbms_dtype::{RecordBatch, Schema};
+
+/// An open destination that accepts record batches one at a time.
+pub trait BatchWriter {
+    /// Writes a batch, which must match the schema the writer was opened with.
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), String>;
+
+    /// Returns the number of bytes written so far, including buffered data.
+    fn bytes_written(&self) -> u64;
+
+    /// Flushes buffered data and finalizes the output.
+    fn finish(self: Box<Self>) -> Result<(), String>;
+}
+
+/// A destination that record batches can be written to.
+pub trait DataSink {
+    /// Creates the output and returns a writer for batches with the given schema.
+    fn open(&self, schema: &Schema) -> Result<Box<dyn BatchWriter>, String>;
+
+    /// Writes all batches to the sink, returning the number of rows written.
+    fn write(
+        &self,
+        schema: &Schema,
+        batches: Box<dyn Iterator<Item = Result<RecordBatch, String>>>,
+    ) -> Result<usize, String> {
+        let mut writer = self.open(schema)?;
+        let mut rows = 0;
+        for batch in batches {
+            let batch = batch?;
+            rows += batch.row_count();
+            writer.write(&batch)?;
+        }
+        writer.finish()?;
+        Ok(rows)
+    }
+}
+
+/// Converts a batch to Arrow after checking it matches the writer's schema.
+fn to_arrow(schema: &Schema, batch: &RecordBatch) -> Result<ArrowRecordBatch, String> {
+    if batch.schema() != schema {
+        return Err(format!(
+            "batch schema {:?} does not match sink schema {:?}",
+            batch.schema(),
+            schema
+        ));
+    }
+    ArrowRecordBatch::try_from(batch.clone())
+}
+
+/// A writer that counts the bytes passed through it.
+struct CountingWriter<W> {
+    inner: W,
+    count: Arc<AtomicU64>,
+}
+
+impl<W: Write> CountingWriter<W> {
+    /// Wraps `inner`, returning the writer and a handle to its byte count.
+    fn new(inner: W) -> (Self, Arc<AtomicU64>) {
+        let count = Arc::new(AtomicU64::new(0));
+        let writer = Self {
+            inner,
+            count: count.clone(),
+        };
+        (writer, count)
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbms_dtype::{Column, DataType, Field, Scalar};
+
+    pub(super) fn test_schema() -> Schema {
+        Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::Utf8),
+        ])
+    }
+
+    pub(super) fn test_batches() -> Vec<RecordBatch> {
+        (0..2)
+            .map(|i| {
+                RecordBatch::new(
+                    test_schema(),
+                    vec![
+                        Column::from_literal(Scalar::Int64(Some(i)), 2),
+                        Column::from_literal(Scalar::Utf8(Some(format!("n{}", i))), 2),
+                    ],
+                )
+            })
+            .collect()
+    }
+
+    pub(super) fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("dbms-sink-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_counting_writer() {
+        let (mut writer, count) = CountingWriter::new(Vec::new());
+        writer.write_all(b"hello").unwrap();
+
+        assert_eq!(count.load(Ordering::Relaxed), 5);
+        assert_eq!(writer.inner, b"hello");
+    }
+
+    #[test]
+    fn test_schema_mismatch() {
+        let other = Schema::new(vec![Field::new("x", DataType::Int64)]);
+        let err = to_arrow(&other, &test_batches()[0]).unwrap_err();
+
+        assert!(err.contains("does not match"), "{}", err);
+    }
+}
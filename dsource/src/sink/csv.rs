@@ -0,0 +1,161 @@
+//! CSV data sink implementation.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use arrow::csv::{Writer, WriterBuilder};
+use arrow::datatypes::Schema as ArrowSchema;
+use arrow::record_batch::RecordBatch as ArrowRecordBatch;
+use dbms_dtype::{RecordBatch, Schema};
+
+use super::{BatchWriter, CountingWriter, DataSink, to_arrow};
+
+/// Options controlling how CSV files are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvWriteOptions {
+    /// Field delimiter, `,` by default.
+    pub delimiter: u8,
+    /// Quote character, `"` by default.
+    pub quote: u8,
+    /// Whether to write a header row.
+    pub header: bool,
+    /// Text written for null values, empty by default.
+    pub null_value: String,
+}
+
+impl Default for CsvWriteOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            header: true,
+            null_value: String::new(),
+        }
+    }
+}
+
+impl CsvWriteOptions {
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_quote(mut self, quote: u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn with_null_value(mut self, null_value: impl Into<String>) -> Self {
+        self.null_value = null_value.into();
+        self
+    }
+}
+
+/// A data sink that writes a CSV file.
+pub struct CsvSink {
+    path: PathBuf,
+    options: CsvWriteOptions,
+}
+
+impl CsvSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            options: CsvWriteOptions::default(),
+        }
+    }
+
+    /// Sets the options used to write the file.
+    pub fn with_options(mut self, options: CsvWriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl DataSink for CsvSink {
+    fn open(&self, schema: &Schema) -> Result<Box<dyn BatchWriter>, String> {
+        let file = File::create(&self.path).map_err(|e| e.to_string())?;
+        let (output, bytes) = CountingWriter::new(BufWriter::new(file));
+        let mut writer = WriterBuilder::new()
+            .with_delimiter(self.options.delimiter)
+            .with_quote(self.options.quote)
+            .with_header(self.options.header)
+            .with_null(self.options.null_value.clone())
+            .build(output);
+
+        // Write the header up front so that empty outputs still have one
+        let arrow_schema: ArrowSchema = schema.clone().into();
+        writer
+            .write(&ArrowRecordBatch::new_empty(Arc::new(arrow_schema)))
+            .map_err(|e| e.to_string())?;
+
+        Ok(Box::new(CsvBatchWriter {
+            writer,
+            schema: schema.clone(),
+            bytes,
+        }))
+    }
+}
+
+struct CsvBatchWriter {
+    writer: Writer<CountingWriter<BufWriter<File>>>,
+    schema: Schema,
+    bytes: Arc<AtomicU64>,
+}
+
+impl BatchWriter for CsvBatchWriter {
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), String> {
+        let batch = to_arrow(&self.schema, batch)?;
+        self.writer.write(&batch).map_err(|e| e.to_string())
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        self.writer.into_inner().flush().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::{temp_path, test_batches, test_schema};
+
+    #[test]
+    fn test_write() {
+        let path = temp_path("write.csv");
+        let sink =
+            CsvSink::new(&path).with_options(CsvWriteOptions::default().with_delimiter(b';'));
+        let batches = test_batches().into_iter().map(Ok);
+        let rows = sink.write(&test_schema(), Box::new(batches)).unwrap();
+
+        assert_eq!(rows, 4);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text, "id;name\n0;n0\n0;n0\n1;n1\n1;n1\n");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_write_empty() {
+        let path = temp_path("empty.csv");
+        let rows = CsvSink::new(&path)
+            .write(&test_schema(), Box::new(std::iter::empty()))
+            .unwrap();
+
+        assert_eq!(rows, 0);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "id,name\n");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+//! Arrow IPC data sink implementation.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use arrow::datatypes::Schema as ArrowSchema;
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use dbms_dtype::{RecordBatch, Schema};
+
+use super::{BatchWriter, CountingWriter, DataSink, to_arrow};
+use crate::IpcFormat;
+
+type Output = CountingWriter<BufWriter<File>>;
+
+/// A data sink that writes an Arrow IPC file or stream.
+pub struct ArrowIpcSink {
+    path: PathBuf,
+    format: IpcFormat,
+}
+
+impl ArrowIpcSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            format: IpcFormat::File,
+        }
+    }
+
+    /// Sets the IPC format to write, the file format by default.
+    pub fn with_format(mut self, format: IpcFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl DataSink for ArrowIpcSink {
+    fn open(&self, schema: &Schema) -> Result<Box<dyn BatchWriter>, String> {
+        let file = File::create(&self.path).map_err(|e| e.to_string())?;
+        let (output, bytes) = CountingWriter::new(BufWriter::new(file));
+        let arrow_schema: ArrowSchema = schema.clone().into();
+        let writer = match self.format {
+            IpcFormat::File => IpcWriter::File(
+                FileWriter::try_new(output, &arrow_schema).map_err(|e| e.to_string())?,
+            ),
+            IpcFormat::Stream => IpcWriter::Stream(
+                StreamWriter::try_new(output, &arrow_schema).map_err(|e| e.to_string())?,
+            ),
+        };
+        Ok(Box::new(IpcBatchWriter {
+            writer,
+            schema: schema.clone(),
+            bytes,
+        }))
+    }
+}
+
+enum IpcWriter {
+    File(FileWriter<Output>),
+    Stream(StreamWriter<Output>),
+}
+
+struct IpcBatchWriter {
+    writer: IpcWriter,
+    schema: Schema,
+    bytes: Arc<AtomicU64>,
+}
+
+impl BatchWriter for IpcBatchWriter {
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), String> {
+        let batch = to_arrow(&self.schema, batch)?;
+        match &mut self.writer {
+            IpcWriter::File(writer) => writer.write(&batch),
+            IpcWriter::Stream(writer) => writer.write(&batch),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), String> {
+        match &mut self.writer {
+            IpcWriter::File(writer) => writer.finish(),
+            IpcWriter::Stream(writer) => writer.finish(),
+        }
+        .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::{temp_path, test_batches, test_schema};
+    use crate::{ArrowIpcDataSource, DataSource};
+
+    #[test]
+    fn test_round_trip() {
+        for format in [IpcFormat::File, IpcFormat::Stream] {
+            let path = temp_path(&format!("round-trip-{:?}.arrow", format));
+            let batches = test_batches().into_iter().map(Ok);
+            let rows = ArrowIpcSink::new(&path)
+                .with_format(format)
+                .write(&test_schema(), Box::new(batches))
+                .unwrap();
+            assert_eq!(rows, 4);
+
+            let source = ArrowIpcDataSource::new(&path);
+            assert_eq!(source.schema().unwrap(), test_schema());
+            let batches: Vec<_> = source.scan(None).unwrap().collect();
+            assert_eq!(batches.len(), 2);
+
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Newline-delimited JSON data sink implementation.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use arrow::json::LineDelimitedWriter;
+use dbms_dtype::{RecordBatch, Schema};
+
+use super::{BatchWriter, CountingWriter, DataSink, to_arrow};
+
+/// A data sink that writes one JSON object per line. Null values are omitted.
+pub struct JsonSink {
+    path: PathBuf,
+}
+
+impl JsonSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl DataSink for JsonSink {
+    fn open(&self, schema: &Schema) -> Result<Box<dyn BatchWriter>, String> {
+        let file = File::create(&self.path).map_err(|e| e.to_string())?;
+        let (output, bytes) = CountingWriter::new(BufWriter::new(file));
+        Ok(Box::new(JsonBatchWriter {
+            writer: LineDelimitedWriter::new(output),
+            schema: schema.clone(),
+            bytes,
+        }))
+    }
+}
+
+struct JsonBatchWriter {
+    writer: LineDelimitedWriter<CountingWriter<BufWriter<File>>>,
+    schema: Schema,
+    bytes: Arc<AtomicU64>,
+}
+
+impl BatchWriter for JsonBatchWriter {
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), String> {
+        let batch = to_arrow(&self.schema, batch)?;
+        self.writer.write(&batch).map_err(|e| e.to_string())
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), String> {
+        self.writer.finish().map_err(|e| e.to_string())?;
+        self.writer.into_inner().flush().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::{temp_path, test_batches, test_schema};
+    use crate::{DataSource, JsonDataSource};
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_path("round-trip.ndjson");
+        let batches = test_batches().into_iter().map(Ok);
+        let rows = JsonSink::new(&path)
+            .write(&test_schema(), Box::new(batches))
+            .unwrap();
+        assert_eq!(rows, 4);
+
+        let source = JsonDataSource::new(&path, None, 1024);
+        assert_eq!(source.schema().unwrap(), test_schema());
+        let batch = source.scan(None).unwrap().next().unwrap().unwrap();
+        assert_eq!(batch.row_count(), 4);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
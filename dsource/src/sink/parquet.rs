@@ -0,0 +1,231 @@
+//! Parquet data sink implementation.
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arrow::datatypes::Schema as ArrowSchema;
+use dbms_dtype::{RecordBatch, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::{DEFAULT_MAX_ROW_GROUP_SIZE, EnabledStatistics, WriterProperties};
+
+use super::{BatchWriter, DataSink, to_arrow};
+
+/// Compression codec applied to Parquet column chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParquetCompression {
+    Uncompressed,
+    #[default]
+    Snappy,
+    Gzip,
+    Lz4,
+    Zstd,
+}
+
+impl From<ParquetCompression> for Compression {
+    fn from(c: ParquetCompression) -> Self {
+        match c {
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+            ParquetCompression::Lz4 => Compression::LZ4_RAW,
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        }
+    }
+}
+
+/// Options controlling how Parquet files are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParquetWriteOptions {
+    /// Compression codec for column chunks.
+    pub compression: ParquetCompression,
+    /// Maximum number of rows per row group.
+    pub max_row_group_size: usize,
+    /// Whether to write min/max and null count statistics.
+    pub statistics: bool,
+    /// Whether to dictionary-encode columns.
+    pub dictionary: bool,
+}
+
+impl Default for ParquetWriteOptions {
+    fn default() -> Self {
+        Self {
+            compression: ParquetCompression::default(),
+            max_row_group_size: DEFAULT_MAX_ROW_GROUP_SIZE,
+            statistics: true,
+            dictionary: true,
+        }
+    }
+}
+
+impl ParquetWriteOptions {
+    pub fn with_compression(mut self, compression: ParquetCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_max_row_group_size(mut self, max_row_group_size: usize) -> Self {
+        self.max_row_group_size = max_row_group_size;
+        self
+    }
+
+    pub fn with_statistics(mut self, statistics: bool) -> Self {
+        self.statistics = statistics;
+        self
+    }
+
+    pub fn with_dictionary(mut self, dictionary: bool) -> Self {
+        self.dictionary = dictionary;
+        self
+    }
+
+    /// Builds the Parquet writer properties described by these options.
+    fn properties(&self) -> WriterProperties {
+        let statistics = if self.statistics {
+            EnabledStatistics::Page
+        } else {
+            EnabledStatistics::None
+        };
+        WriterProperties::builder()
+            .set_compression(self.compression.into())
+            .set_max_row_group_size(self.max_row_group_size)
+            .set_statistics_enabled(statistics)
+            .set_dictionary_enabled(self.dictionary)
+            .build()
+    }
+}
+
+/// A data sink that writes a Parquet file.
+pub struct ParquetSink {
+    path: PathBuf,
+    options: ParquetWriteOptions,
+}
+
+impl ParquetSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            options: ParquetWriteOptions::default(),
+        }
+    }
+
+    /// Sets the options used to write the file.
+    pub fn with_options(mut self, options: ParquetWriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl DataSink for ParquetSink {
+    fn open(&self, schema: &Schema) -> Result<Box<dyn BatchWriter>, String> {
+        let file = File::create(&self.path).map_err(|e| e.to_string())?;
+        let arrow_schema: ArrowSchema = schema.clone().into();
+        let writer = ArrowWriter::try_new(
+            file,
+            Arc::new(arrow_schema),
+            Some(self.options.properties()),
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(Box::new(ParquetBatchWriter {
+            writer,
+            schema: schema.clone(),
+        }))
+    }
+}
+
+struct ParquetBatchWriter {
+    writer: ArrowWriter<File>,
+    schema: Schema,
+}
+
+impl BatchWriter for ParquetBatchWriter {
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), String> {
+        let batch = to_arrow(&self.schema, batch)?;
+        self.writer.write(&batch).map_err(|e| e.to_string())
+    }
+
+    fn bytes_written(&self) -> u64 {
+        (self.writer.bytes_written() + self.writer.in_progress_size()) as u64
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        self.writer.close().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::{temp_path, test_batches, test_schema};
+    use crate::{DataSource, ParquetDataSource};
+    use dbms_dtype::Scalar;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_path("round-trip.parquet");
+        let options = ParquetWriteOptions::default()
+            .with_compression(ParquetCompression::Zstd)
+            .with_max_row_group_size(3);
+        let batches = test_batches().into_iter().map(Ok);
+        let rows = ParquetSink::new(&path)
+            .with_options(options)
+            .write(&test_schema(), Box::new(batches))
+            .unwrap();
+        assert_eq!(rows, 4);
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 2);
+        let column = metadata.row_group(0).column(0);
+        assert_eq!(
+            column.compression(),
+            Compression::ZSTD(ZstdLevel::default())
+        );
+        assert!(column.statistics().is_some());
+
+        let source = ParquetDataSource::new(&path, 1024);
+        assert_eq!(source.schema().unwrap(), test_schema());
+        let rows: Vec<(Scalar, Scalar)> = source
+            .scan(None)
+            .unwrap()
+            .flat_map(|batch| {
+                let batch = batch.unwrap();
+                (0..batch.row_count())
+                    .map(|i| (batch.field(0).get(i), batch.field(1).get(i)))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let row = |id: i64| {
+            (
+                Scalar::Int64(Some(id)),
+                Scalar::Utf8(Some(format!("n{}", id))),
+            )
+        };
+        assert_eq!(rows, vec![row(0), row(0), row(1), row(1)]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_without_statistics() {
+        let path = temp_path("no-stats.parquet");
+        let options = ParquetWriteOptions::default()
+            .with_statistics(false)
+            .with_dictionary(false);
+        let batches = test_batches().into_iter().map(Ok);
+        ParquetSink::new(&path)
+            .with_options(options)
+            .write(&test_schema(), Box::new(batches))
+            .unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let column = reader.metadata().row_group(0).column(0);
+        assert!(column.statistics().is_none());
+        assert!(column.dictionary_page_offset().is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
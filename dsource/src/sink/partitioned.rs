@@ -0,0 +1,601 @@
+//! Hive-style partitioned data sink implementation.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use arrow::array::UInt32Array;
+use arrow::compute::take;
+use dbms_dtype::{Column, RecordBatch, Scalar, Schema};
+
+use super::{
+    ArrowIpcSink, BatchWriter, CsvSink, CsvWriteOptions, DataSink, JsonSink, ParquetSink,
+    ParquetWriteOptions,
+};
+use crate::IpcFormat;
+
+/// Directory name used for null partition values, as in Hive.
+pub const DEFAULT_PARTITION_NAME: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Number of partition files a sink keeps open at once by default.
+pub const DEFAULT_MAX_OPEN_FILES: usize = 64;
+
+/// The file format written into each partition directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SinkFormat {
+    Csv(CsvWriteOptions),
+    Parquet(ParquetWriteOptions),
+    Json,
+    ArrowIpc(IpcFormat),
+}
+
+impl SinkFormat {
+    /// Returns the file extension for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv(_) => "csv",
+            Self::Parquet(_) => "parquet",
+            Self::Json => "ndjson",
+            Self::ArrowIpc(IpcFormat::File) => "arrow",
+            Self::ArrowIpc(IpcFormat::Stream) => "arrows",
+        }
+    }
+
+    /// Returns a sink writing this format to `path`.
+    pub fn sink(&self, path: impl Into<PathBuf>) -> Box<dyn DataSink> {
+        match self {
+            Self::Csv(options) => Box::new(CsvSink::new(path).with_options(options.clone())),
+            Self::Parquet(options) => {
+                Box::new(ParquetSink::new(path).with_options(options.clone()))
+            }
+            Self::Json => Box::new(JsonSink::new(path)),
+            Self::ArrowIpc(format) => Box::new(ArrowIpcSink::new(path).with_format(*format)),
+        }
+    }
+}
+
+/// A data sink that splits rows into `column=value/` directories below a root.
+///
+/// Partition columns are encoded in the directory names and omitted from the
+/// files. Each partition has at most one file open at a time, and only a
+/// limited number of files are open at once: writing to a partition without
+/// an open file closes the least recently written one if needed, and rows
+/// of that partition written later go into a new file. Input sorted by the
+/// partition columns therefore gives one file per partition.
+pub struct PartitionedSink {
+    root: PathBuf,
+    partition_columns: Vec<String>,
+    format: SinkFormat,
+    max_file_size: Option<u64>,
+    max_open_files: usize,
+}
+
+impl PartitionedSink {
+    /// Creates a sink partitioned by the given columns, which must be
+    /// distinct.
+    pub fn new(
+        root: impl Into<PathBuf>,
+        partition_columns: Vec<String>,
+        format: SinkFormat,
+    ) -> Result<Self, String> {
+        for (i, name) in partition_columns.iter().enumerate() {
+            if partition_columns[..i].contains(name) {
+                return Err(format!("duplicate partition column: {}", name));
+            }
+        }
+        Ok(Self {
+            root: root.into(),
+            partition_columns,
+            format,
+            max_file_size: None,
+            max_open_files: DEFAULT_MAX_OPEN_FILES,
+        })
+    }
+
+    /// Closes the file of a partition once it reaches `bytes`, so that the
+    /// next rows of the partition start a new file. Batches are not split,
+    /// so a file can exceed the limit by the size of its last batch.
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Sets how many partition files are kept open at once.
+    pub fn with_max_open_files(mut self, max_open_files: usize) -> Self {
+        self.max_open_files = max_open_files.max(1);
+        self
+    }
+}
+
+impl DataSink for PartitionedSink {
+    fn open(&self, schema: &Schema) -> Result<Box<dyn BatchWriter>, String> {
+        let partition_indices = self
+            .partition_columns
+            .iter()
+            .map(|name| {
+                schema
+                    .fields()
+                    .iter()
+                    .position(|f| f.name() == name)
+                    .ok_or_else(|| format!("partition column not found: {}", name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let data_indices: Vec<usize> = (0..schema.fields().len())
+            .filter(|i| !partition_indices.contains(i))
+            .collect();
+        if data_indices.is_empty() {
+            return Err("at least one column must not be a partition column".to_string());
+        }
+
+        fs::create_dir_all(&self.root).map_err(|e| e.to_string())?;
+        Ok(Box::new(PartitionedWriter {
+            root: self.root.clone(),
+            format: self.format.clone(),
+            max_file_size: self.max_file_size,
+            max_open_files: self.max_open_files,
+            schema: schema.clone(),
+            file_schema: schema.project(&data_indices),
+            partition_indices,
+            data_indices,
+            partitions: HashMap::new(),
+            open_files: 0,
+            writes: 0,
+            finished_bytes: 0,
+        }))
+    }
+}
+
+/// One partition directory and its open file, if any.
+struct Partition {
+    dir: PathBuf,
+    writer: Option<Box<dyn BatchWriter>>,
+    file_count: usize,
+    /// Number of the last write to the partition, to find the least recently
+    /// written one.
+    last_write: u64,
+}
+
+struct PartitionedWriter {
+    root: PathBuf,
+    format: SinkFormat,
+    max_file_size: Option<u64>,
+    max_open_files: usize,
+    schema: Schema,
+    file_schema: Schema,
+    partition_indices: Vec<usize>,
+    data_indices: Vec<usize>,
+    partitions: HashMap<String, Partition>,
+    open_files: usize,
+    writes: u64,
+    finished_bytes: u64,
+}
+
+impl PartitionedWriter {
+    /// Returns the relative directory of the partition that row `i` belongs to.
+    fn partition_dir(&self, batch: &RecordBatch, i: usize) -> String {
+        self.partition_indices
+            .iter()
+            .map(|&c| {
+                let name = self.schema.fields()[c].name();
+                let value = partition_value(&batch.field(c).get(i));
+                format!("{}={}", escape(name), value)
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Opens the next file of the partition stored in `dir`.
+    fn open_file(&self, dir: &Path, file_count: usize) -> Result<Box<dyn BatchWriter>, String> {
+        let name = format!("part-{:05}.{}", file_count, self.format.extension());
+        self.format.sink(dir.join(name)).open(&self.file_schema)
+    }
+
+    /// Writes rows already routed to the partition in `key`, opening a file
+    /// first if it has none and closing the file if it is then full.
+    fn write_partition(&mut self, key: String, batch: RecordBatch) -> Result<(), String> {
+        if !self.partitions.contains_key(&key) {
+            let dir = self.root.join(&key);
+            fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            let partition = Partition {
+                dir,
+                writer: None,
+                file_count: 0,
+                last_write: 0,
+            };
+            self.partitions.insert(key.clone(), partition);
+        }
+        if self.partitions[&key].writer.is_none() {
+            if self.open_files >= self.max_open_files {
+                self.close_least_recent()?;
+            }
+            let partition = &self.partitions[&key];
+            let writer = self.open_file(&partition.dir, partition.file_count)?;
+            let partition = self.partitions.get_mut(&key).unwrap();
+            partition.writer = Some(writer);
+            partition.file_count += 1;
+            self.open_files += 1;
+        }
+
+        self.writes += 1;
+        let partition = self.partitions.get_mut(&key).unwrap();
+        partition.last_write = self.writes;
+        let writer = partition.writer.as_mut().unwrap();
+        writer.write(&batch)?;
+        if self
+            .max_file_size
+            .is_some_and(|max| writer.bytes_written() >= max)
+        {
+            self.close(&key)?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the open file of the partition in `key`.
+    fn close(&mut self, key: &str) -> Result<(), String> {
+        if let Some(writer) = self.partitions.get_mut(key).and_then(|p| p.writer.take()) {
+            self.open_files -= 1;
+            self.finished_bytes += writer.bytes_written();
+            writer.finish()?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the open file of the least recently written partition.
+    fn close_least_recent(&mut self) -> Result<(), String> {
+        let key = self
+            .partitions
+            .iter()
+            .filter(|(_, p)| p.writer.is_some())
+            .min_by_key(|(_, p)| p.last_write)
+            .map(|(key, _)| key.clone());
+        match key {
+            Some(key) => self.close(&key),
+            None => Ok(()),
+        }
+    }
+}
+
+impl BatchWriter for PartitionedWriter {
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), String> {
+        if batch.schema() != &self.schema {
+            return Err(format!(
+                "batch schema {:?} does not match sink schema {:?}",
+                batch.schema(),
+                self.schema
+            ));
+        }
+
+        // Group row indices by partition, preserving first-seen order
+        let mut order: Vec<String> = Vec::new();
+        let mut rows: HashMap<String, Vec<u32>> = HashMap::new();
+        for i in 0..batch.row_count() {
+            let key = self.partition_dir(batch, i);
+            rows.entry(key.clone())
+                .or_insert_with(|| {
+                    order.push(key);
+                    Vec::new()
+                })
+                .push(i as u32);
+        }
+
+        let arrays: Vec<_> = self
+            .data_indices
+            .iter()
+            .map(|&c| batch.field(c).to_array())
+            .collect();
+        for key in order {
+            let indices = UInt32Array::from(rows.remove(&key).unwrap());
+            let columns = arrays
+                .iter()
+                .map(|array| {
+                    let taken = take(array.as_ref(), &indices, None).map_err(|e| e.to_string())?;
+                    Ok(Column::Array(taken))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            self.write_partition(key, RecordBatch::new(self.file_schema.clone(), columns))?;
+        }
+        Ok(())
+    }
+
+    fn bytes_written(&self) -> u64 {
+        let open: u64 = self
+            .partitions
+            .values()
+            .filter_map(|p| p.writer.as_ref())
+            .map(|writer| writer.bytes_written())
+            .sum();
+        self.finished_bytes + open
+    }
+
+    /// Every open file is finished, even after one fails, and the first
+    /// error is returned.
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        let mut result = Ok(());
+        for writer in self.partitions.into_values().filter_map(|p| p.writer) {
+            let finished = writer.finish();
+            if result.is_ok() {
+                result = finished;
+            }
+        }
+        result
+    }
+}
+
+/// Formats a partition value for use in a directory name.
+fn partition_value(value: &Scalar) -> String {
+    let text = match value {
+        Scalar::Boolean(Some(v)) => v.to_string(),
+        Scalar::Int8(Some(v)) => v.to_string(),
+        Scalar::Int16(Some(v)) => v.to_string(),
+        Scalar::Int32(Some(v)) => v.to_string(),
+        Scalar::Int64(Some(v)) => v.to_string(),
+        Scalar::UInt8(Some(v)) => v.to_string(),
+        Scalar::UInt16(Some(v)) => v.to_string(),
+        Scalar::UInt32(Some(v)) => v.to_string(),
+        Scalar::UInt64(Some(v)) => v.to_string(),
+        Scalar::Float32(Some(v)) => v.to_string(),
+        Scalar::Float64(Some(v)) => v.to_string(),
+        Scalar::Utf8(Some(v)) => v.clone(),
+        Scalar::Binary(Some(v)) => v.iter().map(|b| format!("{:02x}", b)).collect(),
+        _ => return DEFAULT_PARTITION_NAME.to_string(),
+    };
+    escape(&text)
+}
+
+/// Percent-encodes characters that are unsafe in Hive partition directory names.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_control() || "\"#%'*/:=?\\{[]^".contains(c) {
+            let mut buf = [0u8; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::temp_path;
+    use crate::{CsvDataSource, DataSource, ParquetDataSource};
+    use dbms_dtype::{DataType, Field};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use arrow::array::{Int64Array, StringArray};
+
+    fn test_schema() -> Schema {
+        Schema::new(vec![
+            Field::new("region", DataType::Utf8),
+            Field::new("id", DataType::Int64),
+            Field::new("date", DataType::Utf8),
+        ])
+    }
+
+    fn test_batch() -> RecordBatch {
+        let region = StringArray::from(vec![Some("eu"), Some("us"), Some("eu"), None]);
+        let id = Int64Array::from(vec![1, 2, 3, 4]);
+        let date = StringArray::from(vec!["2024-01-01", "2024-01-01", "2024-01-02", "2024-01-01"]);
+        RecordBatch::new(
+            test_schema(),
+            vec![
+                Column::Array(Arc::new(region)),
+                Column::Array(Arc::new(id)),
+                Column::Array(Arc::new(date)),
+            ],
+        )
+    }
+
+    fn files_under(dir: &Path) -> Vec<String> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(files_under(&path));
+            } else {
+                files.push(path.to_string_lossy().into_owned());
+            }
+        }
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_partitioned_write() {
+        let root = temp_path("partitioned-csv");
+        let sink = PartitionedSink::new(
+            &root,
+            vec!["region".to_string(), "date".to_string()],
+            SinkFormat::Csv(CsvWriteOptions::default()),
+        )
+        .unwrap();
+        let rows = sink
+            .write(&test_schema(), Box::new(std::iter::once(Ok(test_batch()))))
+            .unwrap();
+        assert_eq!(rows, 4);
+
+        let prefix = format!("{}/", root.display());
+        let files: Vec<_> = files_under(&root)
+            .into_iter()
+            .map(|f| f.trim_start_matches(&prefix).to_string())
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                "region=__HIVE_DEFAULT_PARTITION__/date=2024-01-01/part-00000.csv",
+                "region=eu/date=2024-01-01/part-00000.csv",
+                "region=eu/date=2024-01-02/part-00000.csv",
+                "region=us/date=2024-01-01/part-00000.csv",
+            ]
+        );
+
+        let source = CsvDataSource::new(
+            root.join("region=eu/date=2024-01-02/part-00000.csv"),
+            None,
+            1024,
+        );
+        let schema = source.schema().unwrap();
+        assert_eq!(schema.fields().len(), 1);
+        assert_eq!(schema.fields()[0].name(), "id");
+        let batch = source.scan(None).unwrap().next().unwrap().unwrap();
+        assert_eq!(batch.field(0).get(0), Scalar::Int64(Some(3)));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_rolling_files() {
+        let root = temp_path("partitioned-parquet");
+        let sink = PartitionedSink::new(
+            &root,
+            vec!["region".to_string()],
+            SinkFormat::Parquet(ParquetWriteOptions::default()),
+        )
+        .unwrap()
+        .with_max_file_size(1);
+        let batches = (0..3).map(|_| Ok(test_batch()));
+        sink.write(&test_schema(), Box::new(batches)).unwrap();
+
+        let files = files_under(&root.join("region=us"));
+        assert_eq!(files.len(), 3);
+        let rows: usize = files
+            .iter()
+            .map(|f| {
+                ParquetDataSource::new(f, 1024)
+                    .scan(None)
+                    .unwrap()
+                    .map(|b| b.unwrap().row_count())
+                    .sum::<usize>()
+            })
+            .sum();
+        assert_eq!(rows, 3);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_max_open_files() {
+        let root = temp_path("partitioned-open-files");
+        let sink = |max_open_files| {
+            PartitionedSink::new(&root, vec!["region".to_string()], SinkFormat::Json)
+                .unwrap()
+                .with_max_open_files(max_open_files)
+        };
+
+        // Each batch touches three partitions, so one open file is closed
+        // and reopened as a new file for every partition written
+        let batches = (0..2).map(|_| Ok(test_batch()));
+        assert_eq!(sink(1).write(&test_schema(), Box::new(batches)).unwrap(), 8);
+        assert_eq!(files_under(&root).len(), 6);
+        let rows: usize = files_under(&root.join("region=eu"))
+            .iter()
+            .map(|f| fs::read_to_string(f).unwrap().lines().count())
+            .sum();
+        assert_eq!(rows, 4);
+        fs::remove_dir_all(&root).unwrap();
+
+        let batches = (0..2).map(|_| Ok(test_batch()));
+        sink(3).write(&test_schema(), Box::new(batches)).unwrap();
+        assert_eq!(files_under(&root).len(), 3);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_partition_column() {
+        let sink = PartitionedSink::new(
+            temp_path("unknown"),
+            vec!["x".to_string()],
+            SinkFormat::Json,
+        )
+        .unwrap();
+        assert!(sink.open(&test_schema()).is_err());
+
+        let duplicate = PartitionedSink::new(
+            temp_path("duplicate"),
+            vec![
+                "region".to_string(),
+                "date".to_string(),
+                "region".to_string(),
+            ],
+            SinkFormat::Json,
+        );
+        assert_eq!(
+            duplicate.err().unwrap(),
+            "duplicate partition column: region"
+        );
+    }
+
+    /// Counts the writers finished, failing to finish if `fail` is set.
+    struct FinishCounter {
+        finished: Arc<AtomicUsize>,
+        fail: bool,
+    }
+
+    impl BatchWriter for FinishCounter {
+        fn write(&mut self, _batch: &RecordBatch) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn bytes_written(&self) -> u64 {
+            0
+        }
+
+        fn finish(self: Box<Self>) -> Result<(), String> {
+            self.finished.fetch_add(1, Ordering::SeqCst);
+            match self.fail {
+                true => Err("disk full".to_string()),
+                false => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_finish_after_error() {
+        let finished = Arc::new(AtomicUsize::new(0));
+        let partitions = (0..3)
+            .map(|i| {
+                let writer = FinishCounter {
+                    finished: finished.clone(),
+                    fail: i != 1,
+                };
+                let partition = Partition {
+                    dir: PathBuf::new(),
+                    writer: Some(Box::new(writer) as Box<dyn BatchWriter>),
+                    file_count: 1,
+                    last_write: i,
+                };
+                (i.to_string(), partition)
+            })
+            .collect();
+        let writer = Box::new(PartitionedWriter {
+            root: PathBuf::new(),
+            format: SinkFormat::Json,
+            max_file_size: None,
+            max_open_files: DEFAULT_MAX_OPEN_FILES,
+            schema: test_schema(),
+            file_schema: test_schema(),
+            partition_indices: vec![],
+            data_indices: vec![],
+            partitions,
+            open_files: 3,
+            writes: 3,
+            finished_bytes: 0,
+        });
+
+        // Every file is finished, and the first error is returned
+        assert_eq!(writer.finish().unwrap_err(), "disk full");
+        assert_eq!(finished.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a/b=c"), "a%2Fb%3Dc");
+        assert_eq!(escape("plain text"), "plain text");
+    }
+}
@@ -0,0 +1,297 @@
+//! Statistics describing the contents of a data source.
+
+use std::collections::HashSet;
+
+use arrow::array::{Array, ArrayRef, AsArray};
+use arrow::compute::{
+    max, max_binary, max_boolean, max_string, min, min_binary, min_boolean, min_string,
+};
+use arrow::datatypes::{
+    Float32Type, Float64Type, Int8Type, Int16Type, Int32Type, Int64Type, UInt8Type, UInt16Type,
+    UInt32Type, UInt64Type,
+};
+use arrow::row::{RowConverter, SortField};
+use dbms_dtype::{DataType, Scalar, Schema};
+
+/// A statistic that is known exactly, estimated, or unknown.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Precision<T> {
+    /// The value is exact.
+    Exact(T),
+    /// The value is an estimate.
+    Inexact(T),
+    /// Nothing is known about the value.
+    #[default]
+    Absent,
+}
+
+impl<T> Precision<T> {
+    /// Returns the value, whether exact or estimated.
+    pub fn get(&self) -> Option<&T> {
+        match self {
+            Self::Exact(v) | Self::Inexact(v) => Some(v),
+            Self::Absent => None,
+        }
+    }
+
+    /// Returns true if the value is known exactly.
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Self::Exact(_))
+    }
+
+    /// Demotes an exact value to an estimate.
+    pub fn to_inexact(self) -> Self {
+        match self {
+            Self::Exact(v) => Self::Inexact(v),
+            other => other,
+        }
+    }
+
+    /// Combines the statistics of two disjoint sets of rows with `f`; the
+    /// result is exact only if both are.
+    pub fn combine(self, other: Self, f: impl FnOnce(T, T) -> T) -> Self {
+        match (self, other) {
+            (Self::Exact(a), Self::Exact(b)) => Self::Exact(f(a, b)),
+            (Self::Exact(a) | Self::Inexact(a), Self::Exact(b) | Self::Inexact(b)) => {
+                Self::Inexact(f(a, b))
+            }
+            _ => Self::Absent,
+        }
+    }
+
+    /// Applies `f` to the value, keeping its precision.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Precision<U> {
+        match self {
+            Self::Exact(v) => Precision::Exact(f(v)),
+            Self::Inexact(v) => Precision::Inexact(f(v)),
+            Self::Absent => Precision::Absent,
+        }
+    }
+}
+
+/// Statistics about a single column.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColumnStatistics {
+    /// Number of null values.
+    pub null_count: Precision<usize>,
+    /// Smallest non-null value, or a null scalar if there are none.
+    pub min_value: Precision<Scalar>,
+    /// Largest non-null value, or a null scalar if there are none.
+    pub max_value: Precision<Scalar>,
+    /// Number of distinct non-null values.
+    pub distinct_count: Precision<usize>,
+}
+
+/// Statistics about the contents of a data source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    /// Number of rows.
+    pub num_rows: Precision<usize>,
+    /// Size of the data in bytes, as stored by the source.
+    pub total_byte_size: Precision<usize>,
+    /// Statistics for each column, in schema order.
+    pub column_statistics: Vec<ColumnStatistics>,
+}
+
+impl Statistics {
+    /// Returns statistics with nothing known about a source with the given schema.
+    pub fn unknown(schema: &Schema) -> Self {
+        Self {
+            num_rows: Precision::Absent,
+            total_byte_size: Precision::Absent,
+            column_statistics: vec![ColumnStatistics::default(); schema.fields().len()],
+        }
+    }
+
+    /// Combines the statistics of two disjoint sets of rows with the same
+    /// schema. Distinct counts cannot be combined and become unknown.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            num_rows: self.num_rows.combine(other.num_rows, |a, b| a + b),
+            total_byte_size: self
+                .total_byte_size
+                .combine(other.total_byte_size, |a, b| a + b),
+            column_statistics: self
+                .column_statistics
+                .into_iter()
+                .zip(other.column_statistics)
+                .map(|(a, b)| ColumnStatistics {
+                    null_count: a.null_count.combine(b.null_count, |a, b| a + b),
+                    min_value: a.min_value.combine(b.min_value, merge_min),
+                    max_value: a.max_value.combine(b.max_value, merge_max),
+                    distinct_count: Precision::Absent,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the statistics of the given columns, in the given order.
+    pub fn project(&self, indices: &[usize]) -> Self {
+        Self {
+            num_rows: self.num_rows.clone(),
+            total_byte_size: self.total_byte_size.clone(),
+            column_statistics: indices
+                .iter()
+                .map(|&i| self.column_statistics[i].clone())
+                .collect(),
+        }
+    }
+}
+
+/// Returns the smallest and largest non-null values of an array, which are
+/// null scalars if the array has no non-null values.
+pub(crate) fn min_max(array: &ArrayRef) -> Result<(Scalar, Scalar), String> {
+    macro_rules! primitive {
+        ($variant:ident, $type:ty) => {{
+            let a = array.as_primitive::<$type>();
+            (Scalar::$variant(min(a)), Scalar::$variant(max(a)))
+        }};
+    }
+
+    let dtype = DataType::try_from(array.data_type().clone())?;
+    Ok(match dtype {
+        DataType::Boolean => {
+            let a = array.as_boolean();
+            (
+                Scalar::Boolean(min_boolean(a)),
+                Scalar::Boolean(max_boolean(a)),
+            )
+        }
+        DataType::Int8 => primitive!(Int8, Int8Type),
+        DataType::Int16 => primitive!(Int16, Int16Type),
+        DataType::Int32 => primitive!(Int32, Int32Type),
+        DataType::Int64 => primitive!(Int64, Int64Type),
+        DataType::UInt8 => primitive!(UInt8, UInt8Type),
+        DataType::UInt16 => primitive!(UInt16, UInt16Type),
+        DataType::UInt32 => primitive!(UInt32, UInt32Type),
+        DataType::UInt64 => primitive!(UInt64, UInt64Type),
+        DataType::Float32 => primitive!(Float32, Float32Type),
+        DataType::Float64 => primitive!(Float64, Float64Type),
+        DataType::Utf8 => {
+            let a = array.as_string::<i32>();
+            (
+                Scalar::Utf8(min_string(a).map(String::from)),
+                Scalar::Utf8(max_string(a).map(String::from)),
+            )
+        }
+        DataType::Binary => {
+            let a = array.as_binary::<i32>();
+            (
+                Scalar::Binary(min_binary(a).map(<[u8]>::to_vec)),
+                Scalar::Binary(max_binary(a).map(<[u8]>::to_vec)),
+            )
+        }
+    })
+}
+
+/// Returns the smaller of two optional minimums, ignoring nulls.
+pub(crate) fn merge_min(a: Scalar, b: Scalar) -> Scalar {
+    merge(a, b, std::cmp::Ordering::Less)
+}
+
+/// Returns the larger of two optional maximums, ignoring nulls.
+pub(crate) fn merge_max(a: Scalar, b: Scalar) -> Scalar {
+    merge(a, b, std::cmp::Ordering::Greater)
+}
+
+fn merge(a: Scalar, b: Scalar, keep: std::cmp::Ordering) -> Scalar {
+    let null = Scalar::null(a.dtype());
+    if a == null {
+        b
+    } else if b == null || a.partial_cmp(&b) == Some(keep) {
+        a
+    } else {
+        b
+    }
+}
+
+/// Counts the distinct non-null values across arrays of the same type.
+pub(crate) fn distinct_count(arrays: &[ArrayRef]) -> Result<usize, String> {
+    let Some(first) = arrays.first() else {
+        return Ok(0);
+    };
+    let converter = RowConverter::new(vec![SortField::new(first.data_type().clone())])
+        .map_err(|e| e.to_string())?;
+
+    let mut seen = HashSet::new();
+    for array in arrays {
+        let rows = converter
+            .convert_columns(std::slice::from_ref(array))
+            .map_err(|e| e.to_string())?;
+        for (i, row) in rows.iter().enumerate() {
+            if array.is_valid(i) {
+                seen.insert(row.as_ref().to_vec());
+            }
+        }
+    }
+    Ok(seen.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int64Array, StringArray};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_min_max() {
+        let array: ArrayRef = Arc::new(StringArray::from(vec![Some("b"), None, Some("a")]));
+        let (lo, hi) = min_max(&array).unwrap();
+
+        assert_eq!(lo, Scalar::Utf8(Some("a".to_string())));
+        assert_eq!(hi, Scalar::Utf8(Some("b".to_string())));
+    }
+
+    #[test]
+    fn test_merge() {
+        let a = Scalar::Int64(Some(3));
+        let b = Scalar::Int64(Some(5));
+        let null = Scalar::Int64(None);
+
+        assert_eq!(merge_min(a.clone(), b.clone()), a);
+        assert_eq!(merge_max(a.clone(), b.clone()), b);
+        assert_eq!(merge_min(null.clone(), b.clone()), b);
+        assert_eq!(merge_max(a.clone(), null), a);
+    }
+
+    #[test]
+    fn test_distinct_count() {
+        let a: ArrayRef = Arc::new(Int64Array::from(vec![Some(1), Some(2), None]));
+        let b: ArrayRef = Arc::new(Int64Array::from(vec![Some(2), Some(3)]));
+
+        assert_eq!(distinct_count(&[a, b]).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_precision() {
+        let p = Precision::Exact(10usize);
+
+        assert_eq!(p.get(), Some(&10));
+        assert!(p.is_exact());
+        assert_eq!(p.clone().to_inexact(), Precision::Inexact(10));
+        assert_eq!(p.map(|v| v * 2), Precision::Exact(20));
+        assert_eq!(Precision::<usize>::Absent.get(), None);
+    }
+
+    #[test]
+    fn test_merge_statistics() {
+        let stats = |rows, min: i64, max: i64| Statistics {
+            num_rows: Precision::Exact(rows),
+            total_byte_size: Precision::Inexact(rows * 8),
+            column_statistics: vec![ColumnStatistics {
+                null_count: Precision::Exact(0),
+                min_value: Precision::Exact(Scalar::Int64(Some(min))),
+                max_value: Precision::Exact(Scalar::Int64(Some(max))),
+                distinct_count: Precision::Exact(rows),
+            }],
+        };
+        let merged = stats(2, 5, 9).merge(stats(3, 1, 4));
+
+        assert_eq!(merged.num_rows, Precision::Exact(5));
+        assert_eq!(merged.total_byte_size, Precision::Inexact(40));
+        let column = &merged.column_statistics[0];
+        assert_eq!(column.min_value, Precision::Exact(Scalar::Int64(Some(1))));
+        assert_eq!(column.max_value, Precision::Exact(Scalar::Int64(Some(9))));
+        assert_eq!(column.distinct_count, Precision::Absent);
+    }
+}
@@ -0,0 +1,79 @@
+//! Asynchronous scans producing streams of record batches.
+
+use std::pin::Pin;
+
+use dbms_dtype::RecordBatch;
+use futures::Stream;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+
+use crate::BatchIterator;
+
+/// A stream of the record batches produced by an asynchronous scan.
+pub type BatchStream = Pin<Box<dyn Stream<Item = Result<RecordBatch, String>> + Send>>;
+
+/// Number of batches a blocking scan may read ahead of its consumer.
+const BLOCKING_BUFFER: usize = 2;
+
+/// Returns the current Tokio runtime, which asynchronous scans read through.
+pub(crate) fn current_runtime() -> Result<Handle, String> {
+    Handle::try_current()
+        .map_err(|_| "asynchronous scans must be started within a Tokio runtime".to_string())
+}
+
+/// Runs a synchronous scan on Tokio's blocking thread pool. The scan pauses
+/// once it is `BLOCKING_BUFFER` batches ahead, and stops when the stream is
+/// dropped.
+///
+/// Fails if called outside a Tokio runtime.
+pub(crate) fn blocking_stream(batches: BatchIterator) -> Result<BatchStream, String> {
+    let runtime = current_runtime()?;
+    let (tx, rx) = mpsc::channel(BLOCKING_BUFFER);
+    runtime.spawn_blocking(move || {
+        for batch in batches {
+            if tx.blocking_send(batch).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(Box::pin(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|batch| (batch, rx))
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbms_dtype::Schema;
+    use futures::StreamExt;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_backpressure() {
+        // Count the batches the blocking scan has produced
+        let produced = Arc::new(AtomicUsize::new(0));
+        let counter = produced.clone();
+        let batches = (0..100).map(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(RecordBatch::new(Schema::new(vec![]), vec![]))
+        });
+
+        let mut stream = blocking_stream(Box::new(batches)).unwrap();
+        stream.next().await.unwrap().unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        // One batch was consumed, a few more are buffered, the rest wait
+        assert!(produced.load(Ordering::SeqCst) <= BLOCKING_BUFFER + 2);
+        assert_eq!(stream.count().await, 99);
+    }
+
+    #[test]
+    fn test_outside_runtime() {
+        let batches = std::iter::empty();
+        assert_eq!(
+            blocking_stream(Box::new(batches)).err().unwrap(),
+            "asynchronous scans must be started within a Tokio runtime"
+        );
+    }
+}
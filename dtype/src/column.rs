@@ -1,15 +1,22 @@
 //! Column types for the DBMS query engine.
 
+use std::cmp::Ordering;
+use std::iter::repeat_n;
+use std::sync::Arc;
+
 use crate::DataType;
 use arrow::array::{
-    ArrayRef, AsArray,
+    ArrayRef, AsArray, BinaryArray, BooleanArray, PrimitiveArray, StringArray, new_null_array,
     types::{
-        Float32Type, Float64Type, Int8Type, Int16Type, Int32Type, Int64Type, UInt8Type, UInt16Type,
-        UInt32Type, UInt64Type,
+        ArrowPrimitiveType, Float32Type, Float64Type, Int8Type, Int16Type, Int32Type, Int64Type,
+        UInt8Type, UInt16Type, UInt32Type, UInt64Type,
     },
 };
 
 /// A scalar value that can be broadcast across rows.
+///
+/// Scalars of the same type are ordered by value, with null first. Scalars
+/// of different types are not ordered.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Scalar {
     Boolean(Option<bool>),
@@ -27,6 +34,27 @@ pub enum Scalar {
     Binary(Option<Vec<u8>>),
 }
 
+impl PartialOrd for Scalar {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Boolean(a), Self::Boolean(b)) => a.partial_cmp(b),
+            (Self::Int8(a), Self::Int8(b)) => a.partial_cmp(b),
+            (Self::Int16(a), Self::Int16(b)) => a.partial_cmp(b),
+            (Self::Int32(a), Self::Int32(b)) => a.partial_cmp(b),
+            (Self::Int64(a), Self::Int64(b)) => a.partial_cmp(b),
+            (Self::UInt8(a), Self::UInt8(b)) => a.partial_cmp(b),
+            (Self::UInt16(a), Self::UInt16(b)) => a.partial_cmp(b),
+            (Self::UInt32(a), Self::UInt32(b)) => a.partial_cmp(b),
+            (Self::UInt64(a), Self::UInt64(b)) => a.partial_cmp(b),
+            (Self::Float32(a), Self::Float32(b)) => a.partial_cmp(b),
+            (Self::Float64(a), Self::Float64(b)) => a.partial_cmp(b),
+            (Self::Utf8(a), Self::Utf8(b)) => a.partial_cmp(b),
+            (Self::Binary(a), Self::Binary(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
 impl Scalar {
     /// Creates a null Scalar of the given data type.
     pub fn null(dtype: DataType) -> Self {
@@ -47,6 +75,30 @@ impl Scalar {
         }
     }
 
+    /// Returns an Arrow array holding this value repeated `len` times.
+    pub fn to_array(&self, len: usize) -> ArrayRef {
+        fn primitive<T: ArrowPrimitiveType>(v: T::Native, len: usize) -> ArrayRef {
+            Arc::new(PrimitiveArray::<T>::from_value(v, len))
+        }
+
+        match self {
+            Self::Boolean(Some(v)) => Arc::new(BooleanArray::from(vec![*v; len])),
+            Self::Int8(Some(v)) => primitive::<Int8Type>(*v, len),
+            Self::Int16(Some(v)) => primitive::<Int16Type>(*v, len),
+            Self::Int32(Some(v)) => primitive::<Int32Type>(*v, len),
+            Self::Int64(Some(v)) => primitive::<Int64Type>(*v, len),
+            Self::UInt8(Some(v)) => primitive::<UInt8Type>(*v, len),
+            Self::UInt16(Some(v)) => primitive::<UInt16Type>(*v, len),
+            Self::UInt32(Some(v)) => primitive::<UInt32Type>(*v, len),
+            Self::UInt64(Some(v)) => primitive::<UInt64Type>(*v, len),
+            Self::Float32(Some(v)) => primitive::<Float32Type>(*v, len),
+            Self::Float64(Some(v)) => primitive::<Float64Type>(*v, len),
+            Self::Utf8(Some(v)) => Arc::new(StringArray::from_iter_values(repeat_n(v, len))),
+            Self::Binary(Some(v)) => Arc::new(BinaryArray::from_iter_values(repeat_n(v, len))),
+            _ => new_null_array(&self.dtype().into(), len),
+        }
+    }
+
     /// Returns the data type of this scalar.
     pub fn dtype(&self) -> DataType {
         match self {
@@ -119,6 +171,14 @@ impl Column {
         }
     }
 
+    /// Returns this column as an Arrow array, materializing literals.
+    pub fn to_array(&self) -> ArrayRef {
+        match self {
+            Self::Array(arr) => arr.clone(),
+            Self::Literal { value, len } => value.to_array(*len),
+        }
+    }
+
     /// Returns the value at the given index as a Scalar.
     ///
     /// # Panics
@@ -174,3 +234,43 @@ impl Column {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scalar_ordering() {
+        assert!(Scalar::Int64(Some(1)) < Scalar::Int64(Some(2)));
+        assert!(Scalar::Int64(None) < Scalar::Int64(Some(i64::MIN)));
+        assert!(Scalar::Utf8(Some("a".to_string())) < Scalar::Utf8(Some("b".to_string())));
+        assert_eq!(
+            Scalar::Int64(Some(1)).partial_cmp(&Scalar::Int32(Some(2))),
+            None
+        );
+        assert_eq!(Scalar::Int8(None).partial_cmp(&Scalar::Utf8(None)), None);
+    }
+
+    #[test]
+    fn test_literal_to_array() {
+        let column = Column::from_literal(Scalar::Utf8(Some("x".to_string())), 3);
+        let array = column.to_array();
+
+        assert_eq!(array.len(), 3);
+        assert_eq!(array.null_count(), 0);
+        assert_eq!(
+            Column::Array(array).get(2),
+            Scalar::Utf8(Some("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_null_literal_to_array() {
+        let column = Column::from_literal(Scalar::Int64(None), 2);
+        let array = column.to_array();
+
+        assert_eq!(array.len(), 2);
+        assert_eq!(array.null_count(), 2);
+        assert_eq!(Column::Array(array).dtype(), DataType::Int64);
+    }
+}
@@ -1,6 +1,9 @@
 //! Record batch type for the DBMS query engine.
 
-use arrow::record_batch::RecordBatch as ArrowRecordBatch;
+use std::sync::Arc;
+
+use arrow::datatypes::Schema as ArrowSchema;
+use arrow::record_batch::{RecordBatch as ArrowRecordBatch, RecordBatchOptions};
 
 use crate::{Column, Schema};
 
@@ -49,3 +52,63 @@ impl TryFrom<ArrowRecordBatch> for RecordBatch {
         Ok(Self::new(schema, columns?))
     }
 }
+
+impl TryFrom<RecordBatch> for ArrowRecordBatch {
+    type Error = String;
+
+    fn try_from(batch: RecordBatch) -> Result<Self, Self::Error> {
+        let row_count = batch.row_count();
+        let schema: ArrowSchema = batch.schema.into();
+        let columns = batch.columns.iter().map(Column::to_array).collect();
+        // The row count must be explicit so that batches without columns keep their rows
+        let options = RecordBatchOptions::new().with_row_count(Some(row_count));
+        ArrowRecordBatch::try_new_with_options(Arc::new(schema), columns, &options)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, Field, Scalar};
+
+    #[test]
+    fn test_arrow_round_trip() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::Utf8),
+        ]);
+        let batch = RecordBatch::new(
+            schema.clone(),
+            vec![
+                Column::from_literal(Scalar::Int64(Some(7)), 2),
+                Column::from_literal(Scalar::Utf8(None), 2),
+            ],
+        );
+
+        let arrow_batch = ArrowRecordBatch::try_from(batch).unwrap();
+        assert_eq!(arrow_batch.num_rows(), 2);
+
+        let back = RecordBatch::try_from(arrow_batch).unwrap();
+        assert_eq!(back.schema(), &schema);
+        assert_eq!(back.field(0).get(1), Scalar::Int64(Some(7)));
+        assert_eq!(back.field(1).get(0), Scalar::Utf8(None));
+    }
+
+    #[test]
+    fn test_arrow_length_mismatch() {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64),
+            Field::new("b", DataType::Int64),
+        ]);
+        let batch = RecordBatch::new(
+            schema,
+            vec![
+                Column::from_literal(Scalar::Int64(Some(1)), 2),
+                Column::from_literal(Scalar::Int64(Some(1)), 3),
+            ],
+        );
+
+        assert!(ArrowRecordBatch::try_from(batch).is_err());
+    }
+}